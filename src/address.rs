@@ -0,0 +1,136 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Addresses of the wallet.
+//!
+//! An address is the Base58 encoding of a public key. It can optionally be
+//! checksummed following CB58, appending the last four bytes of the SHA-256
+//! of the key before encoding, so that a mistyped character is caught before
+//! any funds are sent to it. Both forms are accepted wherever an address is
+//! expected.
+
+use alloc::string::String;
+
+use dusk_bytes::Serializable;
+use phoenix_core::PublicKey;
+
+/// The length of the checksum of a checksummed address.
+pub const CHECKSUM_LEN: usize = 4;
+
+/// The reason an address is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressError {
+    /// The character at the given byte position is not in the Base58
+    /// alphabet.
+    Alphabet(usize),
+    /// The address decodes to the given number of bytes, which is neither
+    /// the length of a plain nor of a checksummed address.
+    Length(usize),
+    /// The checksum doesn't match the key.
+    Checksum,
+    /// The key is not a valid point of the curve.
+    Point,
+}
+
+impl AddressError {
+    /// Returns the name of the reason, as reported by
+    /// [`validate_address`](crate::ffi::validate_address).
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            Self::Alphabet(_) => "alphabet",
+            Self::Length(_) => "length",
+            Self::Checksum => "checksum",
+            Self::Point => "point",
+        }
+    }
+
+    /// Returns the position of the offending character, if any.
+    pub const fn position(&self) -> Option<usize> {
+        match self {
+            Self::Alphabet(position) => Some(*position),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes the public key into an address, checksummed if asked to.
+pub fn encode(pk: &PublicKey, checksum: bool) -> String {
+    let encoder = bs58::encode(pk.to_bytes());
+
+    match checksum {
+        true => encoder.as_cb58(None).into_string(),
+        false => encoder.into_string(),
+    }
+}
+
+/// Decodes an address, either plain or checksummed, returning the public key
+/// and whether the address carried a checksum.
+pub fn decode(address: &str) -> Result<(PublicKey, bool), AddressError> {
+    let bytes = bs58::decode(address).into_vec().map_err(|e| match e {
+        bs58::decode::Error::InvalidCharacter { index, .. }
+        | bs58::decode::Error::NonAsciiCharacter { index } => {
+            AddressError::Alphabet(index)
+        }
+        _ => AddressError::Length(0),
+    })?;
+
+    let checksummed = match bytes.len() {
+        PublicKey::SIZE => false,
+        len if len == PublicKey::SIZE + CHECKSUM_LEN => true,
+        len => return Err(AddressError::Length(len)),
+    };
+
+    if checksummed {
+        bs58::decode(address)
+            .as_cb58(None)
+            .into_vec()
+            .map_err(|_| AddressError::Checksum)?;
+    }
+
+    let mut key = [0u8; PublicKey::SIZE];
+    key.copy_from_slice(&bytes[..PublicKey::SIZE]);
+
+    let pk = PublicKey::from_bytes(&key).map_err(|_| AddressError::Point)?;
+
+    Ok((pk, checksummed))
+}
+
+#[test]
+fn address_works() {
+    use dusk_jubjub::JubJubScalar;
+    use phoenix_core::SecretKey;
+
+    let sk = SecretKey::new(
+        JubJubScalar::from(42u64),
+        JubJubScalar::from(0xbeefu64),
+    );
+    let pk = PublicKey::from(&sk);
+
+    let plain = encode(&pk, false);
+    let checked = encode(&pk, true);
+
+    assert_eq!(decode(&plain), Ok((pk, false)));
+    assert_eq!(decode(&checked), Ok((pk, true)));
+
+    // `0` is not in the alphabet
+    let mut typo = plain.clone();
+    typo.replace_range(5..6, "0");
+    assert_eq!(decode(&typo), Err(AddressError::Alphabet(5)));
+
+    assert_eq!(decode(&plain[1..]).map_err(|e| e.reason()), Err("length"));
+
+    // a single changed character is caught by the checksum
+    let mut chars: alloc::vec::Vec<char> = checked.chars().collect();
+    chars[10] = if chars[10] == 'a' { 'b' } else { 'a' };
+    let typo: String = chars.into_iter().collect();
+    assert_eq!(decode(&typo), Err(AddressError::Checksum));
+
+    // a coordinate out of the field is not a valid key
+    let mut bytes = pk.to_bytes();
+    bytes[..32].copy_from_slice(&[0xff; 32]);
+    let invalid = bs58::encode(bytes).into_string();
+    assert_eq!(decode(&invalid), Err(AddressError::Point));
+}
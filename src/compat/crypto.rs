@@ -4,13 +4,10 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use core::mem::size_of;
-
 use dusk_bls12_381::BlsScalar;
 use dusk_bytes::Serializable;
 use phoenix_core::{
-    transaction::{ArchivedTreeLeaf, TreeLeaf},
-    Note, PublicKey, SecretKey, ViewKey,
+    transaction::TreeLeaf, Note, PublicKey, SecretKey, ViewKey,
 };
 
 use alloc::{string::ToString, vec::Vec};
@@ -18,77 +15,125 @@ use alloc::{string::ToString, vec::Vec};
 use crate::alloc::borrow::ToOwned;
 use crate::{
     key::{self},
+    memo,
+    sync::{self, NoteSync},
     types::{self},
     utils::{self},
-    MAX_KEY, MAX_LEN,
+    WalletError, GAP_LIMIT, MAX_ACCOUNTS, MAX_KEY, MAX_LEN, RNG_SEED,
 };
 
-const TREE_LEAF_SIZE: usize = size_of::<ArchivedTreeLeaf>();
-
 /// Returns true or false if the note is owned by the index
 /// if its true then nullifier of that note if sent with it
+///
+/// The arguments are the seed followed by the rkyv serialized leaves. Only
+/// the keys of the first [`MAX_KEY`] accounts are checked, see
+/// [check_accounts_ownership] to check more.
 #[no_mangle]
 pub fn check_note_ownership(args: i32, len: i32) -> i64 {
     // SAFETY: We assume the caller has passed a valid pointer and len as the
     // function arguments else we might get undefined behavior
     let args = unsafe { core::slice::from_raw_parts(args as _, len as _) };
 
-    let (seed, leaves) = args.split_at(64);
+    if args.len() < RNG_SEED {
+        return utils::fail(WalletError::InvalidLength("seed"));
+    }
+
+    let (seed, leaves) = args.split_at(RNG_SEED);
 
     let seed = match seed.try_into() {
         Ok(s) => s,
-        Err(_) => return utils::fail(),
+        Err(_) => return utils::fail(WalletError::InvalidLength("seed")),
     };
 
-    let mut leaf_chunk = leaves.chunks_exact(TREE_LEAF_SIZE);
+    ownership(seed, MAX_KEY, leaves)
+}
+
+/// Returns the notes owned by the keys of the given number of accounts, as
+/// [check_note_ownership] does for the first [`MAX_KEY`].
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::CheckAccountsOwnershipArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::CheckNoteOwnershipResponse].
+#[no_mangle]
+pub fn check_accounts_ownership(args: i32, len: i32) -> i64 {
+    let types::CheckAccountsOwnershipArgs {
+        account_count,
+        leaves,
+        seed,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let seed = match utils::sanitize_seed(seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    let account_count = match utils::sanitize_account_count(account_count) {
+        Some(c) => c,
+        None => return utils::fail(WalletError::InvalidValue("account_count")),
+    };
+
+    ownership(&seed, account_count, &leaves)
+}
+
+/// Checks the ownership of the serialized leaves against the keys of the
+/// first `account_count` accounts.
+fn ownership(
+    seed: &[u8; RNG_SEED],
+    account_count: usize,
+    leaves: &[u8],
+) -> i64 {
     let mut last_pos = 0;
 
     let mut notes = Vec::new();
     let mut nullifiers = Vec::new();
     let mut block_heights = Vec::new();
     let mut public_spend_keys = Vec::new();
-    let view_keys: [ViewKey; MAX_KEY] =
-        core::array::from_fn(|i| key::derive_vk(&seed, i as _));
-    let secret_keys: [SecretKey; MAX_KEY] =
-        core::array::from_fn(|i| key::derive_sk(&seed, i as _));
+    let mut ref_ids = Vec::new();
+    let secret_keys: Vec<SecretKey> = (0..account_count)
+        .map(|i| key::derive_sk(seed, i as _))
+        .collect();
+    let view_keys: Vec<ViewKey> =
+        secret_keys.iter().map(ViewKey::from).collect();
 
-    for leaf_bytes in leaf_chunk.by_ref() {
-        let TreeLeaf { block_height, note } = match rkyv::from_bytes(leaf_bytes)
-        {
-            Ok(a) => a,
-            Err(_) => {
-                return utils::fail();
-            }
-        };
+    let leaves = match sync::leaves(leaves) {
+        Ok(l) => l,
+        Err(e) => return utils::fail(e),
+    };
 
+    for TreeLeaf { block_height, note } in leaves {
         last_pos = core::cmp::max(last_pos, *note.pos());
 
-        for idx in 0..MAX_KEY {
-            if view_keys[idx].owns(&note) {
-                let sk = secret_keys[idx];
-                let nullifier = note.gen_nullifier(&sk);
-
-                let nullifier_found =
-                    match rkyv::to_bytes::<BlsScalar, MAX_LEN>(&nullifier).ok()
-                    {
-                        Some(n) => n.to_vec(),
-                        None => return utils::fail(),
-                    };
-
-                let psk_found =
-                    bs58::encode(PublicKey::from(sk).to_bytes()).into_string();
-
-                let raw_note: Vec<u8> =
-                    match rkyv::to_bytes::<Note, MAX_LEN>(&note) {
-                        Ok(n) => n.to_vec(),
-                        Err(_) => return utils::fail(),
-                    };
-
-                notes.push(raw_note.to_owned());
-                block_heights.push(block_height);
-                public_spend_keys.push(psk_found);
-                nullifiers.push(nullifier_found);
-            }
+        if let Some(idx) = key::owner_index(&view_keys, &note) {
+            let sk = secret_keys[idx];
+            let nullifier = note.gen_nullifier(&sk);
+
+            let nullifier_found =
+                match rkyv::to_bytes::<BlsScalar, MAX_LEN>(&nullifier).ok() {
+                    Some(n) => n.to_vec(),
+                    None => {
+                        return utils::fail(WalletError::Encode("nullifiers"))
+                    }
+                };
+
+            let psk_found =
+                bs58::encode(PublicKey::from(sk).to_bytes()).into_string();
+
+            let raw_note: Vec<u8> = match rkyv::to_bytes::<Note, MAX_LEN>(&note)
+            {
+                Ok(n) => n.to_vec(),
+                Err(_) => return utils::fail(WalletError::Encode("notes")),
+            };
+
+            notes.push(raw_note.to_owned());
+            block_heights.push(block_height);
+            public_spend_keys.push(psk_found);
+            nullifiers.push(nullifier_found);
+            ref_ids.push(memo::ref_id(&view_keys[idx], &note));
         }
     }
 
@@ -104,6 +149,270 @@ pub fn check_note_ownership(args: i32, len: i32) -> i64 {
         public_spend_keys,
         nullifiers,
         last_pos,
+        ref_ids,
+    })
+}
+
+/// Returns the notes owned by any of the given view keys, along with the
+/// public spend keys they belong to. Unlike [check_note_ownership] it needs no
+/// seed, hence returns no nullifiers.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::WatchNoteOwnershipArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::WatchNoteOwnershipResponse].
+#[no_mangle]
+pub fn watch_note_ownership(args: i32, len: i32) -> i64 {
+    let types::WatchNoteOwnershipArgs { leaves, view_keys } =
+        match utils::take_args(args, len) {
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
+        };
+
+    let view_keys = match utils::sanitize_view_keys(&view_keys) {
+        Some(k) => k,
+        None => return utils::fail(WalletError::Decode("view_keys")),
+    };
+
+    let leaves = match sync::leaves(&leaves) {
+        Ok(l) => l,
+        Err(e) => return utils::fail(e),
+    };
+
+    let mut last_pos = 0;
+
+    let mut notes = Vec::new();
+    let mut block_heights = Vec::new();
+    let mut public_spend_keys = Vec::new();
+    let mut ref_ids = Vec::new();
+
+    for TreeLeaf { block_height, note } in leaves {
+        last_pos = core::cmp::max(last_pos, *note.pos());
+
+        if let Some(idx) = key::owner_index(&view_keys, &note) {
+            let psk_found =
+                bs58::encode(PublicKey::from(&view_keys[idx]).to_bytes())
+                    .into_string();
+
+            let raw_note: Vec<u8> = match rkyv::to_bytes::<Note, MAX_LEN>(&note)
+            {
+                Ok(n) => n.to_vec(),
+                Err(_) => return utils::fail(WalletError::Encode("notes")),
+            };
+
+            notes.push(raw_note);
+            block_heights.push(block_height);
+            public_spend_keys.push(psk_found);
+            ref_ids.push(memo::ref_id(&view_keys[idx], &note));
+        }
+    }
+
+    let block_heights = block_heights
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    utils::into_ptr(types::WatchNoteOwnershipResponse {
+        notes,
+        block_heights,
+        public_spend_keys,
+        last_pos,
+        ref_ids,
+    })
+}
+
+/// Discovers the accounts of the wallet in use, scanning the given tree
+/// leaves.
+///
+/// Starting from the given account count, the keys are derived one index at a
+/// time until `gap_limit` consecutive accounts own none of the leaves. The
+/// host is expected to call this function for every batch of leaves streamed
+/// from the node, passing along the account count of the previous response.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::DiscoverAccountsArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::DiscoverAccountsResponse].
+#[no_mangle]
+pub fn discover_accounts(args: i32, len: i32) -> i64 {
+    let types::DiscoverAccountsArgs {
+        account_count,
+        gap_limit,
+        leaves,
+        seed,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let seed = match utils::sanitize_seed(seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    let gap_limit = match gap_limit.unwrap_or(GAP_LIMIT) {
+        0 => return utils::fail(WalletError::InvalidValue("gap_limit")),
+        g => g,
+    };
+
+    let notes: Vec<Note> = match sync::leaves(&leaves) {
+        Ok(l) => l.into_iter().map(|leaf| leaf.note).collect(),
+        Err(e) => return utils::fail(e),
+    };
+
+    let last_pos = notes.iter().map(|note| *note.pos()).max().unwrap_or(0);
+
+    let mut account_count = account_count.unwrap_or(0);
+    let mut indexes = Vec::new();
+    let mut index = 0;
+    let mut gap = 0;
+
+    // the known accounts are always scanned, then the scan carries on until
+    // the gap limit is reached
+    while index < account_count || gap < gap_limit {
+        if index >= MAX_ACCOUNTS as u64 {
+            break;
+        }
+
+        let vk = key::derive_vk(&seed, index);
+
+        if notes.iter().any(|note| vk.owns(note)) {
+            indexes.push(index);
+            account_count = core::cmp::max(account_count, index + 1);
+            gap = 0;
+        } else if index >= account_count {
+            gap += 1;
+        }
+
+        index += 1;
+    }
+
+    utils::into_ptr(types::DiscoverAccountsResponse {
+        account_count,
+        indexes,
+        last_pos,
+    })
+}
+
+/// Synchronizes the notes of the wallet incrementally, from a checkpoint.
+///
+/// The host is expected to fetch the leaves starting at the `next_pos` of the
+/// previous response, and to persist the returned checkpoint. The nullifiers
+/// of the unspent notes are returned to be checked against the node, the
+/// existing ones being passed along in the next call.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::SyncNotesArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::SyncNotesResponse].
+#[no_mangle]
+pub fn sync_notes(args: i32, len: i32) -> i64 {
+    let types::SyncNotesArgs {
+        account_count,
+        checkpoint,
+        existing_nullifiers,
+        leaves,
+        rollback_height,
+        seed,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let seed = match utils::sanitize_seed(seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    let account_count = match utils::sanitize_account_count(account_count) {
+        Some(c) => c,
+        None => return utils::fail(WalletError::InvalidValue("account_count")),
+    };
+
+    let mut state = match checkpoint {
+        Some(c) => match NoteSync::from_checkpoint(&c) {
+            Ok(s) => s,
+            Err(e) => return utils::fail(e),
+        },
+        None => NoteSync::new(account_count),
+    };
+
+    let leaves = match sync::leaves(&leaves) {
+        Ok(l) => l,
+        Err(e) => return utils::fail(e),
+    };
+
+    if let Some(height) = rollback_height {
+        state.rollback(height);
+    }
+
+    // the leaves start at the cursor of the checkpoint, so they are dropped
+    // when it is moved back to scan for the notes of added accounts
+    if !state.add_accounts(account_count) {
+        state.sync(&seed, leaves);
+    }
+
+    if let Some(existing) = existing_nullifiers {
+        match rkyv::from_bytes::<Vec<BlsScalar>>(&existing) {
+            Ok(n) => state.spend(&n),
+            Err(_) => {
+                return utils::fail(WalletError::Decode("existing_nullifiers"))
+            }
+        };
+    }
+
+    let mut unspent_notes = Vec::new();
+
+    for owned in state.unspent_notes() {
+        let note = match rkyv::to_bytes::<Note, MAX_LEN>(&owned.note) {
+            Ok(n) => n.to_vec(),
+            Err(_) => return utils::fail(WalletError::Encode("notes")),
+        };
+
+        let nullifier =
+            match rkyv::to_bytes::<BlsScalar, MAX_LEN>(&owned.nullifier) {
+                Ok(n) => n.to_vec(),
+                Err(_) => {
+                    return utils::fail(WalletError::Encode("nullifiers"))
+                }
+            };
+
+        let pk = key::derive_pk(&seed, owned.index);
+
+        unspent_notes.push(types::NoteInfoType {
+            block_height: owned.block_height,
+            note,
+            nullifier,
+            pk: bs58::encode(pk.to_bytes()).into_string(),
+            pos: *owned.note.pos(),
+            ref_id: memo::ref_id(
+                &key::derive_vk(&seed, owned.index),
+                &owned.note,
+            ),
+        });
+    }
+
+    let nullifiers =
+        match rkyv::to_bytes::<_, MAX_LEN>(&state.unspent_nullifiers()) {
+            Ok(n) => n.to_vec(),
+            Err(_) => return utils::fail(WalletError::Encode("nullifiers")),
+        };
+
+    let checkpoint = match state.checkpoint() {
+        Ok(c) => c,
+        Err(e) => return utils::fail(e),
+    };
+
+    utils::into_ptr(types::SyncNotesResponse {
+        block_height: state.block_height(),
+        checkpoint,
+        next_pos: state.next_pos(),
+        nullifiers,
+        unspent_notes,
     })
 }
 
@@ -118,14 +427,16 @@ pub fn unspent_spent_notes(args: i32, len: i32) -> i64 {
         existing_nullifiers,
         pks,
     } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let existing_nullifiers =
         match rkyv::from_bytes::<Vec<BlsScalar>>(&existing_nullifiers).ok() {
             Some(a) => a,
-            None => return utils::fail(),
+            None => {
+                return utils::fail(WalletError::Decode("existing_nullifiers"))
+            }
         };
 
     let mut spent_notes = Vec::new();
@@ -139,18 +450,23 @@ pub fn unspent_spent_notes(args: i32, len: i32) -> i64 {
     {
         let parsed_note: Note = match rkyv::from_bytes::<Note>(&note).ok() {
             Some(a) => a,
-            None => return utils::fail(),
+            None => return utils::fail(WalletError::Decode("notes")),
         };
 
-        let parsed_nullifier =
-            match rkyv::from_bytes::<BlsScalar>(&nullifier).ok() {
-                Some(a) => a,
-                None => return utils::fail(),
-            };
+        let parsed_nullifier = match rkyv::from_bytes::<BlsScalar>(&nullifier)
+            .ok()
+        {
+            Some(a) => a,
+            None => {
+                return utils::fail(WalletError::Decode("nullifiers_of_notes"))
+            }
+        };
 
         let block_height = match block_heights.get(index) {
             Some(a) => *a as u64,
-            None => return utils::fail(),
+            None => {
+                return utils::fail(WalletError::InvalidLength("block_heights"))
+            }
         };
 
         if existing_nullifiers.contains(&parsed_nullifier) {
@@ -160,6 +476,7 @@ pub fn unspent_spent_notes(args: i32, len: i32) -> i64 {
                 block_height,
                 note,
                 nullifier,
+                ref_id: None,
             });
         } else {
             unspent_notes.push(types::NoteInfoType {
@@ -168,6 +485,7 @@ pub fn unspent_spent_notes(args: i32, len: i32) -> i64 {
                 block_height,
                 pk,
                 nullifier,
+                ref_id: None,
             });
         }
     }
@@ -182,8 +500,8 @@ pub fn unspent_spent_notes(args: i32, len: i32) -> i64 {
 #[no_mangle]
 fn dusk_to_lux(args: i32, len: i32) -> i64 {
     let types::DuskToLuxArgs { dusk } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     utils::into_ptr(types::DuskToLuxResponse {
@@ -196,8 +514,8 @@ fn dusk_to_lux(args: i32, len: i32) -> i64 {
 fn lux_to_dusk(args: i32, len: i32) -> i64 {
     // reusing the type from above, two less type definitions
     let types::DuskToLuxResponse { lux } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     utils::into_ptr(types::DuskToLuxArgs {
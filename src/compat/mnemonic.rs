@@ -4,15 +4,28 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use bip39::Mnemonic;
+use bip39::{Language, Mnemonic};
 
 use crate::{
     types,
-    types::{GetMnemonicSeedArgs, MnemonicNewArgs},
-    utils,
+    types::{
+        GetMnemonicSeedArgs, MnemonicLanguage, MnemonicNewArgs,
+        MnemonicSuggestionsArgs, ValidateMnemonicArgs,
+    },
+    utils, WalletError, RNG_SEED,
 };
 
-use alloc::string::ToString;
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// The number of words a mnemonic can have.
+pub const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// The maximum number of words suggested for a prefix.
+pub const MAX_SUGGESTIONS: usize = 8;
 
 /// Create a new mnemonic randomized on the seed bytes provided
 /// Its the host's job to provide a crypto
@@ -20,20 +33,38 @@ use alloc::string::ToString;
 /// in no_std
 #[no_mangle]
 pub fn new_mnemonic(args: i32, len: i32) -> i64 {
-    let MnemonicNewArgs { rng_seed } = match utils::take_args(args, len) {
-        Some(val) => val,
-        None => return utils::fail(),
+    let MnemonicNewArgs {
+        language,
+        rng_seed,
+        word_count,
+    } = match utils::take_args(args, len) {
+        Ok(val) => val,
+        Err(e) => return utils::fail(e),
     };
 
     // check if we our seed is secure
     let bytes_check: [u8; 32] = match rng_seed.try_into().ok() {
         Some(bytes) => bytes,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("rng_seed")),
     };
 
-    let mnemonic = match Mnemonic::from_entropy(&bytes_check).ok() {
-        Some(m) => m,
-        None => return utils::fail(),
+    let word_count = word_count.unwrap_or(24) as usize;
+    if !WORD_COUNTS.contains(&word_count) {
+        return utils::fail(WalletError::InvalidValue("word_count"));
+    }
+
+    // every three words encode four bytes of entropy
+    let entropy = &bytes_check[..word_count / 3 * 4];
+
+    let language =
+        match to_language(language.unwrap_or(MnemonicLanguage::English)) {
+            Ok(l) => l,
+            Err(e) => return utils::fail(e),
+        };
+
+    let mnemonic = match Mnemonic::from_entropy_in(language, entropy) {
+        Ok(m) => m,
+        Err(_) => return utils::fail(WalletError::InvalidLength("rng_seed")),
     };
 
     utils::into_ptr(types::MnewmonicNewResponse {
@@ -41,26 +72,326 @@ pub fn new_mnemonic(args: i32, len: i32) -> i64 {
     })
 }
 
-/// Get the wallet seed bytes [u8; 64] from the given normalized
-/// passphrase and Mnemomnic
+/// Get the wallet seed bytes [u8; 64] from the given passphrase and
+/// Mnemomnic
 #[no_mangle]
 pub fn get_mnemonic_seed(args: i32, len: i32) -> i64 {
     let GetMnemonicSeedArgs {
+        language,
         mnemonic,
         passphrase,
     } = match utils::take_args(args, len) {
-        Some(val) => val,
-        None => return utils::fail(),
+        Ok(val) => val,
+        Err(e) => return utils::fail(e),
     };
 
-    let mnemonic = match Mnemonic::parse_normalized(&mnemonic).ok() {
-        Some(m) => m,
-        None => return utils::fail(),
+    let seed = match language.map(to_language).transpose() {
+        Ok(Some(l)) => mnemonic_seed_in(l, &mnemonic, &passphrase),
+        Ok(None) => mnemonic_seed(&mnemonic, &passphrase),
+        Err(e) => return utils::fail(e),
     };
 
-    let seed = mnemonic.to_seed_normalized(&passphrase).to_vec();
+    let seed = match seed {
+        Ok(s) => s.to_vec(),
+        Err(e) => return utils::fail(e),
+    };
 
     utils::into_ptr(types::GetMnemonicSeedResponse {
         mnemonic_seed: seed,
     })
 }
+
+/// Validates a mnemonic, possibly still being typed, reporting the position
+/// of every unknown word along with suggestions to correct it.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::ValidateMnemonicArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::ValidateMnemonicResponse].
+#[no_mangle]
+pub fn validate_mnemonic(args: i32, len: i32) -> i64 {
+    let ValidateMnemonicArgs { language, mnemonic } =
+        match utils::take_args(args, len) {
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
+        };
+
+    let language = match language.map(to_language).transpose() {
+        Ok(l) => l,
+        Err(e) => return utils::fail(e),
+    };
+
+    utils::into_ptr(validate(&mnemonic, language))
+}
+
+/// Returns the words of the word list starting with the given prefix, to
+/// autocomplete a word as it is typed.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::MnemonicSuggestionsArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::MnemonicSuggestionsResponse].
+#[no_mangle]
+pub fn mnemonic_suggestions(args: i32, len: i32) -> i64 {
+    let MnemonicSuggestionsArgs { language, prefix } =
+        match utils::take_args(args, len) {
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
+        };
+
+    let language =
+        match to_language(language.unwrap_or(MnemonicLanguage::English)) {
+            Ok(l) => l,
+            Err(e) => return utils::fail(e),
+        };
+
+    utils::into_ptr(types::MnemonicSuggestionsResponse {
+        words: suggestions(language, &prefix),
+    })
+}
+
+/// Computes the wallet seed from a mnemonic and passphrase, as specified by
+/// BIP-39. Both are normalized to NFKD first, and the language of the
+/// mnemonic is detected from its words.
+pub fn mnemonic_seed(
+    mnemonic: &str,
+    passphrase: &str,
+) -> Result<[u8; RNG_SEED], WalletError> {
+    let mnemonic = Mnemonic::parse(mnemonic)
+        .map_err(|_| WalletError::Decode("mnemonic"))?;
+
+    Ok(mnemonic.to_seed(passphrase))
+}
+
+/// Computes the wallet seed from a mnemonic in the given language and
+/// passphrase, as specified by BIP-39. Both are normalized to NFKD first.
+pub fn mnemonic_seed_in(
+    language: Language,
+    mnemonic: &str,
+    passphrase: &str,
+) -> Result<[u8; RNG_SEED], WalletError> {
+    let mnemonic = Mnemonic::parse_in(language, mnemonic)
+        .map_err(|_| WalletError::Decode("mnemonic"))?;
+
+    Ok(mnemonic.to_seed(passphrase))
+}
+
+/// Validates a mnemonic, in the given language or, if none, in the language
+/// knowing most of its words. The mnemonic is normalized to NFKD first, as
+/// the word lists are.
+pub fn validate(
+    mnemonic: &str,
+    language: Option<Language>,
+) -> types::ValidateMnemonicResponse {
+    let mnemonic = normalize(mnemonic);
+    let mnemonic = mnemonic.as_ref();
+
+    let language = language.unwrap_or_else(|| detect_language(mnemonic));
+
+    let invalid_words: Vec<_> = mnemonic
+        .split_whitespace()
+        .enumerate()
+        .filter(|(_, word)| language.find_word(word).is_none())
+        .map(|(position, word)| types::MnemonicWordType {
+            position: position as u64,
+            suggestions: corrections(language, word),
+            word: word.to_string(),
+        })
+        .collect();
+
+    let word_count = mnemonic.split_whitespace().count();
+
+    let reason = if !invalid_words.is_empty() {
+        Some("unknown_word")
+    } else if !WORD_COUNTS.contains(&word_count) {
+        Some("word_count")
+    } else if Mnemonic::parse_in_normalized(language, mnemonic).is_err() {
+        Some("checksum")
+    } else {
+        None
+    };
+
+    types::ValidateMnemonicResponse {
+        invalid_words,
+        language: from_language(language),
+        reason: reason.map(ToString::to_string),
+        valid: reason.is_none(),
+        word_count: word_count as u64,
+    }
+}
+
+/// Returns up to [`MAX_SUGGESTIONS`] words of the word list starting with
+/// `prefix`, once normalized to NFKD.
+pub fn suggestions(language: Language, prefix: &str) -> Vec<String> {
+    if prefix.is_empty() {
+        return Vec::new();
+    }
+
+    language
+        .words_by_prefix(&normalize(prefix))
+        .iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|w| w.to_string())
+        .collect()
+}
+
+/// Suggests the words sharing the longest prefix with an unknown word.
+fn corrections(language: Language, word: &str) -> Vec<String> {
+    (1..=word.len())
+        .rev()
+        .filter(|end| word.is_char_boundary(*end))
+        .map(|end| suggestions(language, &word[..end]))
+        .find(|words| !words.is_empty())
+        .unwrap_or_default()
+}
+
+/// Picks the language knowing most of the words of the mnemonic, preferring
+/// the earliest in [`Language::ALL`], English first, on a tie.
+fn detect_language(mnemonic: &str) -> Language {
+    Language::ALL
+        .iter()
+        .rev()
+        .copied()
+        .max_by_key(|language| {
+            mnemonic
+                .split_whitespace()
+                .filter(|word| language.find_word(word).is_some())
+                .count()
+        })
+        .unwrap_or_default()
+}
+
+/// Normalizes the input to NFKD, the form the word lists are stored in.
+fn normalize(input: &str) -> Cow<'_, str> {
+    let mut input = Cow::Borrowed(input);
+    Mnemonic::normalize_utf8_cow(&mut input);
+    input
+}
+
+/// Maps the language to its word list, failing for the languages other than
+/// English unless the `all-languages` feature is enabled.
+pub(crate) fn to_language(
+    language: MnemonicLanguage,
+) -> Result<Language, WalletError> {
+    Ok(match language {
+        MnemonicLanguage::English => Language::English,
+        #[cfg(feature = "all-languages")]
+        MnemonicLanguage::ChineseSimplified => Language::SimplifiedChinese,
+        #[cfg(feature = "all-languages")]
+        MnemonicLanguage::ChineseTraditional => Language::TraditionalChinese,
+        #[cfg(feature = "all-languages")]
+        MnemonicLanguage::Czech => Language::Czech,
+        #[cfg(feature = "all-languages")]
+        MnemonicLanguage::French => Language::French,
+        #[cfg(feature = "all-languages")]
+        MnemonicLanguage::Italian => Language::Italian,
+        #[cfg(feature = "all-languages")]
+        MnemonicLanguage::Japanese => Language::Japanese,
+        #[cfg(feature = "all-languages")]
+        MnemonicLanguage::Korean => Language::Korean,
+        #[cfg(feature = "all-languages")]
+        MnemonicLanguage::Portuguese => Language::Portuguese,
+        #[cfg(feature = "all-languages")]
+        MnemonicLanguage::Spanish => Language::Spanish,
+        #[cfg(not(feature = "all-languages"))]
+        _ => return Err(WalletError::InvalidValue("language")),
+    })
+}
+
+fn from_language(language: Language) -> MnemonicLanguage {
+    match language {
+        Language::English => MnemonicLanguage::English,
+        #[cfg(feature = "all-languages")]
+        Language::SimplifiedChinese => MnemonicLanguage::ChineseSimplified,
+        #[cfg(feature = "all-languages")]
+        Language::TraditionalChinese => MnemonicLanguage::ChineseTraditional,
+        #[cfg(feature = "all-languages")]
+        Language::Czech => MnemonicLanguage::Czech,
+        #[cfg(feature = "all-languages")]
+        Language::French => MnemonicLanguage::French,
+        #[cfg(feature = "all-languages")]
+        Language::Italian => MnemonicLanguage::Italian,
+        #[cfg(feature = "all-languages")]
+        Language::Japanese => MnemonicLanguage::Japanese,
+        #[cfg(feature = "all-languages")]
+        Language::Korean => MnemonicLanguage::Korean,
+        #[cfg(feature = "all-languages")]
+        Language::Portuguese => MnemonicLanguage::Portuguese,
+        #[cfg(feature = "all-languages")]
+        Language::Spanish => MnemonicLanguage::Spanish,
+    }
+}
+
+#[test]
+fn validate_works() {
+    let entropy = [0x42; 16];
+
+    for language in Language::ALL {
+        let mnemonic = Mnemonic::from_entropy_in(*language, &entropy)
+            .unwrap()
+            .to_string();
+
+        let response = validate(&mnemonic, None);
+        assert!(response.valid, "{language:?}");
+        assert_eq!(to_language(response.language), Ok(*language));
+        assert_eq!(response.word_count, 12);
+    }
+
+    let mnemonic = Mnemonic::from_entropy(&entropy).unwrap().to_string();
+    let mut words: Vec<&str> = mnemonic.split_whitespace().collect();
+
+    // a typo in the third word
+    words[2] = "abandom";
+    let response = validate(&words.join(" "), None);
+    assert!(!response.valid);
+    assert_eq!(response.reason.as_deref(), Some("unknown_word"));
+    assert_eq!(response.invalid_words.len(), 1);
+    assert_eq!(response.invalid_words[0].position, 2);
+    assert_eq!(response.invalid_words[0].suggestions, ["abandon"]);
+
+    // a known word breaking the checksum
+    words[2] = "zoo";
+    let response = validate(&words.join(" "), Some(Language::English));
+    assert_eq!(response.reason.as_deref(), Some("checksum"));
+
+    let response = validate(&words[..11].join(" "), None);
+    assert_eq!(response.reason.as_deref(), Some("word_count"));
+
+    assert_eq!(suggestions(Language::English, "zo"), ["zone", "zoo"]);
+    assert!(suggestions(Language::English, "").is_empty());
+}
+
+#[test]
+#[cfg(feature = "all-languages")]
+fn nfc_works() {
+    let entropy = [0x42; 16];
+
+    let mnemonic = Mnemonic::from_entropy_in(Language::French, &entropy)
+        .unwrap()
+        .to_string();
+    // the way a user types it, with precomposed accents
+    let nfc = mnemonic
+        .replace("e\u{301}", "\u{e9}")
+        .replace("e\u{300}", "\u{e8}");
+    assert_ne!(nfc, mnemonic);
+
+    let response = validate(&nfc, None);
+    assert!(response.valid);
+    assert_eq!(response.language, MnemonicLanguage::French);
+
+    assert_eq!(
+        mnemonic_seed_in(Language::French, &nfc, "").unwrap(),
+        mnemonic_seed_in(Language::French, &mnemonic, "").unwrap()
+    );
+    assert_eq!(
+        mnemonic_seed(&nfc, "").unwrap(),
+        mnemonic_seed(&mnemonic, "").unwrap()
+    );
+
+    assert_eq!(
+        suggestions(Language::French, "\u{e9}l\u{e8}v"),
+        ["e\u{301}le\u{300}ve"]
+    );
+}
@@ -11,6 +11,8 @@ pub mod mnemonic;
 /// Includes functions to rkyv serialize types like phoenix_core and crypto
 /// primitives
 pub mod rkyv;
+/// Includes functions to back up the seed in Shamir's secret shares
+pub mod shamir;
 /// Includes functions to interact with the stake contract
 pub mod stake;
 /// Includes functions to deal with UnprovenTransaction and Transaction
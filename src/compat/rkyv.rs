@@ -8,7 +8,7 @@ use crate::{
     key::{self},
     tx,
     types::{self},
-    utils, MAX_LEN,
+    utils, WalletError, MAX_LEN,
 };
 
 use bls12_381_bls::PublicKey as StakePublicKey;
@@ -21,8 +21,8 @@ use alloc::vec::Vec;
 #[no_mangle]
 pub fn rkyv_u64(args: i32, len: i32) -> i64 {
     let types::RkyvU64 { value } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     utils::rkyv_into_ptr(value)
@@ -33,8 +33,8 @@ pub fn rkyv_u64(args: i32, len: i32) -> i64 {
 #[no_mangle]
 pub fn rkyv_notes_array(args: i32, len: i32) -> i64 {
     let types::RkyvNotesArray { notes } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let mut vec_notes = Vec::with_capacity(notes.len());
@@ -42,7 +42,7 @@ pub fn rkyv_notes_array(args: i32, len: i32) -> i64 {
     for note in notes {
         let parsed_note: Note = match rkyv::from_bytes(&note).ok() {
             Some(t) => t,
-            None => return utils::fail(),
+            None => return utils::fail(WalletError::Decode("notes")),
         };
 
         vec_notes.push(parsed_note);
@@ -57,8 +57,8 @@ pub fn rkyv_bls_scalar_array(args: i32, len: i32) -> i64 {
     // we reuse this argument
     let types::RkyvBlsScalarArrayArgs { bytes } =
         match utils::take_args(args, len) {
-            Some(a) => a,
-            None => return utils::fail(),
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
         };
 
     let mut bls_scalars = Vec::new();
@@ -66,7 +66,7 @@ pub fn rkyv_bls_scalar_array(args: i32, len: i32) -> i64 {
     for scalar in bytes {
         match rkyv::from_bytes::<BlsScalar>(&scalar).ok() {
             Some(v) => bls_scalars.push(v),
-            None => return utils::fail(),
+            None => return utils::fail(WalletError::Decode("bytes")),
         }
     }
 
@@ -79,13 +79,13 @@ pub fn rkyv_bls_scalar_array(args: i32, len: i32) -> i64 {
 pub fn bls_scalar_array_rkyv(args: i32, len: i32) -> i64 {
     // reusing this type
     let types::RkyvTreeLeaf { bytes } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let scalars: Vec<BlsScalar> = match rkyv::from_bytes(&bytes).ok() {
         Some(n) => n,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::Decode("bytes")),
     };
 
     let mut scalar_array = Vec::new();
@@ -94,7 +94,7 @@ pub fn bls_scalar_array_rkyv(args: i32, len: i32) -> i64 {
         let serialized =
             match rkyv::to_bytes::<BlsScalar, MAX_LEN>(&scalar).ok() {
                 Some(n) => n.to_vec(),
-                None => return utils::fail(),
+                None => return utils::fail(WalletError::Encode("bytes")),
             };
 
         scalar_array.push(serialized);
@@ -111,8 +111,8 @@ pub fn bls_scalar_array_rkyv(args: i32, len: i32) -> i64 {
 pub fn rkyv_openings_array(args: i32, len: i32) -> i64 {
     let types::RkyvOpeningsArray { openings } =
         match utils::take_args(args, len) {
-            Some(a) => a,
-            None => return utils::fail(),
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
         };
 
     let mut openings_vec: Vec<(tx::Opening, u64)> = Vec::new();
@@ -121,7 +121,7 @@ pub fn rkyv_openings_array(args: i32, len: i32) -> i64 {
         let opening_parsed: tx::Opening =
             match rkyv::from_bytes(&opening.opening).ok() {
                 Some(x) => x,
-                None => return utils::fail(),
+                None => return utils::fail(WalletError::Decode("openings")),
             };
 
         openings_vec.push((opening_parsed, opening.pos));
@@ -136,13 +136,13 @@ pub fn rkyv_openings_array(args: i32, len: i32) -> i64 {
 fn get_stake_pk_rkyv_serialized(args: i32, len: i32) -> i64 {
     let types::GetStakePKrkyvSerializedArgs { seed, index } =
         match utils::take_args(args, len) {
-            Some(a) => a,
-            None => return utils::fail(),
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
         };
 
     let seed = match utils::sanitize_seed(seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("seed")),
     };
 
     let stake_sk = key::derive_stake_sk(&seed, index);
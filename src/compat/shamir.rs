@@ -0,0 +1,585 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Shamir's secret sharing of the wallet seed, in the style of SLIP-39.
+//!
+//! The secret, either the seed or the entropy of a mnemonic, is split in two
+//! levels: first into group shares, any `group_threshold` of which recover
+//! the secret, then each group share into member shares, any of the group's
+//! `threshold` of which recover the group share. Sharing is done byte-wise
+//! over GF(256), after appending a digest of the secret so that combining
+//! the wrong shares is detected.
+//!
+//! Every share is laid out as:
+//!
+//! | field            | size |
+//! |------------------|------|
+//! | identifier       | 2    |
+//! | group index      | 1    |
+//! | group threshold  | 1    |
+//! | group count      | 1    |
+//! | member index     | 1    |
+//! | member threshold | 1    |
+//! | value length     | 1    |
+//! | value            | ..   |
+//! | checksum         | 4    |
+//!
+//! where the checksum is the first four bytes of the SHA-256 of the rest, and
+//! is encoded as words of the English BIP-39 word list, eleven bits each.
+
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+
+use bip39::{Language, Mnemonic};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    compat::mnemonic::to_language,
+    types,
+    types::{MnemonicLanguage, ShamirCombineArgs, ShamirSplitArgs},
+    utils, WalletError, RNG_SEED,
+};
+
+/// The maximum number of groups, and of members of a group.
+pub const MAX_SHARES: usize = 16;
+
+/// The minimum length of a shared secret.
+pub const MIN_SECRET_LEN: usize = 16;
+
+const DIGEST_LEN: usize = 4;
+const CHECKSUM_LEN: usize = 4;
+const HEADER_LEN: usize = 8;
+const BITS_PER_WORD: usize = 11;
+
+/// Splits a seed, or the entropy of a mnemonic, into groups of word shares.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::ShamirSplitArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::ShamirSplitResponse].
+#[no_mangle]
+pub fn shamir_split(args: i32, len: i32) -> i64 {
+    let ShamirSplitArgs {
+        group_threshold,
+        groups,
+        rng_seed,
+        secret,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    // the secret must be usable as a seed, directly or through a mnemonic
+    if secret.len() != RNG_SEED && Mnemonic::from_entropy(&secret).is_err() {
+        return utils::fail(WalletError::InvalidLength("secret"));
+    }
+
+    let rng_seed = match utils::sanitize_rng_seed(rng_seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("rng_seed")),
+    };
+
+    let groups: Vec<_> = groups
+        .into_iter()
+        .map(|g| (g.threshold as usize, g.count as usize))
+        .collect();
+
+    let mut rng = utils::rng(rng_seed);
+
+    match split(&mut rng, &secret, group_threshold as usize, &groups) {
+        Ok(groups) => utils::into_ptr(types::ShamirSplitResponse {
+            groups: groups
+                .into_iter()
+                .map(|shares| types::ShamirSharesType { shares })
+                .collect(),
+        }),
+        Err(e) => utils::fail(e),
+    }
+}
+
+/// Combines word shares back into the secret, and the seed it stands for.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::ShamirCombineArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::ShamirCombineResponse].
+#[no_mangle]
+pub fn shamir_combine(args: i32, len: i32) -> i64 {
+    let ShamirCombineArgs {
+        language,
+        passphrase,
+        shares,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let language =
+        match to_language(language.unwrap_or(MnemonicLanguage::English)) {
+            Ok(l) => l,
+            Err(e) => return utils::fail(e),
+        };
+
+    let secret = match combine(&shares) {
+        Ok(s) => s,
+        Err(e) => return utils::fail(e),
+    };
+
+    let seed = match secret_seed(&secret, language, passphrase.as_deref()) {
+        Ok(s) => s.to_vec(),
+        Err(e) => return utils::fail(e),
+    };
+
+    utils::into_ptr(types::ShamirCombineResponse { secret, seed })
+}
+
+/// Returns the seed the combined secret stands for: the secret itself if it
+/// is a seed, or else the seed of the mnemonic in `language` it is the
+/// entropy of. A passphrase is only accepted along with the latter.
+pub fn secret_seed(
+    secret: &[u8],
+    language: Language,
+    passphrase: Option<&str>,
+) -> Result<[u8; RNG_SEED], WalletError> {
+    if let Ok(seed) = secret.try_into() {
+        return match passphrase {
+            Some(_) => Err(WalletError::InvalidValue("passphrase")),
+            None => Ok(seed),
+        };
+    }
+
+    let mnemonic = Mnemonic::from_entropy_in(language, secret)
+        .map_err(|_| WalletError::InvalidLength("secret"))?;
+
+    Ok(mnemonic.to_seed(passphrase.unwrap_or_default()))
+}
+
+/// Splits the secret into groups of word shares, any `group_threshold`
+/// groups being needed to recover it. Each group is given as its
+/// `(threshold, count)` of members.
+pub fn split<Rng: RngCore + CryptoRng>(
+    rng: &mut Rng,
+    secret: &[u8],
+    group_threshold: usize,
+    groups: &[(usize, usize)],
+) -> Result<Vec<Vec<String>>, WalletError> {
+    if secret.len() < MIN_SECRET_LEN || secret.len() > RNG_SEED {
+        return Err(WalletError::InvalidLength("secret"));
+    }
+    if groups.is_empty() || groups.len() > MAX_SHARES {
+        return Err(WalletError::InvalidValue("groups"));
+    }
+    if group_threshold == 0 || group_threshold > groups.len() {
+        return Err(WalletError::InvalidValue("group_threshold"));
+    }
+    if groups
+        .iter()
+        .any(|(t, n)| *t == 0 || t > n || *n > MAX_SHARES)
+    {
+        return Err(WalletError::InvalidValue("groups"));
+    }
+
+    let mut id = [0u8; 2];
+    rng.fill_bytes(&mut id);
+
+    let mut value = secret.to_vec();
+    value.extend_from_slice(&Sha256::digest(secret)[..DIGEST_LEN]);
+
+    let group_shares = split_bytes(rng, &value, group_threshold, groups.len());
+
+    let shares = groups
+        .iter()
+        .zip(group_shares)
+        .enumerate()
+        .map(|(group_index, (&(threshold, count), group_share))| {
+            split_bytes(rng, &group_share, threshold, count)
+                .into_iter()
+                .enumerate()
+                .map(|(member_index, member_share)| {
+                    let mut bytes = Vec::with_capacity(
+                        HEADER_LEN + member_share.len() + CHECKSUM_LEN,
+                    );
+                    bytes.extend_from_slice(&id);
+                    bytes.extend_from_slice(&[
+                        group_index as u8,
+                        group_threshold as u8,
+                        groups.len() as u8,
+                        member_index as u8,
+                        threshold as u8,
+                        member_share.len() as u8,
+                    ]);
+                    bytes.extend_from_slice(&member_share);
+                    let checksum = Sha256::digest(&bytes);
+                    bytes.extend_from_slice(&checksum[..CHECKSUM_LEN]);
+
+                    to_words(&bytes)
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(shares)
+}
+
+/// Combines word shares back into the secret.
+pub fn combine<S: AsRef<str>>(shares: &[S]) -> Result<Vec<u8>, WalletError> {
+    let shares = shares
+        .iter()
+        .map(|s| Share::from_words(s.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let first = shares.first().ok_or(WalletError::InvalidValue("shares"))?;
+
+    let mut groups = BTreeMap::<u8, BTreeMap<u8, &Share>>::new();
+    for share in &shares {
+        if share.id != first.id
+            || share.group_threshold != first.group_threshold
+            || share.group_count != first.group_count
+            || share.value.len() != first.value.len()
+        {
+            return Err(WalletError::InvalidValue("shares"));
+        }
+        groups
+            .entry(share.group_index)
+            .or_default()
+            .insert(share.member_index, share);
+    }
+
+    let mut group_shares = Vec::new();
+    for (group_index, members) in groups {
+        let threshold = members.values().next().map(|s| s.member_threshold);
+        if members
+            .values()
+            .any(|s| Some(s.member_threshold) != threshold)
+        {
+            return Err(WalletError::InvalidValue("shares"));
+        }
+
+        let threshold = threshold.unwrap_or_default() as usize;
+        if members.len() >= threshold {
+            let points: Vec<_> = members
+                .values()
+                .take(threshold)
+                .map(|s| (s.member_index + 1, &s.value[..]))
+                .collect();
+            group_shares.push((group_index + 1, interpolate(&points)));
+        }
+    }
+
+    let group_threshold = first.group_threshold as usize;
+    if group_shares.len() < group_threshold {
+        return Err(WalletError::InvalidValue("shares"));
+    }
+
+    let points: Vec<_> = group_shares
+        .iter()
+        .take(group_threshold)
+        .map(|(x, value)| (*x, &value[..]))
+        .collect();
+    let mut secret = interpolate(&points);
+
+    let digest = secret.split_off(secret.len() - DIGEST_LEN);
+    if digest[..] != Sha256::digest(&secret)[..DIGEST_LEN] {
+        return Err(WalletError::InvalidValue("shares"));
+    }
+
+    Ok(secret)
+}
+
+/// A decoded word share.
+struct Share {
+    id: [u8; 2],
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Vec<u8>,
+}
+
+impl Share {
+    fn from_words(words: &str) -> Result<Self, WalletError> {
+        let err = WalletError::Decode("shares");
+
+        let mut indices = Vec::new();
+        for word in words.split_whitespace() {
+            indices.push(Language::English.find_word(word).ok_or(err)?);
+        }
+
+        let mut bytes = vec![0u8; indices.len() * BITS_PER_WORD / 8];
+        for bit in 0..bytes.len() * 8 {
+            let index = indices[bit / BITS_PER_WORD];
+            if index >> (BITS_PER_WORD - 1 - bit % BITS_PER_WORD) & 1 == 1 {
+                bytes[bit / 8] |= 0x80 >> (bit % 8);
+            }
+        }
+
+        if bytes.len() < HEADER_LEN {
+            return Err(err);
+        }
+
+        let len = HEADER_LEN + bytes[HEADER_LEN - 1] as usize + CHECKSUM_LEN;
+        if len > bytes.len() || words_len(len) != indices.len() {
+            return Err(err);
+        }
+
+        let (payload, checksum) = bytes[..len].split_at(len - CHECKSUM_LEN);
+        if checksum != &Sha256::digest(payload)[..CHECKSUM_LEN] {
+            return Err(err);
+        }
+
+        let group_count = payload[4];
+        let group_index = payload[2];
+        let member_index = payload[5];
+        let member_threshold = payload[6];
+        if group_count as usize > MAX_SHARES
+            || group_index >= group_count
+            || payload[3] == 0
+            || payload[3] > group_count
+            || member_index as usize >= MAX_SHARES
+            || member_threshold == 0
+            || member_threshold as usize > MAX_SHARES
+            || payload.len() < HEADER_LEN + MIN_SECRET_LEN + DIGEST_LEN
+        {
+            return Err(err);
+        }
+
+        Ok(Self {
+            id: [payload[0], payload[1]],
+            group_index,
+            group_threshold: payload[3],
+            group_count,
+            member_index,
+            member_threshold,
+            value: payload[HEADER_LEN..].to_vec(),
+        })
+    }
+}
+
+/// Returns the number of words encoding the given number of bytes.
+fn words_len(bytes: usize) -> usize {
+    (bytes * 8).div_ceil(BITS_PER_WORD)
+}
+
+/// Encodes bytes as words, padding the last one with zeros.
+fn to_words(bytes: &[u8]) -> String {
+    let list = Language::English.word_list();
+
+    (0..words_len(bytes.len()))
+        .map(|w| {
+            let index = (0..BITS_PER_WORD).fold(0usize, |index, b| {
+                let bit = w * BITS_PER_WORD + b;
+                let set = bit < bytes.len() * 8
+                    && bytes[bit / 8] & (0x80 >> (bit % 8)) != 0;
+                index << 1 | set as usize
+            });
+            list[index]
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits every byte of the secret into `count` shares, any `threshold` of
+/// which recover it. The share at index `i` is evaluated at `x = i + 1`.
+fn split_bytes<Rng: RngCore + CryptoRng>(
+    rng: &mut Rng,
+    secret: &[u8],
+    threshold: usize,
+    count: usize,
+) -> Vec<Vec<u8>> {
+    let mut coefficients = vec![vec![0u8; secret.len()]; threshold - 1];
+    coefficients.iter_mut().for_each(|c| rng.fill_bytes(c));
+
+    (1..=count as u8)
+        .map(|x| {
+            (0..secret.len())
+                .map(|i| {
+                    // Horner's method, from the highest degree down
+                    let high = coefficients
+                        .iter()
+                        .rev()
+                        .fold(0, |acc, c| gf_mul(acc, x) ^ c[i]);
+                    gf_mul(high, x) ^ secret[i]
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Interpolates the points at `x = 0`.
+fn interpolate(points: &[(u8, &[u8])]) -> Vec<u8> {
+    let len = points.first().map(|(_, y)| y.len()).unwrap_or_default();
+    let mut secret = vec![0u8; len];
+
+    for (i, (xi, yi)) in points.iter().enumerate() {
+        let basis = points
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold(1, |acc, (_, (xj, _))| {
+                gf_mul(acc, gf_mul(*xj, gf_inv(xj ^ xi)))
+            });
+
+        secret
+            .iter_mut()
+            .zip(yi.iter())
+            .for_each(|(s, y)| *s ^= gf_mul(basis, *y));
+    }
+
+    secret
+}
+
+/// Multiplies in GF(256), modulo the AES polynomial.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        a = (a << 1) ^ (0x1b & 0u8.wrapping_sub(a >> 7));
+        b >>= 1;
+    }
+    product
+}
+
+/// Inverts in GF(256), as `a^254`.
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1;
+    let mut base = a;
+    let mut exp = 254u8;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+#[test]
+fn shamir_works() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let seed = [0xfa; RNG_SEED];
+
+    // 2 of 3 groups: a 1 of 1, a 2 of 3 and a 3 of 5
+    let groups = split(rng, &seed, 2, &[(1, 1), (2, 3), (3, 5)]).unwrap();
+
+    assert_eq!(groups.len(), 3);
+    assert_eq!(groups[2].len(), 5);
+
+    let shares = [&groups[0][0], &groups[1][2], &groups[1][0]];
+    assert_eq!(combine(&shares).unwrap(), seed);
+
+    // the incomplete second group is ignored
+    let shares = [
+        &groups[2][4],
+        &groups[1][1],
+        &groups[2][0],
+        &groups[2][2],
+        &groups[0][0],
+    ];
+    assert_eq!(combine(&shares).unwrap(), seed);
+
+    // a single group is not enough
+    let shares = [&groups[1][0], &groups[1][1]];
+    assert_eq!(combine(&shares), Err(WalletError::InvalidValue("shares")));
+
+    // neither are too few members of a group
+    let shares = [&groups[0][0], &groups[2][0], &groups[2][1]];
+    assert_eq!(combine(&shares), Err(WalletError::InvalidValue("shares")));
+
+    // a mistyped word is caught by the checksum
+    let mut words: Vec<&str> = groups[0][0].split_whitespace().collect();
+    words[3] = if words[3] == "zoo" { "abandon" } else { "zoo" };
+    let typo = words.join(" ");
+    assert_eq!(
+        combine(&[&typo, &groups[1][0], &groups[1][1]]),
+        Err(WalletError::Decode("shares"))
+    );
+
+    // shares of another split don't mix
+    let other = split(rng, &seed, 2, &[(1, 1), (1, 1)]).unwrap();
+    assert!(combine(&[&groups[0][0], &other[1][0]]).is_err());
+
+    // the entropy of a mnemonic is shared just as well
+    let entropy = [0x42; 16];
+    let groups = split(rng, &entropy, 1, &[(2, 2)]).unwrap();
+    assert_eq!(combine(&groups[0]).unwrap(), entropy);
+
+    assert!(split(rng, &entropy, 2, &[(2, 2)]).is_err());
+    assert!(split(rng, &entropy, 1, &[(3, 2)]).is_err());
+    assert!(split(rng, &entropy[..8], 1, &[(1, 1)]).is_err());
+
+    // a passphrase only goes with the entropy of a mnemonic
+    let english = Mnemonic::from_entropy(&entropy).unwrap();
+    assert_eq!(
+        secret_seed(&entropy, Language::English, Some("pass")),
+        Ok(english.to_seed("pass"))
+    );
+    assert_eq!(secret_seed(&seed, Language::English, None), Ok(seed));
+    assert_eq!(
+        secret_seed(&seed, Language::English, Some("")),
+        Err(WalletError::InvalidValue("passphrase"))
+    );
+
+    // a well formed header out of bounds is rejected rather than overflowing
+    let share = |group_count, member_index, member_threshold, value_len| {
+        let mut bytes = vec![0, 0, 0, 1, group_count, member_index];
+        bytes.extend_from_slice(&[member_threshold, value_len as u8]);
+        bytes.resize(HEADER_LEN + value_len, 0);
+        let checksum = Sha256::digest(&bytes);
+        bytes.extend_from_slice(&checksum[..CHECKSUM_LEN]);
+        to_words(&bytes)
+    };
+    let value_len = MIN_SECRET_LEN + DIGEST_LEN;
+    assert!(Share::from_words(&share(1, 15, 16, value_len)).is_ok());
+    for (group_count, member_index, member_threshold) in
+        [(17, 0, 1), (1, 16, 1), (1, 255, 1), (1, 0, 17)]
+    {
+        assert_eq!(
+            combine(&[share(
+                group_count,
+                member_index,
+                member_threshold,
+                value_len
+            )]),
+            Err(WalletError::Decode("shares"))
+        );
+    }
+
+    // a value too short to hold a secret and its digest is rejected, rather
+    // than failing to split the digest off
+    for value_len in [0, 1, DIGEST_LEN, value_len - 1] {
+        assert_eq!(
+            combine(&[share(1, 0, 1, value_len)]),
+            Err(WalletError::Decode("shares"))
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "all-languages")]
+fn shamir_languages_work() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let entropy = [0x42; 16];
+
+    let groups = split(rng, &entropy, 1, &[(2, 2)]).unwrap();
+    let secret = combine(&groups[0]).unwrap();
+
+    let french = Mnemonic::from_entropy_in(Language::French, &entropy).unwrap();
+    let seed = secret_seed(&secret, Language::French, Some("pass")).unwrap();
+
+    assert_eq!(seed, french.to_seed("pass"));
+    assert_ne!(
+        seed,
+        Mnemonic::from_entropy(&entropy).unwrap().to_seed("pass")
+    );
+}
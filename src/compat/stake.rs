@@ -5,35 +5,18 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use crate::{
-    ffi::allocate,
     key::*,
+    stake::{self, Stct},
     types::{self},
     utils::{self, *},
-    MAX_LEN,
+    WalletError, MAX_LEN,
 };
 
-use alloc::string::String;
-use alloc::vec::Vec;
-
-use bls12_381_bls::PublicKey as StakePublicKey;
-use dusk_bls12_381::BlsScalar;
-use dusk_bytes::{Serializable, Write};
+use dusk_bytes::Serializable;
 use dusk_jubjub::JubJubScalar;
 use dusk_plonk::prelude::Proof;
-use ff::Field;
 use jubjub_schnorr::Signature;
-use phoenix_core::{
-    transaction::{stct_signature_message, StakeData},
-    Crossover, Fee, Note, Ownable,
-};
-use stake_contract_types::{stake_signature_message, Stake};
-
-const STCT_INPUT_SIZE: usize = Fee::SIZE
-    + Crossover::SIZE
-    + u64::SIZE
-    + JubJubScalar::SIZE
-    + BlsScalar::SIZE
-    + Signature::SIZE;
+use phoenix_core::{transaction::StakeData, Crossover, Fee};
 
 /// Get the bytes to send to the node to prove stct proof
 /// and then we can get the proof verified from the node
@@ -48,90 +31,50 @@ pub fn get_stct_proof(args: i32, len: i32) -> i64 {
         gas_limit,
         gas_price,
     } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let rng_seed = match utils::sanitize_rng_seed(rng_seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("rng_seed")),
     };
 
     let seed = match utils::sanitize_seed(seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("seed")),
     };
 
     let sender = derive_sk(&seed, sender_index);
     let refund = match bs58_to_pk(&refund) {
         Some(a) => a,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidAddress("refund")),
     };
 
     let rng = &mut utils::rng(rng_seed);
 
-    let blinder = JubJubScalar::random(&mut *rng);
-    let note = Note::obfuscated(rng, &refund, value, blinder);
-    let (mut fee, crossover) = note
-        .try_into()
-        .expect("Obfuscated notes should always yield crossovers");
-
-    fee.gas_limit = gas_limit;
-    fee.gas_price = gas_price;
-
-    let contract_id = rusk_abi::STAKE_CONTRACT;
-    let address = rusk_abi::contract_to_scalar(&contract_id);
-
-    let contract_id = rusk_abi::contract_to_scalar(&contract_id);
-
-    let stct_message = stct_signature_message(&crossover, value, contract_id);
-    let stct_message = dusk_poseidon::sponge::hash(&stct_message);
-
-    let note_sk = sender.sk_r(fee.stealth_address());
-    let stct_signature = note_sk.sign(rng, stct_message);
-
-    let vec_allocation = allocate(STCT_INPUT_SIZE as i32) as *mut _;
-    let mut buf: Vec<u8> = unsafe {
-        Vec::from_raw_parts(vec_allocation, STCT_INPUT_SIZE, STCT_INPUT_SIZE)
-    };
-
-    let mut writer = &mut buf[..];
+    let stct = Stct::new(rng, &sender, &refund, value, gas_limit, gas_price);
+    let bytes = stct.to_bytes().to_vec();
 
-    let mut bytes = || {
-        writer.write(&fee.to_bytes()).ok()?;
-        writer.write(&crossover.to_bytes()).ok()?;
-        writer.write(&value.to_bytes()).ok()?;
-        writer.write(&blinder.to_bytes()).ok()?;
-        writer.write(&address.to_bytes()).ok()?;
-        writer.write(&stct_signature.to_bytes()).ok()?;
-
-        Some(())
-    };
-
-    let bytes = match bytes() {
-        Some(_) => buf,
-        None => return utils::fail(),
-    }
-    .to_vec();
-
-    let stct_sig = match rkyv::to_bytes::<Signature, MAX_LEN>(&stct_signature) {
+    let stct_sig = match rkyv::to_bytes::<Signature, MAX_LEN>(&stct.signature) {
         Ok(a) => a.to_vec(),
-        Err(_) => return utils::fail(),
+        Err(_) => return utils::fail(WalletError::Encode("signature")),
     };
 
-    let crossover = match rkyv::to_bytes::<Crossover, MAX_LEN>(&crossover) {
+    let crossover = match rkyv::to_bytes::<Crossover, MAX_LEN>(&stct.crossover)
+    {
         Ok(a) => a.to_vec(),
-        Err(_) => return utils::fail(),
+        Err(_) => return utils::fail(WalletError::Encode("crossover")),
     };
 
-    let blinder = match rkyv::to_bytes::<JubJubScalar, MAX_LEN>(&blinder) {
+    let blinder = match rkyv::to_bytes::<JubJubScalar, MAX_LEN>(&stct.blinder) {
         Ok(a) => a.to_vec(),
-        Err(_) => return utils::fail(),
+        Err(_) => return utils::fail(WalletError::Encode("blinder")),
     };
 
-    let fee = match rkyv::to_bytes::<Fee, MAX_LEN>(&fee) {
+    let fee = match rkyv::to_bytes::<Fee, MAX_LEN>(&stct.fee) {
         Ok(a) => a.to_vec(),
-        Err(_) => return utils::fail(),
+        Err(_) => return utils::fail(WalletError::Encode("fee")),
     };
 
     utils::into_ptr(types::GetStctProofResponse {
@@ -153,49 +96,36 @@ pub fn get_stake_call_data(args: i32, len: i32) -> i64 {
         value,
         counter,
     } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let proof: [u8; Proof::SIZE] = match proof.try_into().ok() {
         Some(a) => a,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("proof")),
     };
 
     let proof = match Proof::from_bytes(&proof).ok() {
-        Some(a) => a.to_bytes().to_vec(),
-        None => return utils::fail(),
+        Some(a) => a,
+        None => return utils::fail(WalletError::Decode("proof")),
     };
 
     let seed = match utils::sanitize_seed(seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("seed")),
     };
 
     let stake_sk = derive_stake_sk(&seed, staker_index);
-    let stake_pk = StakePublicKey::from(&stake_sk);
-
-    let msg = stake_signature_message(counter, value);
-    let stake_sig = stake_sk.sign(&stake_pk, &msg);
-
-    let stake = Stake {
-        public_key: stake_pk,
-        signature: stake_sig,
-        value,
-        proof,
-    };
 
-    let contract = bs58::encode(rusk_abi::STAKE_CONTRACT).into_string();
-    let method = String::from("stake");
-    let payload = match rkyv::to_bytes::<_, MAX_LEN>(&stake).ok() {
-        Some(a) => a.to_vec(),
-        None => return utils::fail(),
+    let call = match stake::stake_call(&stake_sk, value, counter, &proof) {
+        Ok(c) => c,
+        Err(e) => return utils::fail(e),
     };
 
     utils::into_ptr(types::GetStakeCallDataResponse {
-        contract,
-        method,
-        payload,
+        contract: call.contract,
+        method: call.method,
+        payload: call.payload,
     })
 }
 
@@ -203,8 +133,8 @@ pub fn get_stake_call_data(args: i32, len: i32) -> i64 {
 fn get_stake_info(args: i32, len: i32) -> i64 {
     let types::GetStakeInfoArgs { stake_info } =
         match utils::take_args(args, len) {
-            Some(a) => a,
-            None => return utils::fail(),
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
         };
 
     let mut has_staked = false;
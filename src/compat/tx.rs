@@ -5,50 +5,40 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use crate::{
-    ffi::allocate,
-    key::derive_vk,
+    key::{derive_sk, derive_vk},
+    memo,
     tx::{self},
-    types, utils,
+    types, utils, WalletError,
 };
 
-use alloc::{
-    format,
-    string::{String, ToString},
-    vec::Vec,
-};
+use alloc::{format, string::String, vec::Vec};
 
-use dusk_bytes::{
-    DeserializableSlice, Error as BytesError, Serializable, Write,
-};
-use dusk_jubjub::{BlsScalar, JubJubAffine, JubJubScalar};
+use dusk_bytes::{DeserializableSlice, Serializable};
+use dusk_jubjub::BlsScalar;
 use dusk_plonk::prelude::Proof;
 use hashbrown::{hash_map::Entry, HashMap};
-use jubjub_schnorr::SignatureDouble;
-use phoenix_core::{transaction, Crossover, Fee, Note, Transaction};
-use rusk_abi::{hash::Hasher, ContractId, CONTRACT_ID_BYTES};
+use phoenix_core::{
+    transaction, Note, NoteType, Ownable, Transaction, ViewKey,
+};
+use rusk_abi::hash::Hasher;
 
 /// Convert a tx::UnprovenTransaction to bytes ready to be sent to the node
 #[no_mangle]
 pub fn unproven_tx_to_bytes(args: i32, len: i32) -> i64 {
     // re-using this type
     let types::RkyvTreeLeaf { bytes } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let tx: tx::UnprovenTransaction = match rkyv::from_bytes(&bytes).ok() {
         Some(a) => a,
-        None => return utils::fail(),
-    };
-
-    let bytes = utx_to_var_bytes(&tx);
-
-    let serialized = match bytes.ok() {
-        Some(a) => a.to_vec(),
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::Decode("bytes")),
     };
 
-    utils::into_ptr(types::UnprovenTxToBytesResponse { serialized })
+    utils::into_ptr(types::UnprovenTxToBytesResponse {
+        serialized: tx.to_var_bytes(),
+    })
 }
 
 /// Make sure the proof is okay and convert the given unproven tx
@@ -57,21 +47,21 @@ pub fn unproven_tx_to_bytes(args: i32, len: i32) -> i64 {
 pub fn prove_tx(args: i32, len: i32) -> i64 {
     let types::ProveTxArgs { unproven_tx, proof } =
         match utils::take_args(args, len) {
-            Some(a) => a,
-            None => return utils::fail(),
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
         };
 
     let utx: tx::UnprovenTransaction = match rkyv::from_bytes(&unproven_tx).ok()
     {
         Some(a) => a,
         None => {
-            return utils::fail();
+            return utils::fail(WalletError::Decode("unproven_tx"));
         }
     };
 
     let proof = match Proof::from_slice(&proof).ok() {
         Some(a) => a,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::Decode("proof")),
     };
 
     let mut call = None;
@@ -112,6 +102,38 @@ pub fn prove_tx(args: i32, len: i32) -> i64 {
     utils::into_ptr(types::ProveTxResponse { bytes, hash })
 }
 
+/// Verify an unproven tx spends the notes of the sender correctly, before
+/// sending it to the prover
+#[no_mangle]
+pub fn verify_unproven_tx(args: i32, len: i32) -> i64 {
+    let types::VerifyUnprovenTxArgs {
+        sender_index,
+        seed,
+        unproven_tx,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let utx: tx::UnprovenTransaction = match rkyv::from_bytes(&unproven_tx) {
+        Ok(a) => a,
+        Err(_) => return utils::fail(WalletError::Decode("unproven_tx")),
+    };
+
+    let seed = match utils::sanitize_seed(seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    if let Err(e) = utx.verify(&derive_sk(&seed, sender_index)) {
+        return utils::fail(e);
+    }
+
+    utils::into_ptr(types::VerifyUnprovenTxResponse {
+        hash: hex::encode(utx.hash().to_bytes()),
+    })
+}
+
 /// Calculate the history given the notes and tx data
 #[no_mangle]
 pub fn get_history(args: i32, len: i32) -> i64 {
@@ -121,42 +143,77 @@ pub fn get_history(args: i32, len: i32) -> i64 {
         notes,
         tx_data,
     } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
-    let mut ret: Vec<types::TransactionHistoryType> = Vec::new();
-
     let seed = match utils::sanitize_seed(seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    match history(&derive_vk(&seed, index), &notes, &tx_data) {
+        Ok(history) => utils::into_ptr(types::GetHistoryResponse { history }),
+        Err(e) => utils::fail(e),
+    }
+}
+
+/// Calculate the history given the notes and tx data, with only the view key
+/// of the account the notes belong to. The nullifiers of the notes are to be
+/// given along with them, as the view key can't compute them.
+#[no_mangle]
+pub fn watch_history(args: i32, len: i32) -> i64 {
+    let types::WatchHistoryArgs {
+        notes,
+        tx_data,
+        view_key,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let vk: ViewKey = match rkyv::from_bytes(&view_key) {
+        Ok(vk) => vk,
+        Err(_) => return utils::fail(WalletError::Decode("view_key")),
     };
 
+    match history(&vk, &notes, &tx_data) {
+        Ok(history) => utils::into_ptr(types::GetHistoryResponse { history }),
+        Err(e) => utils::fail(e),
+    }
+}
+
+/// Computes the history of the account of `vk` from its notes.
+fn history(
+    vk: &ViewKey,
+    notes: &[types::NoteInfoType],
+    tx_data: &[types::TxsDataType],
+) -> Result<Vec<types::TransactionHistoryType>, WalletError> {
+    let mut ret: Vec<types::TransactionHistoryType> = Vec::new();
+
     let mut nullifiers = Vec::new();
 
     for note_data in notes.iter() {
         let nullifier =
             match rkyv::from_bytes::<BlsScalar>(&note_data.nullifier) {
                 Ok(a) => a,
-                Err(_) => return utils::fail(),
+                Err(_) => return Err(WalletError::Decode("nullifier")),
             };
 
         let note = match rkyv::from_bytes::<Note>(&note_data.note).ok() {
             Some(a) => a,
-            None => return utils::fail(),
+            None => return Err(WalletError::Decode("note")),
         };
 
-        nullifiers
-            .push((nullifier, note.value(Some(&derive_vk(&seed, index)))));
+        nullifiers.push((nullifier, note.value(Some(vk)), note.note()));
     }
 
     let mut block_txs = HashMap::new();
-    let vk = derive_vk(&seed, index);
 
     for (index, note_data) in notes.iter().enumerate() {
         let mut note = match rkyv::from_bytes::<Note>(&note_data.note).ok() {
             Some(a) => a,
-            None => return utils::fail(),
+            None => return Err(WalletError::Decode("note")),
         };
 
         note.set_pos(u64::MAX);
@@ -181,37 +238,47 @@ pub fn get_history(args: i32, len: i32) -> i64 {
 
                 let txn = match transactions {
                     Some(a) => a,
-                    None => return utils::fail(),
+                    None => return Err(WalletError::Decode("tx_data")),
                 };
 
                 v.insert(txn)
             }
         };
 
-        let note_amount = match note.value(Some(&vk)).ok() {
+        let note_amount = match note.value(Some(vk)).ok() {
             Some(a) => a,
-            None => return utils::fail(),
+            None => return Err(WalletError::NoteOwnership("notes")),
         } as f64;
 
+        let ref_id = memo::ref_id(vk, &note);
+
         let note_creator = txs.iter().find(|(t, _)| {
             t.outputs().iter().any(|&n| n.hash().eq(&note_hash))
         });
 
         if let Some((t, gas_spent)) = note_creator {
-            let inputs_amount: Result<Vec<u64>, _> = t
+            let inputs: Vec<_> = t
                 .nullifiers()
                 .iter()
                 .filter_map(|input| {
                     nullifiers
-                        .clone()
-                        .into_iter()
-                        .find_map(|n| n.0.eq(input).then_some(n.1))
+                        .iter()
+                        .find(|n| n.0.eq(input))
+                        .map(|n| (n.1.clone(), n.2))
                 })
                 .collect();
 
+            // the flow is transparent only if all the notes of the account
+            // in it are
+            let transparent = note.note() == NoteType::Transparent
+                && inputs.iter().all(|(_, t)| *t == NoteType::Transparent);
+
+            let inputs_amount: Result<Vec<u64>, _> =
+                inputs.into_iter().map(|(value, _)| value).collect();
+
             let inputs_amount = match inputs_amount {
                 Ok(a) => a.iter().sum::<u64>() as f64,
-                Err(_) => return utils::fail(),
+                Err(_) => return Err(WalletError::NoteOwnership("notes")),
             };
 
             let direction = match inputs_amount > 0f64 {
@@ -220,16 +287,27 @@ pub fn get_history(args: i32, len: i32) -> i64 {
             };
             let transaction_type =
                 transaction_type(t.call.as_ref().map(|(_, x, _)| x.as_str()));
-            let hash_to_find = Hasher::digest(t.to_hash_input_bytes());
-            match ret.iter_mut().find(|th| th.id == hash_to_find.to_string()) {
-                Some(tx) => tx.amount += note_amount,
+            let id = transaction_hash(Hasher::digest(t.to_hash_input_bytes()));
+            match ret.iter_mut().find(|th| th.id == id) {
+                Some(tx) => {
+                    tx.amount += note_amount;
+                    if !transparent {
+                        tx.note_type = types::OutputType::Obfuscated;
+                    }
+                    tx.ref_id = tx.ref_id.or(ref_id);
+                }
                 None => ret.push(types::TransactionHistoryType {
                     direction,
                     block_height: note_data.block_height,
                     amount: note_amount - inputs_amount,
                     fee: gas_spent * t.fee().gas_price,
+                    note_type: match transparent {
+                        true => types::OutputType::Transparent,
+                        false => types::OutputType::Obfuscated,
+                    },
                     tx_type: transaction_type,
-                    id: transaction_hash(hash_to_find),
+                    id,
+                    ref_id,
                 }),
             }
         } else {
@@ -239,12 +317,15 @@ pub fn get_history(args: i32, len: i32) -> i64 {
             });
 
             if let Some(th) = outgoing_tx {
-                th.amount += note_amount
+                th.amount += note_amount;
+                if note.note() == NoteType::Obfuscated {
+                    th.note_type = types::OutputType::Obfuscated;
+                }
             }
         }
     }
 
-    ret.sort_by(|a, b| a.block_height.cmp(&b.block_height));
+    ret.sort_by_key(|th| th.block_height);
 
     ret = ret
         .into_iter()
@@ -257,7 +338,66 @@ pub fn get_history(args: i32, len: i32) -> i64 {
         })
         .collect::<Vec<_>>();
 
-    utils::into_ptr(types::GetHistoryResponse { history: ret })
+    Ok(ret)
+}
+
+/// Decode the bytes of a proven transaction, as returned by `prove_tx`, for
+/// inspection
+#[no_mangle]
+pub fn decode_tx(args: i32, len: i32) -> i64 {
+    let types::DecodeTxArgs { bytes } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let tx = match Transaction::from_slice(&bytes) {
+        Ok(t) => t,
+        Err(_) => return utils::fail(WalletError::Decode("bytes")),
+    };
+
+    let hash = Hasher::digest(tx.to_hash_input_bytes());
+
+    let outputs = tx
+        .outputs()
+        .iter()
+        .map(|note| types::DecodedNoteType {
+            hash: hex::encode(note.hash().to_bytes()),
+            note_type: match note.note() {
+                NoteType::Transparent => types::OutputType::Transparent,
+                NoteType::Obfuscated => types::OutputType::Obfuscated,
+            },
+            stealth_address: bs58::encode(note.stealth_address().to_bytes())
+                .into_string(),
+            value: note.value(None).ok(),
+        })
+        .collect();
+
+    let (contract, method, payload_len) = match &tx.call {
+        Some((contract, method, payload)) => (
+            Some(bs58::encode(contract).into_string()),
+            Some(method.clone()),
+            Some(payload.len() as u64),
+        ),
+        None => (None, None, None),
+    };
+
+    utils::into_ptr(types::DecodeTxResponse {
+        anchor: hex::encode(tx.anchor.to_bytes()),
+        contract,
+        crossover: tx.crossover.map(|c| hex::encode(c.to_bytes())),
+        gas_limit: tx.fee.gas_limit,
+        gas_price: tx.fee.gas_price,
+        hash: transaction_hash(hash),
+        method,
+        nullifiers: tx
+            .nullifiers
+            .iter()
+            .map(|n| hex::encode(n.to_bytes()))
+            .collect(),
+        outputs,
+        payload_len,
+        proof_len: tx.proof.len() as u64,
+    })
 }
 
 fn transaction_hash(hash: BlsScalar) -> String {
@@ -278,180 +418,3 @@ fn transaction_type(method: Option<&str>) -> String {
         Some(x) => x.to_uppercase(),
     }
 }
-
-/// Serialize a unprovenTx we recieved from the wallet-core
-/// this is copied from old wallet-core (0.20.0-piecrust.0.6)
-fn utx_to_var_bytes(
-    tx: &tx::UnprovenTransaction,
-) -> Result<Vec<u8>, BytesError> {
-    let serialized_inputs: Vec<Vec<u8>> =
-        tx.inputs.iter().map(input_to_var_bytes).collect();
-    let num_inputs = tx.inputs.len();
-    let total_input_len = serialized_inputs
-        .iter()
-        .fold(0, |len, input| len + input.len());
-
-    let serialized_outputs: Vec<
-        [u8; Note::SIZE + u64::SIZE + JubJubScalar::SIZE],
-    > = tx
-        .outputs
-        .iter()
-        .map(
-            |tx::Output {
-                 note,
-                 value,
-                 blinder,
-             }| {
-                let mut buf = [0; Note::SIZE + u64::SIZE + JubJubScalar::SIZE];
-
-                buf[..Note::SIZE].copy_from_slice(&note.to_bytes());
-                buf[Note::SIZE..Note::SIZE + u64::SIZE]
-                    .copy_from_slice(&value.to_bytes());
-                buf[Note::SIZE + u64::SIZE
-                    ..Note::SIZE + u64::SIZE + JubJubScalar::SIZE]
-                    .copy_from_slice(&blinder.to_bytes());
-
-                buf
-            },
-        )
-        .collect();
-    let num_outputs = tx.outputs.len();
-    let total_output_len = serialized_outputs
-        .iter()
-        .fold(0, |len, output| len + output.len());
-
-    let size = u64::SIZE
-        + num_inputs * u64::SIZE
-        + total_input_len
-        + u64::SIZE
-        + total_output_len
-        + BlsScalar::SIZE
-        + Fee::SIZE
-        + u64::SIZE
-        + tx.crossover
-            .clone()
-            .map_or(0, |_| Crossover::SIZE + u64::SIZE + JubJubScalar::SIZE)
-        + u64::SIZE
-        + tx.call
-            .as_ref()
-            .map(
-                |tx::CallData {
-                     contract: _,
-                     method,
-                     payload,
-                 }| {
-                    CONTRACT_ID_BYTES + u64::SIZE + method.len() + payload.len()
-                },
-            )
-            .unwrap_or(0);
-
-    let vec_allocation = allocate(size as i32) as *mut _;
-    let mut buf = unsafe { Vec::from_raw_parts(vec_allocation, size, size) };
-    let mut writer = &mut buf[..];
-
-    writer.write(&(num_inputs as u64).to_bytes())?;
-    for sinput in serialized_inputs {
-        writer.write(&(sinput.len() as u64).to_bytes())?;
-        writer.write(&sinput)?;
-    }
-
-    writer.write(&(num_outputs as u64).to_bytes())?;
-    for soutput in serialized_outputs {
-        writer.write(&soutput)?;
-    }
-
-    writer.write(&tx.anchor.to_bytes())?;
-    writer.write(&tx.fee.to_bytes())?;
-
-    let crossover = &tx.crossover;
-
-    write_crossover_value_blinder(
-        &mut writer,
-        crossover.clone().map(|crossover| {
-            (crossover.crossover, crossover.value, crossover.blinder)
-        }),
-    )?;
-    write_optional_call(
-        &mut writer,
-        &tx.call
-            .clone()
-            .map(|call| (call.contract, call.method, call.payload)),
-    )?;
-
-    Ok(buf)
-}
-
-fn write_crossover_value_blinder<W: Write>(
-    writer: &mut W,
-    crossover: Option<(Crossover, u64, JubJubScalar)>,
-) -> Result<(), BytesError> {
-    match crossover {
-        Some((crossover, value, blinder)) => {
-            writer.write(&1_u64.to_bytes())?;
-            writer.write(&crossover.to_bytes())?;
-            writer.write(&value.to_bytes())?;
-            writer.write(&blinder.to_bytes())?;
-        }
-        None => {
-            writer.write(&0_u64.to_bytes())?;
-        }
-    }
-
-    Ok(())
-}
-
-/// Writes an optional call into the writer, prepending it with a `u64` denoting
-/// if it is present or not. This should be called at the end of writing other
-/// fields since it doesn't write any information about the length of the call
-/// data.
-fn write_optional_call<W: Write>(
-    writer: &mut W,
-    call: &Option<(ContractId, String, Vec<u8>)>,
-) -> Result<(), BytesError> {
-    match call {
-        Some((cid, cname, cdata)) => {
-            writer.write(&1_u64.to_bytes())?;
-
-            writer.write(cid.as_bytes())?;
-
-            let cname_len = cname.len() as u64;
-            writer.write(&cname_len.to_bytes())?;
-            writer.write(cname.as_bytes())?;
-
-            writer.write(cdata)?;
-        }
-        None => {
-            writer.write(&0_u64.to_bytes())?;
-        }
-    };
-
-    Ok(())
-}
-
-fn input_to_var_bytes(input: &tx::Input) -> Vec<u8> {
-    let affine_pkr = JubJubAffine::from(&input.note_pk_prime);
-
-    let opening_bytes = rkyv::to_bytes::<_, 256>(&input.opening)
-        .expect("Rkyv serialization should always succeed for an opening")
-        .to_vec();
-
-    let size = BlsScalar::SIZE
-        + Note::SIZE
-        + JubJubAffine::SIZE
-        + SignatureDouble::SIZE
-        + u64::SIZE
-        + JubJubScalar::SIZE
-        + opening_bytes.len();
-
-    let mut bytes: Vec<_> = Vec::with_capacity(size);
-
-    bytes.extend_from_slice(&input.nullifier.to_bytes());
-    bytes.extend_from_slice(&input.note.to_bytes());
-    bytes.extend_from_slice(&input.value.to_bytes());
-    bytes.extend_from_slice(&input.blinder.to_bytes());
-    bytes.extend_from_slice(&affine_pkr.to_bytes());
-    bytes.extend_from_slice(&input.sig.to_bytes());
-    bytes.extend(opening_bytes);
-
-    bytes
-}
@@ -0,0 +1,222 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Consolidation of the notes of an account.
+//!
+//! A transaction spends at most [`MAX_INPUT_NOTES`] notes, so an account
+//! holding more notes can't spend its whole balance at once. Consolidating
+//! merges the smallest notes into one with self-transfers, over as many rounds
+//! as needed to bring the notes down to [`MAX_INPUT_NOTES`].
+//!
+//! The notes created by a round must be in the tree before being spent, so
+//! only the transactions of the first round can be built at once. The host is
+//! expected to consolidate again once they are executed.
+
+use alloc::vec::Vec;
+
+use dusk_bytes::Serializable;
+use phoenix_core::{PublicKey, SecretKey};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{
+    select::Strategy,
+    tx::{self, Opening, UnprovenTransaction},
+    types, utils, WalletError, MAX_INPUT_NOTES,
+};
+
+/// The plan to consolidate the notes of an account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    /// The notes merged by each transaction of the first round, in order.
+    pub groups: Vec<Vec<utils::Node>>,
+    /// The number of rounds needed to make the whole balance spendable.
+    pub rounds: u64,
+    /// The number of transactions over all the rounds.
+    pub transactions: u64,
+    /// The projected fee over all the rounds, if all the gas is used.
+    pub fee: u64,
+    /// The number of notes left out, since merging them costs more than they
+    /// are worth.
+    pub dust: u64,
+}
+
+impl Plan {
+    /// Plans the consolidation of the given notes, each transaction costing
+    /// at most `fee`.
+    pub fn new(mut nodes: Vec<utils::Node>, fee: u64) -> Self {
+        nodes.sort_by_key(|(_, value, _)| *value);
+
+        // the smallest notes are left out until the smallest group is worth
+        // more than the fee to merge it
+        let excess = nodes.len().saturating_sub(MAX_INPUT_NOTES);
+        let dust = nodes
+            .windows(MAX_INPUT_NOTES)
+            .take(excess)
+            .position(|group| {
+                group
+                    .iter()
+                    .fold(0u64, |sum, (_, v, _)| sum.saturating_add(*v))
+                    > fee
+            })
+            .unwrap_or(excess);
+        nodes.drain(..dust);
+
+        let mut count = nodes.len();
+        let mut rounds = 0;
+        let mut transactions = 0;
+        let mut groups = Vec::new();
+
+        // every transaction merges up to `MAX_INPUT_NOTES` notes into one,
+        // and the transactions of a round spend distinct notes
+        while count > MAX_INPUT_NOTES {
+            let needed =
+                (count - MAX_INPUT_NOTES).div_ceil(MAX_INPUT_NOTES - 1);
+            let round = needed.min(count / MAX_INPUT_NOTES);
+
+            if rounds == 0 {
+                groups = nodes
+                    .chunks(MAX_INPUT_NOTES)
+                    .take(round)
+                    .map(|group| group.to_vec())
+                    .collect();
+            }
+
+            count -= round * (MAX_INPUT_NOTES - 1);
+            rounds += 1;
+            transactions += round as u64;
+        }
+
+        Self {
+            groups,
+            rounds,
+            transactions,
+            fee: fee.saturating_mul(transactions),
+            dust: dust as u64,
+        }
+    }
+}
+
+/// The transactions of the first round of a consolidation, along with the
+/// plan for the whole of it.
+#[derive(Debug, Clone)]
+pub struct Consolidation {
+    /// The transactions of the first round, in order.
+    pub transactions: Vec<UnprovenTransaction>,
+    /// The number of rounds needed to make the whole balance spendable.
+    pub rounds: u64,
+    /// The number of transactions over all the rounds.
+    pub total_transactions: u64,
+    /// The projected fee over all the rounds, if all the gas is used.
+    pub fee: u64,
+    /// The number of notes left out, since merging them costs more than they
+    /// are worth.
+    pub dust: u64,
+}
+
+/// Builds the transactions of the first round of the consolidation of the
+/// notes of `sk`, each merging its notes into a single obfuscated note owned
+/// by `sk`.
+pub fn consolidate<Rng, F, E>(
+    rng: &mut Rng,
+    sk: &SecretKey,
+    notes: Vec<utils::Node>,
+    mut opening: F,
+    gas_limit: u64,
+    gas_price: u64,
+) -> Result<Consolidation, E>
+where
+    Rng: RngCore + CryptoRng,
+    F: FnMut(u64) -> Result<Opening, E>,
+    E: From<WalletError>,
+{
+    let fee = gas_limit.saturating_mul(gas_price);
+    let plan = Plan::new(notes, fee);
+
+    let pk = PublicKey::from(sk);
+    let receiver = bs58::encode(pk.to_bytes()).into_string();
+
+    let transactions = plan
+        .groups
+        .into_iter()
+        .map(|group| {
+            let value = group
+                .iter()
+                .fold(0u64, |sum, (_, v, _)| sum.saturating_add(*v))
+                .saturating_sub(fee);
+
+            let output = types::ExecuteOutput {
+                note_type: types::OutputType::Obfuscated,
+                receiver: receiver.clone(),
+                ref_id: None,
+                value,
+            };
+
+            // the groups are small enough to be spent whole
+            tx::execute(
+                rng,
+                sk,
+                group,
+                &Strategy::Lexicographic,
+                &mut opening,
+                &pk,
+                types::OutputType::Obfuscated,
+                alloc::vec![output],
+                gas_limit,
+                gas_price,
+                None,
+                None,
+                None,
+            )
+        })
+        .collect::<Result<Vec<_>, E>>()?;
+
+    Ok(Consolidation {
+        transactions,
+        rounds: plan.rounds,
+        total_transactions: plan.transactions,
+        fee: plan.fee,
+        dust: plan.dust,
+    })
+}
+
+#[test]
+fn plan_works() {
+    use dusk_jubjub::JubJubScalar;
+    use phoenix_core::Note;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let pk = PublicKey::from(&SecretKey::random(rng));
+
+    let mut nodes = |values: &[u64]| -> Vec<utils::Node> {
+        values
+            .iter()
+            .map(|value| {
+                let note = Note::transparent(&mut *rng, &pk, *value);
+                (note, *value, JubJubScalar::zero())
+            })
+            .collect()
+    };
+
+    // few enough notes are left alone
+    let plan = Plan::new(nodes(&[10, 20, 30, 40]), 5);
+    assert_eq!((plan.rounds, plan.transactions), (0, 0));
+
+    // 13 notes take three merges of four notes to be brought down to four
+    let plan = Plan::new(nodes(&[100; 13]), 5);
+    assert_eq!(plan.groups.len(), 3);
+    assert_eq!((plan.rounds, plan.transactions, plan.fee), (1, 3, 15));
+
+    // 20 notes take five merges in the first round, and one in the second
+    let plan = Plan::new(nodes(&[100; 20]), 5);
+    assert_eq!(plan.groups.len(), 5);
+    assert_eq!((plan.rounds, plan.transactions), (2, 6));
+
+    // the notes not worth the fee are left out
+    let plan = Plan::new(nodes(&[1, 1, 2, 3, 500, 600, 700, 800]), 10);
+    assert_eq!(plan.dust, 1);
+    assert_eq!(plan.groups[0].iter().map(|n| n.1).sum::<u64>(), 506);
+}
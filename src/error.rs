@@ -0,0 +1,152 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Errors returned by the wallet functions.
+
+use alloc::string::ToString;
+use core::fmt;
+
+use crate::types;
+
+/// The error of a wallet operation.
+///
+/// Every variant maps to a stable numeric [`code`](WalletError::code) that is
+/// part of the public interface of the module, so hosts can tell failures
+/// apart without parsing the message. Codes are never reused nor renumbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletError {
+    /// The arguments are not a valid JSON for the called function.
+    InvalidArgs,
+    /// The named field doesn't have the expected length.
+    InvalidLength(&'static str),
+    /// The named field couldn't be deserialized.
+    Decode(&'static str),
+    /// The named value couldn't be serialized.
+    Encode(&'static str),
+    /// The named field is not a valid Base58 public key.
+    InvalidAddress(&'static str),
+    /// A note of the named field can't be decrypted by the wallet keys.
+    NoteOwnership(&'static str),
+    /// No opening was provided for a note chosen as input.
+    MissingOpening,
+    /// The notes don't hold enough value to cover the transaction.
+    InsufficientBalance,
+    /// The transaction couldn't be built from the given arguments.
+    Transaction,
+    /// The named field holds a value out of its allowed range.
+    InvalidValue(&'static str),
+    /// The key has no stake in the stake contract.
+    NoStake,
+    /// An opening doesn't open to the Merkle root of the state.
+    InvalidOpening,
+    /// The encrypted data couldn't be authenticated, either because the
+    /// password is wrong or because the data was tampered with.
+    Decrypt,
+    /// The outputs, change included, are more than the circuit supports.
+    TooManyOutputs,
+    /// The named part of a transaction fails verification.
+    InvalidTransaction(&'static str),
+}
+
+impl WalletError {
+    /// Returns the stable numeric code of the error.
+    pub const fn code(&self) -> u64 {
+        match self {
+            Self::InvalidArgs => 1,
+            Self::InvalidLength(_) => 2,
+            Self::Decode(_) => 3,
+            Self::Encode(_) => 4,
+            Self::InvalidAddress(_) => 5,
+            Self::NoteOwnership(_) => 6,
+            Self::MissingOpening => 7,
+            Self::InsufficientBalance => 8,
+            Self::Transaction => 9,
+            Self::InvalidValue(_) => 10,
+            Self::NoStake => 11,
+            Self::InvalidOpening => 12,
+            Self::Decrypt => 13,
+            Self::TooManyOutputs => 14,
+            Self::InvalidTransaction(_) => 15,
+        }
+    }
+
+    /// Returns a human readable description of the error.
+    pub const fn message(&self) -> &'static str {
+        match self {
+            Self::InvalidArgs => "invalid arguments",
+            Self::InvalidLength(_) => "invalid length",
+            Self::Decode(_) => "failed to deserialize",
+            Self::Encode(_) => "failed to serialize",
+            Self::InvalidAddress(_) => "invalid address",
+            Self::NoteOwnership(_) => "note not owned by the wallet",
+            Self::MissingOpening => "missing opening for input note",
+            Self::InsufficientBalance => "insufficient balance",
+            Self::Transaction => "failed to build the transaction",
+            Self::InvalidValue(_) => "invalid value",
+            Self::NoStake => "no stake for the key",
+            Self::InvalidOpening => "opening doesn't match the anchor",
+            Self::Decrypt => "wrong password or corrupted data",
+            Self::TooManyOutputs => "too many outputs",
+            Self::InvalidTransaction(_) => "invalid transaction",
+        }
+    }
+
+    /// Returns the name of the offending field, if any.
+    pub const fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidLength(f)
+            | Self::Decode(f)
+            | Self::Encode(f)
+            | Self::InvalidAddress(f)
+            | Self::NoteOwnership(f)
+            | Self::InvalidValue(f)
+            | Self::InvalidTransaction(f) => Some(f),
+            Self::MissingOpening | Self::InvalidOpening => Some("openings"),
+            Self::InsufficientBalance => Some("inputs"),
+            Self::TooManyOutputs => Some("outputs"),
+            Self::InvalidArgs
+            | Self::Transaction
+            | Self::NoStake
+            | Self::Decrypt => None,
+        }
+    }
+}
+
+impl fmt::Display for WalletError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.field() {
+            Some(field) => write!(f, "{}: {field}", self.message()),
+            None => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl std::error::Error for WalletError {}
+
+impl From<WalletError> for types::ErrorResponse {
+    fn from(err: WalletError) -> Self {
+        Self {
+            code: err.code(),
+            message: err.message().to_string(),
+            field: err.field().map(|f| f.to_string()),
+        }
+    }
+}
+
+#[test]
+fn error_response_works() {
+    let response = types::ErrorResponse::from(WalletError::Decode("notes"));
+
+    assert_eq!(response.code, 3);
+    assert_eq!(response.message, "failed to deserialize");
+    assert_eq!(response.field.as_deref(), Some("notes"));
+
+    let response = types::ErrorResponse::from(WalletError::InvalidArgs);
+    let json = serde_json::to_string(&response).unwrap();
+
+    assert_eq!(json, r#"{"code":1,"message":"invalid arguments"}"#);
+}
@@ -0,0 +1,330 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Estimation of the fee of a transaction.
+//!
+//! The gas spent by a transaction grows with the notes it spends and creates,
+//! and with the contract call it makes. How much each of them spends is set by
+//! the network, so it is given as a [`GasSchedule`] by the caller.
+
+use alloc::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{
+    select::CoinSelector, types, utils::Node, WalletError, MAX_INPUT_NOTES,
+    MAX_OUTPUT_NOTES,
+};
+
+/// The minimum gas price accepted by the network.
+pub const MIN_GAS_PRICE: u64 = 1;
+
+/// The gas spent by each part of a transaction, as charged by the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasSchedule {
+    /// The gas spent by any transaction, verifying its proof.
+    pub tx: u64,
+    /// The gas spent for each input note.
+    pub input: u64,
+    /// The gas spent for each output note.
+    pub output: u64,
+    /// The gas spent by a contract call, besides its payload.
+    pub call: u64,
+    /// The gas spent for each byte of the payload of a contract call.
+    pub payload_byte: u64,
+    /// The gas spent by the stake contract to verify a STCT or WFCT proof.
+    pub stake: u64,
+    /// The gas spent by the stake contract to withdraw the reward.
+    pub withdraw: u64,
+}
+
+impl From<types::GasSchedule> for GasSchedule {
+    fn from(schedule: types::GasSchedule) -> Self {
+        Self {
+            tx: schedule.tx,
+            input: schedule.input,
+            output: schedule.output,
+            call: schedule.call,
+            payload_byte: schedule.payload_byte,
+            stake: schedule.stake,
+            withdraw: schedule.withdraw,
+        }
+    }
+}
+
+/// The operation performed by a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// A transfer of notes, with no contract call.
+    Transfer,
+    /// A stake of the given value.
+    Stake,
+    /// An unstake of the staked value.
+    Unstake,
+    /// A withdrawal of the stake reward.
+    Withdraw,
+    /// A call to a contract, with a payload of the given length.
+    Call {
+        /// The length of the payload, in bytes.
+        payload_len: u64,
+    },
+}
+
+impl Operation {
+    /// The gas spent by the operation, besides the notes of the transaction.
+    fn gas(&self, schedule: &GasSchedule) -> u64 {
+        match self {
+            Self::Transfer => 0,
+            Self::Stake | Self::Unstake => schedule.stake,
+            Self::Withdraw => schedule.withdraw,
+            Self::Call { payload_len } => schedule.call.saturating_add(
+                payload_len.saturating_mul(schedule.payload_byte),
+            ),
+        }
+    }
+}
+
+/// How much to pay for the gas, over the minimum price of the network.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GasPricePolicy {
+    /// The minimum price.
+    #[default]
+    Minimum,
+    /// Twice the minimum price.
+    Standard,
+    /// Five times the minimum price, for the transaction to be picked up
+    /// first.
+    Priority,
+}
+
+impl GasPricePolicy {
+    /// The gas price given the minimum price of the network.
+    pub fn price(&self, min_price: u64) -> u64 {
+        let factor = match self {
+            Self::Minimum => 1,
+            Self::Standard => 2,
+            Self::Priority => 5,
+        };
+
+        min_price.max(MIN_GAS_PRICE).saturating_mul(factor)
+    }
+}
+
+impl From<types::GasPricePolicy> for GasPricePolicy {
+    fn from(policy: types::GasPricePolicy) -> Self {
+        match policy {
+            types::GasPricePolicy::Minimum => Self::Minimum,
+            types::GasPricePolicy::Standard => Self::Standard,
+            types::GasPricePolicy::Priority => Self::Priority,
+        }
+    }
+}
+
+/// The recommended gas limit of a transaction spending `inputs` notes and
+/// creating `outputs` notes, under the given gas schedule.
+pub fn gas_limit(
+    schedule: &GasSchedule,
+    operation: &Operation,
+    inputs: usize,
+    outputs: usize,
+) -> u64 {
+    schedule
+        .tx
+        .saturating_add(schedule.input.saturating_mul(inputs as u64))
+        .saturating_add(schedule.output.saturating_mul(outputs as u64))
+        .saturating_add(operation.gas(schedule))
+}
+
+/// The estimated fee of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Estimate {
+    /// The recommended gas limit.
+    pub gas_limit: u64,
+    /// The gas price.
+    pub gas_price: u64,
+    /// The maximum fee paid, in lux.
+    pub fee: u64,
+    /// The number of notes spent.
+    pub inputs: usize,
+    /// The number of notes created, change included.
+    pub outputs: usize,
+    /// The largest value that can be sent, after paying the fee of a
+    /// transaction spending the largest notes.
+    pub spendable: u64,
+}
+
+/// Estimates the fee of a transaction performing `operation` with `outputs`
+/// notes, spending the given notes.
+///
+/// If a `value` is given, the notes spent are picked by `selector` to cover it
+/// along with the fee, and a change note is counted if needed. Otherwise the
+/// estimate is for spending the largest notes whole, so for sending the
+/// [`Estimate::spendable`] value.
+#[allow(clippy::too_many_arguments)]
+pub fn estimate<Rng, Sel>(
+    rng: &mut Rng,
+    selector: &Sel,
+    schedule: &GasSchedule,
+    notes: Vec<Node>,
+    operation: Operation,
+    outputs: usize,
+    value: Option<u64>,
+    gas_price: u64,
+) -> Result<Estimate, WalletError>
+where
+    Rng: RngCore + CryptoRng,
+    Sel: CoinSelector,
+{
+    if outputs > MAX_OUTPUT_NOTES {
+        return Err(WalletError::TooManyOutputs);
+    }
+
+    let mut values: Vec<u64> = notes.iter().map(|(_, v, _)| *v).collect();
+    values.sort_by(|a, b| b.cmp(a));
+    values.truncate(MAX_INPUT_NOTES);
+
+    let max_fee = gas_limit(schedule, &operation, values.len(), outputs)
+        .saturating_mul(gas_price);
+    let spendable = values
+        .iter()
+        .fold(0u64, |sum, v| sum.saturating_add(*v))
+        .saturating_sub(max_fee);
+
+    let total = |picked: &[Node]| {
+        picked
+            .iter()
+            .fold(0u64, |sum, (_, v, _)| sum.saturating_add(*v))
+    };
+
+    let (inputs, outputs) = match value {
+        None => (values.len(), outputs),
+        Some(value) => {
+            // the fee grows with the notes, which may in turn take more
+            // inputs to cover, until their count settles
+            let mut count = 1;
+            loop {
+                let fee = gas_limit(schedule, &operation, count, outputs)
+                    .saturating_mul(gas_price);
+                let target = value.saturating_add(fee);
+                let picked = selector
+                    .select(rng, notes.clone(), target)
+                    .ok_or(WalletError::InsufficientBalance)?;
+
+                if picked.len() > count {
+                    count = picked.len();
+                    continue;
+                }
+                if total(&picked) == target {
+                    break (count, outputs);
+                }
+
+                // the change takes another output
+                if outputs == MAX_OUTPUT_NOTES {
+                    return Err(WalletError::TooManyOutputs);
+                }
+
+                let fee = gas_limit(schedule, &operation, count, outputs + 1)
+                    .saturating_mul(gas_price);
+                let picked = selector
+                    .select(rng, notes.clone(), value.saturating_add(fee))
+                    .ok_or(WalletError::InsufficientBalance)?;
+
+                if picked.len() > count {
+                    count = picked.len();
+                    continue;
+                }
+                break (count, outputs + 1);
+            }
+        }
+    };
+
+    let gas_limit = gas_limit(schedule, &operation, inputs, outputs);
+
+    Ok(Estimate {
+        gas_limit,
+        gas_price,
+        fee: gas_limit.saturating_mul(gas_price),
+        inputs,
+        outputs,
+        spendable,
+    })
+}
+
+#[test]
+fn estimate_works() {
+    use crate::select::Strategy;
+    use dusk_jubjub::JubJubScalar;
+    use phoenix_core::{Note, PublicKey, SecretKey};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let pk = PublicKey::from(&SecretKey::random(rng));
+
+    let lux = 1_000_000_000;
+    let nodes: Vec<Node> = [1, 2, 3, 5, 8]
+        .into_iter()
+        .map(|dusk| {
+            let note = Note::transparent(rng, &pk, dusk * lux);
+            (note, dusk * lux, JubJubScalar::zero())
+        })
+        .collect();
+
+    let schedule = GasSchedule {
+        tx: 250_000_000,
+        input: 50_000_000,
+        output: 25_000_000,
+        call: 100_000_000,
+        payload_byte: 10_000,
+        stake: 2_000_000_000,
+        withdraw: 1_000_000_000,
+    };
+
+    let price = GasPricePolicy::Standard.price(0);
+    assert_eq!(price, 2);
+
+    let estimate = |value| {
+        estimate(
+            &mut StdRng::seed_from_u64(7),
+            &Strategy::LargestFirst,
+            &schedule,
+            nodes.clone(),
+            Operation::Transfer,
+            1,
+            value,
+            price,
+        )
+    };
+
+    // the four largest notes are spent whole
+    let max = estimate(None).unwrap();
+    assert_eq!((max.inputs, max.outputs), (4, 1));
+    assert_eq!(max.gas_limit, 475_000_000);
+    assert_eq!(max.fee, 950_000_000);
+    assert_eq!(max.spendable, 18 * lux - max.fee);
+
+    // a single note covers the value, leaving change
+    let one = estimate(Some(7 * lux)).unwrap();
+    assert_eq!((one.inputs, one.outputs), (1, 2));
+    assert_eq!(one.gas_limit, 350_000_000);
+    assert_eq!(one.spendable, max.spendable);
+
+    // the fee takes a second note to cover
+    let two = estimate(Some(8 * lux)).unwrap();
+    assert_eq!(two.inputs, 2);
+
+    // sending the spendable value leaves no change
+    let all = estimate(Some(max.spendable)).unwrap();
+    assert_eq!((all.inputs, all.outputs), (4, 1));
+    assert_eq!(all.fee, max.fee);
+
+    assert_eq!(
+        estimate(Some(max.spendable + 1)),
+        Err(WalletError::InsufficientBalance)
+    );
+
+    let call = Operation::Call { payload_len: 100 };
+    assert_eq!(gas_limit(&schedule, &call, 1, 0), 401_000_000);
+}
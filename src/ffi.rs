@@ -8,14 +8,26 @@
 
 use alloc::{
     alloc::{alloc, dealloc, Layout},
+    string::ToString,
+    vec,
     vec::Vec,
 };
 
-use dusk_bytes::Serializable;
-use phoenix_core::{Fee, Note, SecretKey, ViewKey};
-use sha2::{Digest, Sha512};
-
-use crate::{key, tx, types, utils, MAX_KEY, MAX_LEN};
+use phoenix_core::{Fee, Note, NoteType, SecretKey, ViewKey};
+
+use crate::{
+    address, consolidate, fee,
+    file::{Account, Contact, WalletData},
+    key,
+    offline::{self, Template},
+    preview::Preview,
+    select::Strategy,
+    sync::NoteSync,
+    tx, types,
+    uri::PaymentRequest,
+    utils, BalanceInfo, WalletError, MAX_ACCOUNTS, MAX_INPUT_NOTES, MAX_KEY,
+    MAX_LEN,
+};
 
 /// The alignment of the memory allocated by the FFI.
 ///
@@ -51,16 +63,11 @@ pub fn free_mem(ptr: i32, len: i32) {
 #[no_mangle]
 pub fn seed(args: i32, len: i32) -> i64 {
     let types::SeedArgs { passphrase } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
-    let mut hash = Sha512::new();
-
-    hash.update(passphrase);
-    hash.update(b"SEED");
-
-    let seed = hash.finalize().to_vec();
+    let seed = key::seed_from_passphrase(&passphrase).to_vec();
 
     let (ptr, len) = utils::allocated_copy(seed);
     utils::compose(true, ptr as _, len as _)
@@ -74,52 +81,69 @@ pub fn seed(args: i32, len: i32) -> i64 {
 /// Will return a triplet (status, ptr, len) pointing to JSON string
 /// representing [types::BalanceResult].
 #[no_mangle]
-#[allow(clippy::needless_range_loop)]
 pub fn balance(args: i32, len: i32) -> i64 {
-    let types::BalanceArgs { notes, seed } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+    let types::BalanceArgs {
+        account_count,
+        notes,
+        seed,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let seed = match utils::sanitize_seed(seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    let account_count = match utils::sanitize_account_count(account_count) {
+        Some(c) => c,
+        None => return utils::fail(WalletError::InvalidValue("account_count")),
     };
 
     let notes: Vec<Note> = match rkyv::from_bytes(&notes) {
         Ok(n) => utils::sanitize_notes(n),
-        Err(_) => return utils::fail(),
+        Err(_) => return utils::fail(WalletError::Decode("notes")),
     };
 
-    let keys: [ViewKey; MAX_KEY] =
-        core::array::from_fn(|i| key::derive_vk(&seed, i as _));
+    let keys = key::derive_vks(&seed, account_count);
 
-    let mut values = Vec::with_capacity(notes.len());
-    let mut sum = 0u64;
+    match balances(&keys, notes) {
+        Ok(b) => utils::into_ptr(b),
+        Err(e) => utils::fail(e),
+    }
+}
 
-    'outer: for note in notes {
-        // we iterate all the available keys until one can successfully decrypt
-        // the note. if all fails, returns false
-        for idx in 0..MAX_KEY {
-            if let Ok(v) = note.value(Some(&keys[idx])) {
-                values.push(v);
-                sum = sum.saturating_add(v);
-                continue 'outer;
-            }
-        }
+/// Computes the total balance of the given notes, with only the view keys of
+/// the accounts.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::WatchBalanceArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::BalanceResponse].
+#[no_mangle]
+pub fn watch_balance(args: i32, len: i32) -> i64 {
+    let types::WatchBalanceArgs { notes, view_keys } =
+        match utils::take_args(args, len) {
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
+        };
 
-        return utils::fail();
-    }
+    let keys = match utils::sanitize_view_keys(&view_keys) {
+        Some(k) => k,
+        None => return utils::fail(WalletError::Decode("view_keys")),
+    };
 
-    // the top 4 notes are the maximum value a transaction can have, given the
-    // circuit accepts up to 4 inputs
-    values.sort_by(|a, b| b.cmp(a));
-    let maximum = values.iter().take(4).sum::<u64>();
+    let notes: Vec<Note> = match rkyv::from_bytes(&notes) {
+        Ok(n) => utils::sanitize_notes(n),
+        Err(_) => return utils::fail(WalletError::Decode("notes")),
+    };
 
-    utils::into_ptr(types::BalanceResponse {
-        maximum,
-        value: sum,
-    })
+    match balances(&keys, notes) {
+        Ok(b) => utils::into_ptr(b),
+        Err(e) => utils::fail(e),
+    }
 }
 
 /// Computes a serialized unproven transaction from the given arguments.
@@ -133,139 +157,97 @@ pub fn balance(args: i32, len: i32) -> i64 {
 pub fn execute(args: i32, len: i32) -> i64 {
     let types::ExecuteArgs {
         call,
+        coin_selection,
         crossover,
         fee,
         inputs,
         openings,
         output,
+        outputs,
         gas_limit,
         gas_price,
         refund,
+        refund_note_type,
         rng_seed,
+        send_all,
         sender_index,
         seed,
     } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
-    };
-
-    let inputs: Vec<Note> = match rkyv::from_bytes(&inputs) {
-        Ok(n) => utils::sanitize_notes(n),
-        Err(_) => return utils::fail(),
-    };
-
-    let fee: Option<Fee> =
-        fee.and_then(|fee| match rkyv::from_bytes::<Fee>(&fee) {
-            Ok(n) => Some(n),
-            Err(_) => None,
-        });
-
-    let openings: Vec<(tx::Opening, u64)> = match rkyv::from_bytes(&openings) {
-        Ok(n) => n,
-        Err(_) => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let seed = match utils::sanitize_seed(seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("seed")),
     };
 
     let rng_seed: [u8; 32] = match utils::sanitize_rng_seed(rng_seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("rng_seed")),
     };
 
-    let value = output.as_ref().map(|o| o.value).unwrap_or(0);
-    let total_output = gas_limit
-        .saturating_mul(gas_price)
-        .saturating_add(value)
-        .saturating_add(crossover.clone().map(|c| c.value).unwrap_or_default());
-
-    let mut full_inputs = Vec::with_capacity(inputs.len());
+    let refund = match utils::bs58_to_pk(&refund) {
+        Some(r) => r,
+        None => return utils::fail(WalletError::InvalidAddress("refund")),
+    };
 
     let sk = key::derive_sk(&seed, sender_index);
     let vk = ViewKey::from(&sk);
 
-    'outer: for input in inputs {
-        if let Ok(value) = input.value(Some(&vk)) {
-            let opening =
-                match openings.iter().find(|(_, pos)| input.pos() == pos) {
-                    Some(a) => a.0,
-                    None => {
-                        return utils::fail();
-                    }
-                };
-
-            let blinder = match input.blinding_factor(Some(&vk)).ok() {
-                Some(a) => a,
-                None => return utils::fail(),
-            };
-
-            full_inputs.push((input, opening, value, blinder));
-            continue 'outer;
-        }
-
-        return utils::fail();
-    }
-
-    // optimizes the inputs given the total amount
-    let inputs = match utils::inputs(full_inputs, total_output) {
-        Some(k) => k,
-        None => return utils::fail(),
-    };
-
-    let inputs: Vec<_> = inputs
-        .into_iter()
-        .map(|(note, opening, value, _)| tx::PreInput {
-            note,
-            opening,
-            value,
-            sk: &sk,
-        })
-        .collect();
-
-    let total_input: u64 = inputs.iter().map(|i| i.value).sum();
-    let total_refund = total_input.saturating_sub(total_output);
-
-    let mut outputs = Vec::with_capacity(2);
-    if total_refund > 0 {
-        outputs.push(types::ExecuteOutput {
-            note_type: types::OutputType::Obfuscated,
-            receiver: refund.clone(),
-            ref_id: None,
-            value: total_refund,
-        });
-    }
-    if let Some(o) = output {
-        outputs.push(o);
-    }
-
-    let rng: &mut rand_chacha::ChaCha12Rng = &mut utils::rng(rng_seed);
-    let actual_fee;
-    let refund = match utils::bs58_to_pk(&refund) {
-        Some(r) => r,
-        None => return utils::fail(),
+    let spent = gas_limit.saturating_mul(gas_price).saturating_add(
+        crossover.as_ref().map(|c| c.value).unwrap_or_default(),
+    );
+    let Spend {
+        notes,
+        openings,
+        outputs,
+        fee,
+    } = match spend(
+        &vk,
+        &inputs,
+        &openings,
+        fee,
+        output,
+        outputs,
+        send_all.unwrap_or_default(),
+        spent,
+    ) {
+        Ok(s) => s,
+        Err(e) => return utils::fail(e),
     };
 
-    if let Some(fee) = fee {
-        actual_fee = fee;
-    } else {
-        actual_fee = Fee::new(rng, gas_limit, gas_price, &refund);
-    }
-
-    let tx = tx::UnprovenTransaction::new(
-        rng, inputs, outputs, actual_fee, crossover, call,
+    // only the openings of the picked inputs are required
+    let opening = |pos| find_opening(&openings, pos);
+
+    let rng = &mut utils::rng(rng_seed);
+    let selector = coin_selection.map(Strategy::from).unwrap_or_default();
+
+    let tx = tx::execute(
+        rng,
+        &sk,
+        notes,
+        &selector,
+        opening,
+        &refund,
+        refund_note_type.unwrap_or(types::OutputType::Obfuscated),
+        outputs,
+        gas_limit,
+        gas_price,
+        fee,
+        crossover,
+        call,
     );
 
     let tx = match tx {
-        Some(t) => t,
-        None => return utils::fail(),
+        Ok(t) => t,
+        Err(e) => return utils::fail(e),
     };
 
     let tx = match rkyv::to_bytes::<tx::UnprovenTransaction, MAX_LEN>(&tx).ok()
     {
         Some(t) => t.to_vec(),
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::Encode("tx")),
     };
 
     utils::into_ptr(types::ExecuteResponse { tx })
@@ -281,8 +263,8 @@ pub fn execute(args: i32, len: i32) -> i64 {
 #[no_mangle]
 pub fn merge_notes(args: i32, len: i32) -> i64 {
     let types::MergeNotesArgs { notes } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let mut list = Vec::with_capacity(10);
@@ -290,7 +272,7 @@ pub fn merge_notes(args: i32, len: i32) -> i64 {
         if !notes.is_empty() {
             match rkyv::from_bytes::<Vec<Note>>(&notes) {
                 Ok(n) => list.extend(n),
-                Err(_) => return utils::fail(),
+                Err(_) => return utils::fail(WalletError::Decode("notes")),
             };
         }
     }
@@ -312,13 +294,13 @@ pub fn merge_notes(args: i32, len: i32) -> i64 {
 pub fn filter_notes(args: i32, len: i32) -> i64 {
     let types::FilterNotesArgs { flags, notes } =
         match utils::take_args(args, len) {
-            Some(a) => a,
-            None => return utils::fail(),
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
         };
 
     let notes: Vec<Note> = match rkyv::from_bytes(&notes) {
         Ok(n) => n,
-        Err(_) => return utils::fail(),
+        Err(_) => return utils::fail(WalletError::Decode("notes")),
     };
 
     let notes: Vec<_> = notes
@@ -340,24 +322,135 @@ pub fn filter_notes(args: i32, len: i32) -> i64 {
 /// representing [types::PublicKeysResponse].
 #[no_mangle]
 pub fn public_keys(args: i32, len: i32) -> i64 {
-    let types::PublicKeysArgs { seed } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+    let types::PublicKeysArgs {
+        account_count,
+        checksum,
+        seed,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let seed = match utils::sanitize_seed(seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    let account_count = match utils::sanitize_account_count(account_count) {
+        Some(c) => c,
+        None => return utils::fail(WalletError::InvalidValue("account_count")),
     };
 
-    let keys = (0..MAX_KEY)
+    let checksum = checksum.unwrap_or(false);
+    let keys = (0..account_count)
         .map(|idx| key::derive_pk(&seed, idx as u64))
-        .map(|pk| bs58::encode(pk.to_bytes()).into_string())
+        .map(|pk| address::encode(&pk, checksum))
         .collect();
 
     utils::into_ptr(types::PublicKeysResponse { keys })
 }
 
+/// Validates a Base58 address, plain or checksummed, telling why it is
+/// invalid if it is.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::ValidateAddressArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::ValidateAddressResponse].
+#[no_mangle]
+pub fn validate_address(args: i32, len: i32) -> i64 {
+    let types::ValidateAddressArgs { address } =
+        match utils::take_args(args, len) {
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
+        };
+
+    let response = match address::decode(&address) {
+        Ok((_, checksummed)) => types::ValidateAddressResponse {
+            valid: true,
+            checksummed,
+            reason: None,
+            position: None,
+        },
+        Err(e) => types::ValidateAddressResponse {
+            valid: false,
+            checksummed: false,
+            reason: Some(e.reason().to_string()),
+            position: e.position().map(|p| p as u64),
+        },
+    };
+
+    utils::into_ptr(response)
+}
+
+/// Encodes a request for a payment into a `dusk:` URI.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::PaymentUriArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::PaymentUriResponse].
+#[no_mangle]
+pub fn payment_uri(args: i32, len: i32) -> i64 {
+    let types::PaymentUriArgs {
+        expiry,
+        note_type,
+        receiver,
+        ref_id,
+        value,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    if address::decode(&receiver).is_err() {
+        return utils::fail(WalletError::InvalidAddress("receiver"));
+    }
+
+    let request = PaymentRequest {
+        receiver,
+        value,
+        note_type: note_type.unwrap_or(types::OutputType::Obfuscated),
+        ref_id,
+        expiry,
+    };
+
+    utils::into_ptr(types::PaymentUriResponse {
+        uri: request.to_uri(),
+    })
+}
+
+/// Builds the output paying the request of a `dusk:` URI, to be passed to
+/// [execute].
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::PaymentOutputArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::PaymentOutputResponse].
+#[no_mangle]
+pub fn payment_output(args: i32, len: i32) -> i64 {
+    let types::PaymentOutputArgs { now, uri, value } =
+        match utils::take_args(args, len) {
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
+        };
+
+    let request = match PaymentRequest::from_uri(&uri) {
+        Ok(r) => r,
+        Err(e) => return utils::fail(e),
+    };
+    let expiry = request.expiry;
+
+    match request.output(value, now) {
+        Ok(output) => {
+            utils::into_ptr(types::PaymentOutputResponse { expiry, output })
+        }
+        Err(e) => utils::fail(e),
+    }
+}
+
 /// Returns a list of [ViewKey] that belongs to this wallet.
 ///
 /// Expects as argument a fat pointer to a JSON string representing
@@ -367,19 +460,25 @@ pub fn public_keys(args: i32, len: i32) -> i64 {
 /// [`Vec<ViewKey>`].
 #[no_mangle]
 pub fn view_keys(args: i32, len: i32) -> i64 {
-    let types::ViewKeysArgs { seed } = match utils::take_args(args, len) {
-        Some(a) => a,
-        None => return utils::fail(),
+    let types::ViewKeysArgs {
+        account_count,
+        seed,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
     };
 
     let seed = match utils::sanitize_seed(seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("seed")),
     };
 
-    let keys: Vec<_> = (0..MAX_KEY)
-        .map(|idx| key::derive_vk(&seed, idx as u64))
-        .collect();
+    let account_count = match utils::sanitize_account_count(account_count) {
+        Some(c) => c,
+        None => return utils::fail(WalletError::InvalidValue("account_count")),
+    };
+
+    let keys = key::derive_vks(&seed, account_count);
 
     utils::rkyv_into_ptr(keys)
 }
@@ -394,32 +493,40 @@ pub fn view_keys(args: i32, len: i32) -> i64 {
 /// [Vec<dusk_jubjub::BlsScalar>].
 #[no_mangle]
 pub fn nullifiers(args: i32, len: i32) -> i64 {
-    let types::NullifiersArgs { notes, seed } =
-        match utils::take_args(args, len) {
-            Some(a) => a,
-            None => return utils::fail(),
-        };
+    let types::NullifiersArgs {
+        account_count,
+        notes,
+        seed,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
 
     let notes: Vec<Note> = match rkyv::from_bytes(&notes) {
         Ok(n) => n,
-        Err(_) => return utils::fail(),
+        Err(_) => return utils::fail(WalletError::Decode("notes")),
     };
 
     let seed = match utils::sanitize_seed(seed) {
         Some(s) => s,
-        None => return utils::fail(),
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    let account_count = match utils::sanitize_account_count(account_count) {
+        Some(c) => c,
+        None => return utils::fail(WalletError::InvalidValue("account_count")),
     };
 
     let mut nullifiers = Vec::with_capacity(notes.len());
 
-    let sks: [SecretKey; MAX_KEY] =
-        core::array::from_fn(|i| key::derive_sk(&seed, i as _));
-    let vks: [ViewKey; MAX_KEY] =
-        core::array::from_fn(|i| key::derive_vk(&seed, i as _));
+    let sks: Vec<SecretKey> = (0..account_count)
+        .map(|idx| key::derive_sk(&seed, idx as _))
+        .collect();
+    let vks: Vec<ViewKey> = sks.iter().map(ViewKey::from).collect();
 
     for note in notes {
-        let Some(idx) = vks.iter().position(|vk| vk.owns(&note)) else {
-            return utils::fail();
+        let Some(idx) = key::owner_index(&vks, &note) else {
+            return utils::fail(WalletError::NoteOwnership("notes"));
         };
 
         nullifiers.push(note.gen_nullifier(&sks[idx]));
@@ -427,3 +534,678 @@ pub fn nullifiers(args: i32, len: i32) -> i64 {
 
     utils::rkyv_into_ptr(nullifiers)
 }
+
+/// Encrypts the seed, the accounts and the synchronized notes of the wallet
+/// into a wallet file.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::WalletExportArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::WalletExportResponse].
+#[no_mangle]
+pub fn wallet_export(args: i32, len: i32) -> i64 {
+    let types::WalletExportArgs {
+        accounts,
+        checkpoint,
+        contacts,
+        password,
+        rng_seed,
+        seed,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let seed = match utils::sanitize_seed(seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    let rng_seed = match utils::sanitize_rng_seed(rng_seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("rng_seed")),
+    };
+
+    // the notes are synchronized for every account up to the highest
+    let accounts: Option<Vec<Account>> = accounts
+        .unwrap_or_default()
+        .into_iter()
+        .map(|types::WalletAccountType { index, label }| {
+            (index < MAX_ACCOUNTS as u64).then_some(Account { index, label })
+        })
+        .collect();
+
+    let accounts = match accounts {
+        Some(a) => a,
+        None => return utils::fail(WalletError::InvalidValue("accounts")),
+    };
+
+    let mut book = Vec::new();
+    for types::WalletContactType { address, label } in
+        contacts.unwrap_or_default()
+    {
+        if address::decode(&address).is_err() {
+            return utils::fail(WalletError::InvalidAddress("contacts"));
+        }
+        book.push(Contact { label, address });
+    }
+
+    let notes = match checkpoint {
+        Some(c) => match NoteSync::from_checkpoint(&c) {
+            Ok(n) => n,
+            Err(e) => return utils::fail(e),
+        },
+        None => {
+            let count = accounts.iter().map(|a| a.index as usize + 1).max();
+            NoteSync::new(count.unwrap_or(MAX_KEY))
+        }
+    };
+
+    let data = WalletData {
+        seed,
+        accounts,
+        contacts: book,
+        notes,
+    };
+
+    let mut rng = utils::rng(rng_seed);
+
+    match data.encrypt(&mut rng, password.as_bytes()) {
+        Ok(wallet) => utils::into_ptr(types::WalletExportResponse { wallet }),
+        Err(e) => utils::fail(e),
+    }
+}
+
+/// Decrypts a wallet file created by [wallet_export].
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::WalletImportArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::WalletImportResponse].
+#[no_mangle]
+pub fn wallet_import(args: i32, len: i32) -> i64 {
+    let types::WalletImportArgs { password, wallet } =
+        match utils::take_args(args, len) {
+            Ok(a) => a,
+            Err(e) => return utils::fail(e),
+        };
+
+    let WalletData {
+        seed,
+        accounts,
+        contacts,
+        notes,
+    } = match WalletData::decrypt(&wallet, password.as_bytes()) {
+        Ok(d) => d,
+        Err(e) => return utils::fail(e),
+    };
+
+    let checkpoint = match notes.checkpoint() {
+        Ok(c) => c,
+        Err(e) => return utils::fail(e),
+    };
+
+    let accounts = accounts
+        .into_iter()
+        .map(|Account { index, label }| types::WalletAccountType {
+            index,
+            label,
+        })
+        .collect();
+
+    let contacts = contacts
+        .into_iter()
+        .map(|Contact { label, address }| types::WalletContactType {
+            address,
+            label,
+        })
+        .collect();
+
+    utils::into_ptr(types::WalletImportResponse {
+        accounts,
+        checkpoint,
+        contacts,
+        seed: seed.to_vec(),
+    })
+}
+
+/// Plans the consolidation of the notes of the sender, building the
+/// transactions of its first round.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::ConsolidateArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::ConsolidateResponse].
+#[no_mangle]
+pub fn consolidate(args: i32, len: i32) -> i64 {
+    let types::ConsolidateArgs {
+        gas_limit,
+        gas_price,
+        inputs,
+        openings,
+        rng_seed,
+        sender_index,
+        seed,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let inputs: Vec<Note> = match rkyv::from_bytes(&inputs) {
+        Ok(n) => utils::sanitize_notes(n),
+        Err(_) => return utils::fail(WalletError::Decode("inputs")),
+    };
+
+    let openings: Vec<(tx::Opening, u64)> = match rkyv::from_bytes(&openings) {
+        Ok(n) => n,
+        Err(_) => return utils::fail(WalletError::Decode("openings")),
+    };
+
+    let seed = match utils::sanitize_seed(seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    let rng_seed: [u8; 32] = match utils::sanitize_rng_seed(rng_seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("rng_seed")),
+    };
+
+    let sk = key::derive_sk(&seed, sender_index);
+
+    let notes = match nodes(inputs, &ViewKey::from(&sk)) {
+        Ok(n) => n,
+        Err(e) => return utils::fail(e),
+    };
+
+    let opening = |pos| find_opening(&openings, pos);
+
+    let rng = &mut utils::rng(rng_seed);
+    let consolidation = match consolidate::consolidate(
+        rng, &sk, notes, opening, gas_limit, gas_price,
+    ) {
+        Ok(c) => c,
+        Err(e) => return utils::fail(e),
+    };
+
+    let mut txs = Vec::with_capacity(consolidation.transactions.len());
+    for tx in consolidation.transactions {
+        match rkyv::to_bytes::<tx::UnprovenTransaction, MAX_LEN>(&tx) {
+            Ok(t) => txs.push(t.to_vec()),
+            Err(_) => return utils::fail(WalletError::Encode("txs")),
+        }
+    }
+
+    utils::into_ptr(types::ConsolidateResponse {
+        dust: consolidation.dust,
+        fee: consolidation.fee,
+        rounds: consolidation.rounds,
+        transactions: consolidation.total_transactions,
+        txs,
+    })
+}
+
+/// Estimates the fee of a transaction, and the value spendable after it.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::EstimateFeeArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::EstimateFeeResponse].
+#[no_mangle]
+pub fn estimate_fee(args: i32, len: i32) -> i64 {
+    let types::EstimateFeeArgs {
+        coin_selection,
+        gas_price,
+        gas_price_policy,
+        gas_schedule,
+        inputs,
+        operation,
+        output_count,
+        payload_len,
+        rng_seed,
+        value,
+        view_key,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let inputs: Vec<Note> = match rkyv::from_bytes(&inputs) {
+        Ok(n) => utils::sanitize_notes(n),
+        Err(_) => return utils::fail(WalletError::Decode("inputs")),
+    };
+
+    let vk: ViewKey = match rkyv::from_bytes(&view_key) {
+        Ok(k) => k,
+        Err(_) => return utils::fail(WalletError::Decode("view_key")),
+    };
+
+    let selector = coin_selection.map(Strategy::from).unwrap_or_default();
+
+    // only the random selection draws from the rng, so only it needs a seed
+    let rng_seed: [u8; 32] = match rng_seed.map(utils::sanitize_rng_seed) {
+        Some(Some(s)) => s,
+        None if selector != Strategy::Random => [0; 32],
+        _ => return utils::fail(WalletError::InvalidLength("rng_seed")),
+    };
+
+    let operation = match operation {
+        types::FeeOperation::Transfer => fee::Operation::Transfer,
+        types::FeeOperation::Stake => fee::Operation::Stake,
+        types::FeeOperation::Unstake => fee::Operation::Unstake,
+        types::FeeOperation::Withdraw => fee::Operation::Withdraw,
+        types::FeeOperation::Call => fee::Operation::Call {
+            payload_len: payload_len.unwrap_or_default(),
+        },
+    };
+
+    let outputs = match (output_count, operation) {
+        (Some(n), _) => usize::try_from(n).unwrap_or(usize::MAX),
+        (None, fee::Operation::Transfer) => 1,
+        (None, _) => 0,
+    };
+
+    let notes = match nodes(inputs, &vk) {
+        Ok(n) => n,
+        Err(e) => return utils::fail(e),
+    };
+
+    let schedule = fee::GasSchedule::from(gas_schedule);
+    let gas_price = gas_price_policy
+        .map(fee::GasPricePolicy::from)
+        .unwrap_or_default()
+        .price(gas_price.unwrap_or(fee::MIN_GAS_PRICE));
+
+    let rng = &mut utils::rng(rng_seed);
+    let estimate = match fee::estimate(
+        rng, &selector, &schedule, notes, operation, outputs, value, gas_price,
+    ) {
+        Ok(e) => e,
+        Err(e) => return utils::fail(e),
+    };
+
+    utils::into_ptr(types::EstimateFeeResponse {
+        fee: estimate.fee,
+        gas_limit: estimate.gas_limit,
+        gas_price: estimate.gas_price,
+        input_count: estimate.inputs as u64,
+        output_count: estimate.outputs as u64,
+        spendable: estimate.spendable,
+    })
+}
+
+/// Summarizes an unproven transaction for the user to review it, flagging
+/// anything worth a warning.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::PreviewTxArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::PreviewTxResponse].
+#[no_mangle]
+pub fn preview_tx(args: i32, len: i32) -> i64 {
+    let types::PreviewTxArgs {
+        sender_index,
+        seed,
+        tx,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let tx: tx::UnprovenTransaction = match rkyv::from_bytes(&tx) {
+        Ok(t) => t,
+        Err(_) => return utils::fail(WalletError::Decode("tx")),
+    };
+
+    let vk = match seed.map(utils::sanitize_seed) {
+        Some(Some(s)) => {
+            Some(key::derive_vk(&s, sender_index.unwrap_or_default()))
+        }
+        Some(None) => return utils::fail(WalletError::InvalidLength("seed")),
+        None => None,
+    };
+
+    let preview = Preview::new(&tx, vk.as_ref());
+
+    utils::into_ptr(types::PreviewTxResponse::from(preview))
+}
+
+/// Builds an unsigned transaction template, to be signed offline with
+/// [sign_template]. Takes the view key of the sender in place of the seed.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::ExecuteTemplateArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::ExecuteTemplateResponse].
+#[no_mangle]
+pub fn execute_template(args: i32, len: i32) -> i64 {
+    let types::ExecuteTemplateArgs {
+        call,
+        coin_selection,
+        crossover,
+        fee,
+        inputs,
+        openings,
+        output,
+        outputs,
+        gas_limit,
+        gas_price,
+        refund,
+        refund_note_type,
+        rng_seed,
+        send_all,
+        view_key,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let vk: ViewKey = match rkyv::from_bytes(&view_key) {
+        Ok(vk) => vk,
+        Err(_) => return utils::fail(WalletError::Decode("view_key")),
+    };
+
+    let rng_seed: [u8; 32] = match utils::sanitize_rng_seed(rng_seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("rng_seed")),
+    };
+
+    let refund = match utils::bs58_to_pk(&refund) {
+        Some(r) => r,
+        None => return utils::fail(WalletError::InvalidAddress("refund")),
+    };
+
+    let spent = gas_limit.saturating_mul(gas_price).saturating_add(
+        crossover.as_ref().map(|c| c.value).unwrap_or_default(),
+    );
+    let Spend {
+        notes,
+        openings,
+        outputs,
+        fee,
+    } = match spend(
+        &vk,
+        &inputs,
+        &openings,
+        fee,
+        output,
+        outputs,
+        send_all.unwrap_or_default(),
+        spent,
+    ) {
+        Ok(s) => s,
+        Err(e) => return utils::fail(e),
+    };
+
+    let opening = |pos| find_opening(&openings, pos);
+
+    let rng = &mut utils::rng(rng_seed);
+    let selector = coin_selection.map(Strategy::from).unwrap_or_default();
+
+    let template = offline::template(
+        rng,
+        notes,
+        &selector,
+        opening,
+        &refund,
+        refund_note_type.unwrap_or(types::OutputType::Obfuscated),
+        outputs,
+        gas_limit,
+        gas_price,
+        fee,
+        crossover,
+        call,
+    );
+
+    let template = match template.and_then(|t| t.to_transport_bytes()) {
+        Ok(t) => t,
+        Err(e) => return utils::fail(e),
+    };
+
+    utils::into_ptr(types::ExecuteTemplateResponse { template })
+}
+
+/// Signs a transaction template built by [execute_template], needing no
+/// other state than the seed.
+///
+/// Expects as argument a fat pointer to a JSON string representing
+/// [types::SignTemplateArgs].
+///
+/// Will return a triplet (status, ptr, len) pointing to JSON string
+/// representing [types::SignTemplateResponse].
+#[no_mangle]
+pub fn sign_template(args: i32, len: i32) -> i64 {
+    let types::SignTemplateArgs {
+        rng_seed,
+        sender_index,
+        seed,
+        template,
+    } = match utils::take_args(args, len) {
+        Ok(a) => a,
+        Err(e) => return utils::fail(e),
+    };
+
+    let seed = match utils::sanitize_seed(seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("seed")),
+    };
+
+    let rng_seed: [u8; 32] = match utils::sanitize_rng_seed(rng_seed) {
+        Some(s) => s,
+        None => return utils::fail(WalletError::InvalidLength("rng_seed")),
+    };
+
+    let template = match Template::from_transport_bytes(&template) {
+        Ok(t) => t,
+        Err(e) => return utils::fail(e),
+    };
+
+    let sk = key::derive_sk(&seed, sender_index);
+    let rng = &mut utils::rng(rng_seed);
+
+    let tx = match template.sign(rng, &sk) {
+        Ok(t) => t,
+        Err(e) => return utils::fail(e),
+    };
+
+    let preview = Preview::new(&tx, Some(&ViewKey::from(&sk)));
+
+    let tx = match rkyv::to_bytes::<tx::UnprovenTransaction, MAX_LEN>(&tx).ok()
+    {
+        Some(t) => t.to_vec(),
+        None => return utils::fail(WalletError::Encode("tx")),
+    };
+
+    utils::into_ptr(types::SignTemplateResponse {
+        preview: preview.into(),
+        tx,
+    })
+}
+
+/// Sums the values of the notes owned by each of the accounts of `keys`.
+fn balances(
+    keys: &[ViewKey],
+    notes: Vec<Note>,
+) -> Result<types::BalanceResponse, WalletError> {
+    let mut values = vec![Vec::new(); keys.len()];
+
+    for note in notes {
+        // the note must be owned by one of the accounts, and decryptable by
+        // its key
+        let (idx, value) = key::owner_index(keys, &note)
+            .and_then(|idx| Some((idx, note.value(Some(&keys[idx])).ok()?)))
+            .ok_or(WalletError::NoteOwnership("notes"))?;
+
+        values[idx].push((note.note(), value));
+    }
+
+    let sum = |values: &[(NoteType, u64)], note_type| {
+        values
+            .iter()
+            .filter(|(t, _)| *t == note_type)
+            .fold(0u64, |sum, (_, v)| sum.saturating_add(*v))
+    };
+
+    let accounts: Vec<_> = values
+        .into_iter()
+        .enumerate()
+        .map(|(index, values)| {
+            let transparent = sum(&values, NoteType::Transparent);
+            let obfuscated = sum(&values, NoteType::Obfuscated);
+
+            let BalanceInfo { value, maximum } = BalanceInfo::from_values(
+                values.into_iter().map(|(_, v)| v).collect(),
+            );
+
+            types::AccountBalanceType {
+                index: index as u64,
+                maximum,
+                obfuscated,
+                transparent,
+                value,
+            }
+        })
+        .collect();
+
+    let maximum = accounts.iter().map(|a| a.maximum).max().unwrap_or(0);
+    let total = |f: fn(&types::AccountBalanceType) -> u64| {
+        accounts
+            .iter()
+            .fold(0u64, |sum, a| sum.saturating_add(f(a)))
+    };
+
+    Ok(types::BalanceResponse {
+        maximum,
+        obfuscated: total(|a| a.obfuscated),
+        transparent: total(|a| a.transparent),
+        value: total(|a| a.value),
+        accounts,
+    })
+}
+
+/// The notes, openings, outputs and fee of a transaction, decoded from the
+/// arguments shared by `execute` and `execute_template`.
+struct Spend {
+    notes: Vec<utils::Node>,
+    openings: Vec<(tx::Opening, u64)>,
+    outputs: Vec<types::ExecuteOutput>,
+    fee: Option<Fee>,
+}
+
+/// Decodes the inputs owned by `vk` along with their openings, the outputs and
+/// the fee of a transaction. If `send_all` is set, the largest inputs are
+/// picked to be spent whole, paying their value net of `spent` to the single
+/// output.
+#[allow(clippy::too_many_arguments)]
+fn spend(
+    vk: &ViewKey,
+    inputs: &[u8],
+    openings: &[u8],
+    fee: Option<Vec<u8>>,
+    output: Option<types::ExecuteOutput>,
+    outputs: Option<Vec<types::ExecuteOutput>>,
+    send_all: bool,
+    spent: u64,
+) -> Result<Spend, WalletError> {
+    let inputs: Vec<Note> = rkyv::from_bytes(inputs)
+        .map(utils::sanitize_notes)
+        .map_err(|_| WalletError::Decode("inputs"))?;
+
+    let mut outputs: Vec<types::ExecuteOutput> = output
+        .into_iter()
+        .chain(outputs.unwrap_or_default())
+        .collect();
+
+    let fee: Option<Fee> = fee
+        .map(|fee| rkyv::from_bytes::<Fee>(&fee))
+        .transpose()
+        .map_err(|_| WalletError::Decode("fee"))?;
+
+    let openings: Vec<(tx::Opening, u64)> = rkyv::from_bytes(openings)
+        .map_err(|_| WalletError::Decode("openings"))?;
+
+    let mut notes = nodes(inputs, vk)?;
+    if send_all {
+        notes = spend_all(notes, &mut outputs, fee.as_ref(), spent)?;
+    }
+
+    Ok(Spend {
+        notes,
+        openings,
+        outputs,
+        fee,
+    })
+}
+
+/// Picks the largest notes to be spent whole, paying their value net of the
+/// `spent` fee and crossover to the single output.
+///
+/// The fee is computed from the gas limit and price, so a `fee` given apart
+/// is rejected.
+fn spend_all(
+    mut notes: Vec<utils::Node>,
+    outputs: &mut [types::ExecuteOutput],
+    fee: Option<&Fee>,
+    spent: u64,
+) -> Result<Vec<utils::Node>, WalletError> {
+    let output = match outputs {
+        [output] => output,
+        _ => return Err(WalletError::InvalidValue("outputs")),
+    };
+
+    if fee.is_some() {
+        return Err(WalletError::InvalidValue("fee"));
+    }
+
+    notes.sort_by(|(_, a, _), (_, b, _)| b.cmp(a));
+    notes.truncate(MAX_INPUT_NOTES);
+
+    let maximum = notes
+        .iter()
+        .fold(0u64, |sum, (_, value, _)| sum.saturating_add(*value));
+
+    output.value = match maximum.checked_sub(spent) {
+        Some(v) if v > 0 => v,
+        _ => return Err(WalletError::InsufficientBalance),
+    };
+
+    Ok(notes)
+}
+
+/// Decrypts the values and blinding factors of the input notes.
+fn nodes(
+    inputs: Vec<Note>,
+    vk: &ViewKey,
+) -> Result<Vec<utils::Node>, WalletError> {
+    inputs
+        .into_iter()
+        .map(|input| {
+            let value = input
+                .value(Some(vk))
+                .map_err(|_| WalletError::NoteOwnership("inputs"))?;
+            let blinder = input
+                .blinding_factor(Some(vk))
+                .map_err(|_| WalletError::NoteOwnership("inputs"))?;
+
+            Ok((input, value, blinder))
+        })
+        .collect()
+}
+
+/// Finds the opening of the note at the given position.
+fn find_opening(
+    openings: &[(tx::Opening, u64)],
+    pos: u64,
+) -> Result<tx::Opening, WalletError> {
+    openings
+        .iter()
+        .find(|(_, p)| *p == pos)
+        .map(|(opening, _)| *opening)
+        .ok_or(WalletError::MissingOpening)
+}
@@ -0,0 +1,414 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Password encrypted wallet file format.
+//!
+//! A wallet file holds the seed, the metadata of the accounts in use, the
+//! address book and the cache of the owned notes, encrypted with
+//! ChaCha20-Poly1305 under a key derived from the password with Argon2id. It is
+//! laid out as:
+//!
+//! | field       | size |
+//! |-------------|------|
+//! | magic       | 4    |
+//! | version     | 1    |
+//! | memory cost | 4    |
+//! | time cost   | 4    |
+//! | parallelism | 4    |
+//! | salt        | 16   |
+//! | nonce       | 12   |
+//! | ciphertext  | ..   |
+//!
+//! The integers are little-endian, and the whole header is authenticated
+//! along with the ciphertext. Files of version 1, predating the address book,
+//! are still read, with an empty address book.
+
+use alloc::{string::String, vec::Vec};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use bytecheck::CheckBytes;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand_core::{CryptoRng, RngCore};
+use rkyv::{AlignedVec, Archive, Deserialize, Serialize};
+
+use crate::{
+    sync::{NoteSync, NoteSyncV1},
+    WalletError, MAX_LEN, RNG_SEED,
+};
+
+/// The magic bytes a wallet file starts with.
+pub const MAGIC: [u8; 4] = *b"DUSK";
+
+/// The version of the wallet file format.
+pub const VERSION: u8 = 3;
+
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 12;
+const KEY_SIZE: usize = 32;
+const HEADER_SIZE: usize = MAGIC.len() + 1 + 3 * 4 + SALT_SIZE + NONCE_SIZE;
+
+/// The maximum memory cost of the key derivation, in KiB.
+pub const MAX_MEMORY: u32 = 256 * 1024;
+
+/// The maximum number of iterations of the key derivation.
+pub const MAX_ITERATIONS: u32 = 16;
+
+/// The parameters of the Argon2id key derivation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// The memory cost, in KiB.
+    pub memory: u32,
+    /// The number of iterations.
+    pub iterations: u32,
+    /// The degree of parallelism. Only 1 is supported, since the derivation
+    /// runs on a single thread.
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Derives the key from the password, failing if the parameters are out
+    /// of bounds.
+    ///
+    /// The parameters of a file are read before it is authenticated, so they
+    /// are bounded to keep a crafted file from exhausting the memory or the
+    /// time of the host.
+    fn derive_key(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+    ) -> Result<[u8; KEY_SIZE], WalletError> {
+        if self.memory > MAX_MEMORY
+            || self.iterations > MAX_ITERATIONS
+            || self.parallelism != 1
+        {
+            return Err(WalletError::InvalidValue("kdf"));
+        }
+
+        let params = Params::new(
+            self.memory,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_SIZE),
+        )
+        .map_err(|_| WalletError::InvalidValue("kdf"))?;
+
+        let mut key = [0u8; KEY_SIZE];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password, salt, &mut key)
+            .map_err(|_| WalletError::InvalidValue("kdf"))?;
+
+        Ok(key)
+    }
+}
+
+/// The metadata of an account of the wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Account {
+    /// The index the keys of the account are derived with.
+    pub index: u64,
+    /// The name given to the account by the user.
+    pub label: String,
+}
+
+/// A labeled entry of the address book.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Contact {
+    /// The name given to the address by the user.
+    pub label: String,
+    /// The Base58 address, plain or checksummed.
+    pub address: String,
+}
+
+/// The contents of a wallet file.
+#[derive(Debug, Clone, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct WalletData {
+    /// The seed the keys of the wallet are derived from.
+    pub seed: [u8; RNG_SEED],
+    /// The accounts in use.
+    pub accounts: Vec<Account>,
+    /// The address book.
+    pub contacts: Vec<Contact>,
+    /// The owned notes synchronized so far.
+    pub notes: NoteSync,
+}
+
+/// The contents of a wallet file of version 1.
+#[derive(Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+struct WalletDataV1 {
+    seed: [u8; RNG_SEED],
+    accounts: Vec<Account>,
+    notes: NoteSyncV1,
+}
+
+impl From<WalletDataV1> for WalletData {
+    fn from(data: WalletDataV1) -> Self {
+        Self {
+            seed: data.seed,
+            accounts: data.accounts,
+            contacts: Vec::new(),
+            notes: data.notes.into(),
+        }
+    }
+}
+
+/// The contents of a wallet file of version 2, synchronized before the
+/// positions of the latest blocks were remembered.
+#[derive(Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+struct WalletDataV2 {
+    seed: [u8; RNG_SEED],
+    accounts: Vec<Account>,
+    contacts: Vec<Contact>,
+    notes: NoteSyncV1,
+}
+
+impl From<WalletDataV2> for WalletData {
+    fn from(data: WalletDataV2) -> Self {
+        Self {
+            seed: data.seed,
+            accounts: data.accounts,
+            contacts: data.contacts,
+            notes: data.notes.into(),
+        }
+    }
+}
+
+impl WalletData {
+    /// Encrypts the wallet data with the given password, using the default
+    /// key derivation parameters.
+    pub fn encrypt<Rng: RngCore + CryptoRng>(
+        &self,
+        rng: &mut Rng,
+        password: &[u8],
+    ) -> Result<Vec<u8>, WalletError> {
+        self.encrypt_with(rng, password, KdfParams::default())
+    }
+
+    /// Encrypts the wallet data with the given password and key derivation
+    /// parameters.
+    pub fn encrypt_with<Rng: RngCore + CryptoRng>(
+        &self,
+        rng: &mut Rng,
+        password: &[u8],
+        params: KdfParams,
+    ) -> Result<Vec<u8>, WalletError> {
+        let plaintext = rkyv::to_bytes::<_, MAX_LEN>(self)
+            .map_err(|_| WalletError::Encode("wallet"))?;
+
+        seal(rng, password, params, VERSION, &plaintext)
+    }
+
+    /// Decrypts a wallet file with the given password.
+    pub fn decrypt(bytes: &[u8], password: &[u8]) -> Result<Self, WalletError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(WalletError::InvalidLength("wallet"));
+        }
+
+        let (header, ciphertext) = bytes.split_at(HEADER_SIZE);
+
+        if header[..MAGIC.len()] != MAGIC {
+            return Err(WalletError::Decode("wallet"));
+        }
+        let version = header[MAGIC.len()];
+        if !(1..=VERSION).contains(&version) {
+            return Err(WalletError::InvalidValue("version"));
+        }
+
+        let mut fields = header[MAGIC.len() + 1..].chunks_exact(4);
+        let mut next_u32 = || {
+            let mut int = [0u8; 4];
+            int.copy_from_slice(fields.next().expect("header is long enough"));
+            u32::from_le_bytes(int)
+        };
+
+        let params = KdfParams {
+            memory: next_u32(),
+            iterations: next_u32(),
+            parallelism: next_u32(),
+        };
+
+        let salt_start = HEADER_SIZE - NONCE_SIZE - SALT_SIZE;
+        let salt = &header[salt_start..salt_start + SALT_SIZE];
+        let nonce = &header[HEADER_SIZE - NONCE_SIZE..];
+
+        let key = params.derive_key(password, salt)?;
+
+        let plaintext = ChaCha20Poly1305::new(Key::from_slice(&key))
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| WalletError::Decrypt)?;
+
+        let mut aligned = AlignedVec::with_capacity(plaintext.len());
+        aligned.extend_from_slice(&plaintext);
+
+        match version {
+            1 => rkyv::from_bytes::<WalletDataV1>(&aligned).map(Self::from),
+            2 => rkyv::from_bytes::<WalletDataV2>(&aligned).map(Self::from),
+            _ => rkyv::from_bytes(&aligned),
+        }
+        .map_err(|_| WalletError::Decode("wallet"))
+    }
+}
+
+/// Encrypts the serialized wallet data into a wallet file of the given
+/// version.
+fn seal<Rng: RngCore + CryptoRng>(
+    rng: &mut Rng,
+    password: &[u8],
+    params: KdfParams,
+    version: u8,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, WalletError> {
+    let mut salt = [0u8; SALT_SIZE];
+    let mut nonce = [0u8; NONCE_SIZE];
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    let key = params.derive_key(password, &salt)?;
+
+    let mut bytes = Vec::with_capacity(HEADER_SIZE);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(version);
+    bytes.extend_from_slice(&params.memory.to_le_bytes());
+    bytes.extend_from_slice(&params.iterations.to_le_bytes());
+    bytes.extend_from_slice(&params.parallelism.to_le_bytes());
+    bytes.extend_from_slice(&salt);
+    bytes.extend_from_slice(&nonce);
+
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: &bytes,
+            },
+        )
+        .map_err(|_| WalletError::Encode("wallet"))?;
+
+    bytes.extend_from_slice(&ciphertext);
+
+    Ok(bytes)
+}
+
+#[test]
+fn wallet_file_works() {
+    use alloc::string::ToString;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    // cheap parameters, to keep the test fast
+    let params = KdfParams {
+        memory: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    let data = WalletData {
+        seed: [0xfa; RNG_SEED],
+        accounts: alloc::vec![Account {
+            index: 0,
+            label: "main".to_string(),
+        }],
+        contacts: alloc::vec![Contact {
+            label: "alice".to_string(),
+            address: "alice's address".to_string(),
+        }],
+        notes: NoteSync::new(1),
+    };
+
+    let mut bytes = data.encrypt_with(rng, b"password", params).unwrap();
+
+    let decrypted = WalletData::decrypt(&bytes, b"password").unwrap();
+    assert_eq!(decrypted.seed, data.seed);
+    assert_eq!(decrypted.accounts, data.accounts);
+    assert_eq!(decrypted.contacts, data.contacts);
+
+    assert_eq!(
+        WalletData::decrypt(&bytes, b"wrong").unwrap_err(),
+        WalletError::Decrypt
+    );
+
+    // out of bounds parameters are rejected before deriving the key
+    let kdf = MAGIC.len() + 1;
+    for (offset, value) in [(0, u32::MAX), (4, u32::MAX), (8, 2)] {
+        let mut crafted = bytes.clone();
+        crafted[kdf + offset..kdf + offset + 4]
+            .copy_from_slice(&value.to_le_bytes());
+        assert_eq!(
+            WalletData::decrypt(&crafted, b"password").unwrap_err(),
+            WalletError::InvalidValue("kdf")
+        );
+    }
+
+    // the header is authenticated as well
+    bytes[kdf] ^= 1;
+    assert!(WalletData::decrypt(&bytes, b"password").is_err());
+}
+
+#[test]
+fn wallet_file_v1_works() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    let params = KdfParams {
+        memory: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    let data = WalletDataV1 {
+        seed: [0xfa; RNG_SEED],
+        accounts: Vec::new(),
+        notes: NoteSyncV1::default(),
+    };
+
+    let plaintext = rkyv::to_bytes::<_, MAX_LEN>(&data).unwrap();
+    let bytes = seal(rng, b"password", params, 1, &plaintext).unwrap();
+
+    let decrypted = WalletData::decrypt(&bytes, b"password").unwrap();
+    assert_eq!(decrypted.seed, data.seed);
+    assert!(decrypted.contacts.is_empty());
+
+    let data = WalletDataV2 {
+        seed: [0xfa; RNG_SEED],
+        accounts: Vec::new(),
+        contacts: alloc::vec![Contact {
+            label: "alice".into(),
+            address: "alice's address".into(),
+        }],
+        notes: NoteSyncV1::default(),
+    };
+
+    let plaintext = rkyv::to_bytes::<_, MAX_LEN>(&data).unwrap();
+    let bytes = seal(rng, b"password", params, 2, &plaintext).unwrap();
+
+    let decrypted = WalletData::decrypt(&bytes, b"password").unwrap();
+    assert_eq!(decrypted.contacts, data.contacts);
+}
@@ -5,11 +5,139 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 //! Utilities to derive keys from the seed.
+//!
+//! Keys are located by a [`DerivationPath`] of a subaccount, a role and an
+//! index. The keys of subaccount 0 are the ones derived by index alone, as
+//! they always were, so that existing wallets keep their keys.
+//!
+//! Subaccounts are only reachable from Rust, through [`derive_sk_at`] and
+//! [`derive_stake_sk_at`]. The FFI functions and the [`Store`] locate keys by
+//! index alone, so what they call accounts, as counted by `account_count`,
+//! are the indexes of subaccount 0.
+//!
+//! [`Store`]: crate::Store
 
-use crate::{utils, RNG_SEED};
+use crate::{utils, WalletError, RNG_SEED};
+
+use alloc::vec::Vec;
+use core::str::FromStr;
 
 use bls12_381_bls::SecretKey as StakeSecretKey;
-use phoenix_core::{PublicKey, SecretKey, ViewKey};
+use phoenix_core::{Note, PublicKey, SecretKey, ViewKey};
+use rand_chacha::ChaCha12Rng;
+use sha2::{Digest, Sha512};
+
+/// The role of a key within a subaccount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Keys spending and receiving notes.
+    Spend,
+    /// Keys staking in the stake contract.
+    Stake,
+}
+
+impl Role {
+    /// The constant mixed in the derivation of the keys of the role.
+    const fn tag(&self) -> &'static [u8] {
+        match self {
+            Self::Spend => b"SSK",
+            Self::Stake => b"SK",
+        }
+    }
+
+    const fn name(&self) -> &'static str {
+        match self {
+            Self::Spend => "spend",
+            Self::Stake => "stake",
+        }
+    }
+}
+
+/// The location of a key, written as `subaccount/role/index`, as in
+/// `3/stake/0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DerivationPath {
+    /// The subaccount the key belongs to.
+    pub subaccount: u64,
+    /// The role of the key.
+    pub role: Role,
+    /// The index of the key among the ones of the same subaccount and role.
+    pub index: u64,
+}
+
+impl DerivationPath {
+    /// Creates a new path.
+    pub const fn new(subaccount: u64, role: Role, index: u64) -> Self {
+        Self {
+            subaccount,
+            role,
+            index,
+        }
+    }
+
+    /// Creates the CSPRNG the key at the path is generated with.
+    ///
+    /// For subaccount 0 this is [`utils::rng_with_index`] of the index and
+    /// the constant of the role. Other subaccounts append the little-endian
+    /// representation of the subaccount to the constant, so that the keys of
+    /// different subaccounts never share a hash input.
+    pub fn rng(&self, seed: &[u8; RNG_SEED]) -> ChaCha12Rng {
+        match self.subaccount {
+            0 => utils::rng_with_index(seed, self.index, self.role.tag()),
+            subaccount => {
+                let mut termination = self.role.tag().to_vec();
+                termination.extend_from_slice(&subaccount.to_le_bytes());
+                utils::rng_with_index(seed, self.index, &termination)
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}/{}/{}", self.subaccount, self.role.name(), self.index)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = WalletError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let err = WalletError::InvalidValue("path");
+
+        let mut parts = path.split('/');
+        let (Some(subaccount), Some(role), Some(index), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(err);
+        };
+
+        let role = match role {
+            "spend" => Role::Spend,
+            "stake" => Role::Stake,
+            _ => return Err(err),
+        };
+
+        Ok(Self {
+            subaccount: subaccount.parse().map_err(|_| err)?,
+            role,
+            index: index.parse().map_err(|_| err)?,
+        })
+    }
+}
+
+/// Computes a seed from an arbitrary passphrase.
+///
+/// The `passphrase` and then a constant are passed through SHA-512, and the
+/// resulting hash is used as the seed.
+pub fn seed_from_passphrase(passphrase: &[u8]) -> [u8; RNG_SEED] {
+    let mut hash = Sha512::new();
+
+    hash.update(passphrase);
+    hash.update(b"SEED");
+
+    hash.finalize().into()
+}
 
 /// Generates a stake secret key from its seed and index.
 ///
@@ -18,7 +146,20 @@ use phoenix_core::{PublicKey, SecretKey, ViewKey};
 /// resulting hash is then used to seed a `ChaCha12` CSPRNG, which is
 /// subsequently used to generate the key.
 pub fn derive_stake_sk(seed: &[u8; RNG_SEED], index: u64) -> StakeSecretKey {
-    StakeSecretKey::random(&mut utils::rng_with_index(seed, index, b"SK"))
+    derive_stake_sk_at(seed, 0, index)
+}
+
+/// Generates a stake secret key of a subaccount from its seed and index.
+///
+/// The key is generated from the rng of its [`DerivationPath`], making the
+/// keys of subaccount 0 the ones of [`derive_stake_sk`].
+pub fn derive_stake_sk_at(
+    seed: &[u8; RNG_SEED],
+    subaccount: u64,
+    index: u64,
+) -> StakeSecretKey {
+    let path = DerivationPath::new(subaccount, Role::Stake, index);
+    StakeSecretKey::random(&mut path.rng(seed))
 }
 
 /// Generates a secret key from its seed and index.
@@ -28,7 +169,20 @@ pub fn derive_stake_sk(seed: &[u8; RNG_SEED], index: u64) -> StakeSecretKey {
 /// resulting hash is then used to seed a `ChaCha12` CSPRNG, which is
 /// subsequently used to generate the key.
 pub fn derive_sk(seed: &[u8; RNG_SEED], index: u64) -> SecretKey {
-    SecretKey::random(&mut utils::rng_with_index(seed, index, b"SSK"))
+    derive_sk_at(seed, 0, index)
+}
+
+/// Generates a secret key of a subaccount from its seed and index.
+///
+/// The key is generated from the rng of its [`DerivationPath`], making the
+/// keys of subaccount 0 the ones of [`derive_sk`].
+pub fn derive_sk_at(
+    seed: &[u8; RNG_SEED],
+    subaccount: u64,
+    index: u64,
+) -> SecretKey {
+    let path = DerivationPath::new(subaccount, Role::Spend, index);
+    SecretKey::random(&mut path.rng(seed))
 }
 
 /// Generates a public key from its seed and index.
@@ -48,3 +202,44 @@ pub fn derive_vk(seed: &[u8; RNG_SEED], index: u64) -> ViewKey {
     let sk = derive_sk(seed, index);
     ViewKey::from(&sk)
 }
+
+/// Generates the view keys of the first `count` accounts of the seed.
+///
+/// The key at position `i` of the returned vector is the one derived with
+/// index `i`.
+pub fn derive_vks(seed: &[u8; RNG_SEED], count: usize) -> Vec<ViewKey> {
+    (0..count as u64).map(|idx| derive_vk(seed, idx)).collect()
+}
+
+/// Finds the index of the first of the given `keys` owning the note.
+pub fn owner_index(keys: &[ViewKey], note: &Note) -> Option<usize> {
+    keys.iter().position(|vk| vk.owns(note))
+}
+
+#[test]
+fn derivation_path_works() {
+    use dusk_bytes::Serializable;
+
+    let seed = [0xfa; RNG_SEED];
+
+    let path: DerivationPath = "3/stake/7".parse().unwrap();
+    assert_eq!(path, DerivationPath::new(3, Role::Stake, 7));
+    assert_eq!(alloc::string::ToString::to_string(&path), "3/stake/7");
+
+    for path in ["3/stake", "3/hot/7", "3/stake/7/1", "-1/spend/0"] {
+        assert!(path.parse::<DerivationPath>().is_err(), "{path}");
+    }
+
+    // index-only keys are the ones of subaccount 0
+    let path = DerivationPath::new(0, Role::Spend, 5);
+    assert_eq!(
+        SecretKey::random(&mut path.rng(&seed)),
+        SecretKey::random(&mut utils::rng_with_index(&seed, 5, b"SSK"))
+    );
+
+    assert_ne!(derive_sk_at(&seed, 1, 5), derive_sk(&seed, 5));
+    assert_ne!(
+        derive_stake_sk_at(&seed, 1, 5).to_bytes(),
+        derive_stake_sk(&seed, 5).to_bytes()
+    );
+}
@@ -10,18 +10,44 @@
 
 extern crate alloc;
 
+pub mod address;
 #[cfg(feature = "compat")]
 /// compat module adds compatiblity functions for non rust platforms
 pub mod compat;
+pub mod consolidate;
+pub mod error;
+pub mod fee;
 pub mod ffi;
+pub mod file;
 pub mod key;
+pub mod memo;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod offline;
+pub mod preview;
+pub mod select;
+pub mod stake;
+pub mod sync;
 pub mod tx;
 pub mod types;
+pub mod uri;
 pub mod utils;
+pub mod wallet;
+
+pub use error::WalletError;
+pub use wallet::{BalanceInfo, Error, StateClient, Store, Wallet};
+
 /// The maximum number of keys (inclusive) to derive when attempting to decrypt
-/// a note.
+/// a note, if no account count is given.
 pub const MAX_KEY: usize = 1;
 
+/// The maximum number of accounts a single call can derive keys for.
+pub const MAX_ACCOUNTS: usize = 1024;
+
+/// The default number of consecutive unused accounts after which the account
+/// discovery stops.
+pub const GAP_LIMIT: u64 = 20;
+
 /// The maximum allocated buffer for rkyv serialization.
 pub const MAX_LEN: usize = rusk_abi::ARGBUF_LEN;
 
@@ -33,3 +59,7 @@ pub const RESPONSE_LEN: usize = 3 * i32::BITS as usize / 8;
 
 /// The maximum number of input notes that are sent with the transaction
 pub const MAX_INPUT_NOTES: usize = 4;
+
+/// The maximum number of output notes of a transaction, change included, as
+/// supported by the execute circuits.
+pub const MAX_OUTPUT_NOTES: usize = 2;
@@ -0,0 +1,100 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Payment references carried by notes.
+//!
+//! A note has no room for a memo, but its nonce is free to be chosen by the
+//! sender as long as it is unique. The reference is added to a mask hashed
+//! from the secret the sender shares with the receiver, the same one the
+//! value of the note is encrypted with. To anyone else the nonce looks as
+//! random as ever, while the receiver strips the mask off with its view key.
+
+use dusk_bls12_381::BlsScalar;
+use dusk_bytes::Serializable;
+use dusk_jubjub::{JubJubAffine, JubJubExtended, JubJubScalar};
+use phoenix_core::{Note, Ownable, PublicKey, ViewKey};
+use rusk_abi::hash::Hasher;
+
+/// Domain of the hash masking the reference.
+const DOMAIN: &[u8] = b"dusk-wallet-core:ref_id";
+
+/// Computes the nonce of a note carrying `ref_id`, created with the random
+/// scalar `r` for `receiver`.
+pub fn nonce(r: &JubJubScalar, receiver: &PublicKey, ref_id: u64) -> BlsScalar {
+    mask(&(receiver.A() * r)) + BlsScalar::from(ref_id)
+}
+
+/// Recovers the reference carried by the note, if it is owned by `vk` and
+/// carries any.
+pub fn ref_id(vk: &ViewKey, note: &Note) -> Option<u64> {
+    if !vk.owns(note) {
+        return None;
+    }
+
+    let shared = note.stealth_address().R() * vk.a();
+    let value = (*note.nonce() - mask(&shared)).to_bytes();
+
+    // a nonce with no reference leaves a random scalar, overflowing the
+    // 64 bits almost surely
+    let (low, high) = value.split_at(8);
+    if high.iter().any(|b| *b != 0) {
+        return None;
+    }
+
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(low);
+    Some(u64::from_le_bytes(bytes))
+}
+
+/// Hashes the shared secret into the mask of the reference.
+fn mask(shared: &JubJubExtended) -> BlsScalar {
+    let shared = JubJubAffine::from(shared).to_bytes();
+
+    let mut bytes = [0u8; DOMAIN.len() + JubJubAffine::SIZE];
+    bytes[..DOMAIN.len()].copy_from_slice(DOMAIN);
+    bytes[DOMAIN.len()..].copy_from_slice(&shared);
+
+    Hasher::digest(bytes)
+}
+
+#[test]
+fn ref_id_works() {
+    use crate::{tx::Output, types};
+    use phoenix_core::SecretKey;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    let sk = SecretKey::random(rng);
+    let vk = ViewKey::from(&sk);
+    let pk = PublicKey::from(&sk);
+    let other = ViewKey::from(&SecretKey::random(rng));
+
+    let output = |rng: &mut StdRng, note_type, id| {
+        let output = types::ExecuteOutput {
+            note_type,
+            receiver: bs58::encode(pk.to_bytes()).into_string(),
+            ref_id: id,
+            value: 100,
+        };
+        Output::new(rng, output).unwrap().note
+    };
+
+    for id in [0, 15, u64::MAX] {
+        let note = output(rng, types::OutputType::Obfuscated, Some(id));
+        assert_eq!(ref_id(&vk, &note), Some(id));
+        assert_eq!(ref_id(&other, &note), None);
+
+        // the value is still recovered
+        assert_eq!(note.value(Some(&vk)).unwrap(), 100);
+    }
+
+    let note = output(rng, types::OutputType::Transparent, Some(7));
+    assert_eq!(ref_id(&vk, &note), Some(7));
+
+    let note = output(rng, types::OutputType::Obfuscated, None);
+    assert_eq!(ref_id(&vk, &note), None);
+}
@@ -0,0 +1,136 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! In-memory implementation of the [`StateClient`], to be used in tests and
+//! prototypes without a node.
+
+use alloc::vec::Vec;
+
+use bls12_381_bls::PublicKey as StakePublicKey;
+use dusk_bls12_381::BlsScalar;
+use phoenix_core::{transaction::StakeData, Note, ViewKey};
+use poseidon_merkle::Item;
+use rusk_abi::POSEIDON_TREE_DEPTH;
+
+use crate::{
+    tx::{Opening, POSEIDON_TREE_ARITY},
+    StateClient, WalletError,
+};
+
+type Tree = poseidon_merkle::Tree<(), POSEIDON_TREE_DEPTH, POSEIDON_TREE_ARITY>;
+
+/// A state client holding the notes tree, the spent nullifiers and the stakes
+/// in memory.
+///
+/// The openings are computed from an actual Merkle tree of the notes, so they
+/// open to the anchor returned by the client.
+pub struct MockStateClient {
+    notes: Vec<Note>,
+    tree: Tree,
+    nullifiers: Vec<BlsScalar>,
+    stakes: Vec<(StakePublicKey, StakeData)>,
+}
+
+impl Default for MockStateClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockStateClient {
+    /// Creates a new, empty, state.
+    pub const fn new() -> Self {
+        Self {
+            notes: Vec::new(),
+            tree: Tree::new(),
+            nullifiers: Vec::new(),
+            stakes: Vec::new(),
+        }
+    }
+
+    /// Appends a note to the tree, returning the position it was given.
+    pub fn push_note(&mut self, mut note: Note) -> u64 {
+        let pos = self.notes.len() as u64;
+
+        note.set_pos(pos);
+        self.tree.insert(pos, Item::new(note.hash(), ()));
+        self.notes.push(note);
+
+        pos
+    }
+
+    /// Marks the note of the given nullifier as spent.
+    pub fn spend(&mut self, nullifier: BlsScalar) {
+        if !self.nullifiers.contains(&nullifier) {
+            self.nullifiers.push(nullifier);
+        }
+    }
+
+    /// Sets the stake of the given key, replacing any previous one.
+    pub fn set_stake(&mut self, pk: StakePublicKey, stake: StakeData) {
+        self.stakes.retain(|(k, _)| k != &pk);
+        self.stakes.push((pk, stake));
+    }
+
+    /// Returns all the notes of the tree, in position order.
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+}
+
+impl StateClient for MockStateClient {
+    type Error = WalletError;
+
+    fn fetch_notes(
+        &self,
+        vk: &ViewKey,
+        since: u64,
+    ) -> Result<Vec<Note>, Self::Error> {
+        let notes = self
+            .notes
+            .iter()
+            .skip(since as usize)
+            .filter(|note| vk.owns(*note))
+            .copied()
+            .collect();
+
+        Ok(notes)
+    }
+
+    fn fetch_opening(&self, pos: u64) -> Result<Opening, Self::Error> {
+        self.tree.opening(pos).ok_or(WalletError::MissingOpening)
+    }
+
+    fn fetch_anchor(&self) -> Result<BlsScalar, Self::Error> {
+        Ok(self.tree.root().hash)
+    }
+
+    fn fetch_existing_nullifiers(
+        &self,
+        nullifiers: &[BlsScalar],
+    ) -> Result<Vec<BlsScalar>, Self::Error> {
+        let existing = nullifiers
+            .iter()
+            .filter(|n| self.nullifiers.contains(n))
+            .copied()
+            .collect();
+
+        Ok(existing)
+    }
+
+    fn fetch_stake(
+        &self,
+        pk: &StakePublicKey,
+    ) -> Result<Option<StakeData>, Self::Error> {
+        let stake = self
+            .stakes
+            .iter()
+            .find(|(k, _)| k == pk)
+            .map(|(_, stake)| stake.clone());
+
+        Ok(stake)
+    }
+}
@@ -0,0 +1,355 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Offline signing of transactions.
+//!
+//! Building a transaction takes the state of the network, while signing it
+//! takes the secret key. A networked host holding only the view key builds a
+//! [`Template`], with the inputs, their openings, the outputs, the fee and the
+//! call. The template is carried to an offline host holding the seed, that
+//! nullifies and signs the inputs into an [`UnprovenTransaction`].
+//!
+//! The template travels in a compact transport encoding, fit for a file or a
+//! sequence of QR codes, laid out as:
+//!
+//! | field    | size |
+//! |----------|------|
+//! | magic    | 4    |
+//! | version  | 1    |
+//! | template | ..   |
+//! | checksum | 4    |
+//!
+//! The template is rkyv serialized, and the checksum is the start of the
+//! SHA-256 of the bytes before it, to catch transmission errors.
+
+use alloc::vec::Vec;
+
+use bytecheck::CheckBytes;
+use dusk_bls12_381::BlsScalar;
+use dusk_bytes::Serializable;
+use dusk_jubjub::{JubJubScalar, GENERATOR_NUMS_EXTENDED};
+use phoenix_core::{Fee, Note, Ownable, PublicKey, SecretKey, ViewKey};
+use rand_core::{CryptoRng, RngCore};
+use rkyv::{AlignedVec, Archive, Deserialize, Serialize};
+use rusk_abi::hash::Hasher;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    select::CoinSelector,
+    tx::{
+        CallData, Input, Opening, Output, UnprovenTransaction, WasmCrossover,
+    },
+    types, utils, WalletError, MAX_LEN, MAX_OUTPUT_NOTES,
+};
+
+/// The magic bytes a transported template starts with.
+pub const MAGIC: [u8; 4] = *b"DTPL";
+
+/// The version of the transport encoding.
+pub const VERSION: u8 = 1;
+
+const CHECKSUM_SIZE: usize = 4;
+const HEADER_SIZE: usize = MAGIC.len() + 1;
+
+/// An input of a template, yet to be nullified and signed.
+#[derive(Debug, Clone, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct TemplateInput {
+    /// Input note to be used in the transaction.
+    pub note: Note,
+    /// Opening from the `input` to the Merkle root of the state.
+    pub opening: Opening,
+    /// Decrypted value of the input note.
+    pub value: u64,
+    /// Blinding factor used to construct the note.
+    pub blinder: JubJubScalar,
+}
+
+/// A transaction that is yet to be signed.
+#[derive(Debug, Clone, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Template {
+    /// Inputs to the transaction.
+    pub inputs: Vec<TemplateInput>,
+    /// Outputs to the transaction.
+    pub outputs: Vec<Output>,
+    /// Merkle root of the state for the inputs openings.
+    pub anchor: BlsScalar,
+    /// Fee setup for the transaction.
+    pub fee: Fee,
+    /// Crossover value for inter-contract calls.
+    pub crossover: Option<WasmCrossover>,
+    /// Call data payload for contract calls.
+    pub call: Option<CallData>,
+}
+
+impl Template {
+    /// Creates a new template from the arguments, with the same semantics as
+    /// [`UnprovenTransaction::new`].
+    pub fn new<Rng, I, O>(
+        rng: &mut Rng,
+        inputs: I,
+        outputs: O,
+        fee: Fee,
+        crossover: Option<types::CrossoverType>,
+        call: Option<types::ExecuteCall>,
+    ) -> Option<Self>
+    where
+        Rng: RngCore + CryptoRng,
+        I: IntoIterator<Item = TemplateInput>,
+        O: IntoIterator<Item = types::ExecuteOutput>,
+    {
+        let inputs: Vec<TemplateInput> = inputs.into_iter().collect();
+        let anchor = inputs.first().map(|i| i.opening.root().hash)?;
+
+        let outputs = outputs
+            .into_iter()
+            .map(|output| Output::new(rng, output))
+            .collect::<Option<Vec<_>>>()?;
+
+        let call = call.map(CallData::try_from).transpose().ok()?;
+        let crossover =
+            crossover.map(WasmCrossover::try_from).transpose().ok()?;
+
+        Some(Self {
+            inputs,
+            outputs,
+            anchor,
+            fee,
+            crossover,
+            call,
+        })
+    }
+
+    /// Nullifies and signs the inputs with `sk`, turning the template into a
+    /// transaction ready to be proven.
+    ///
+    /// The template comes from a networked host, that may be compromised, so
+    /// the transaction is checked with [`UnprovenTransaction::verify`] before
+    /// it is returned. Its outputs and fee should still be shown to the user
+    /// for confirmation, as summarized by [`crate::preview::Preview`].
+    ///
+    /// Fails with [`WalletError::NoteOwnership`] if an input is not owned by
+    /// `sk`, and as [`UnprovenTransaction::verify`] otherwise.
+    pub fn sign<Rng>(
+        self,
+        rng: &mut Rng,
+        sk: &SecretKey,
+    ) -> Result<UnprovenTransaction, WalletError>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let tx = self.sign_unverified(rng, sk)?;
+        tx.verify(sk)?;
+
+        Ok(tx)
+    }
+
+    /// Signs the template as [`Template::sign`] does, without verifying the
+    /// transaction, for templates built by the signer itself.
+    pub(crate) fn sign_unverified<Rng>(
+        self,
+        rng: &mut Rng,
+        sk: &SecretKey,
+    ) -> Result<UnprovenTransaction, WalletError>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let vk = ViewKey::from(sk);
+        if !self.inputs.iter().all(|input| vk.owns(&input.note)) {
+            return Err(WalletError::NoteOwnership("inputs"));
+        }
+
+        let nullifiers: Vec<BlsScalar> = self
+            .inputs
+            .iter()
+            .map(|input| input.note.gen_nullifier(sk))
+            .collect();
+        let outputs: Vec<Note> =
+            self.outputs.iter().map(|output| output.note).collect();
+        let call = self.call.as_ref().map(|c| {
+            (c.contract.to_bytes(), c.method.clone(), c.payload.clone())
+        });
+
+        let tx_hash = Hasher::digest(
+            phoenix_core::Transaction::hash_input_bytes_from_components(
+                &nullifiers,
+                &outputs,
+                &self.anchor,
+                &self.fee,
+                &self.crossover.as_ref().map(|c| c.crossover),
+                &call,
+            ),
+        );
+
+        let inputs = self
+            .inputs
+            .into_iter()
+            .zip(nullifiers)
+            .map(|(input, nullifier)| {
+                let note_sk = sk.sk_r(input.note.stealth_address());
+                let note_pk_prime = GENERATOR_NUMS_EXTENDED * note_sk.as_ref();
+                let sig = note_sk.sign_double(rng, tx_hash);
+
+                Input {
+                    nullifier,
+                    opening: input.opening,
+                    note: input.note,
+                    value: input.value,
+                    blinder: input.blinder,
+                    note_pk_prime,
+                    sig,
+                }
+            })
+            .collect();
+
+        Ok(UnprovenTransaction {
+            inputs,
+            outputs: self.outputs,
+            anchor: self.anchor,
+            fee: self.fee,
+            crossover: self.crossover,
+            call: self.call,
+        })
+    }
+
+    /// Encodes the template for transport.
+    pub fn to_transport_bytes(&self) -> Result<Vec<u8>, WalletError> {
+        let template = rkyv::to_bytes::<_, MAX_LEN>(self)
+            .map_err(|_| WalletError::Encode("template"))?;
+
+        let mut bytes =
+            Vec::with_capacity(HEADER_SIZE + template.len() + CHECKSUM_SIZE);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&template);
+
+        let checksum = Sha256::digest(&bytes);
+        bytes.extend_from_slice(&checksum[..CHECKSUM_SIZE]);
+
+        Ok(bytes)
+    }
+
+    /// Decodes a template from its transport encoding.
+    ///
+    /// Fails with [`WalletError::Decode`] if the bytes are not a template or
+    /// were corrupted in transit.
+    pub fn from_transport_bytes(bytes: &[u8]) -> Result<Self, WalletError> {
+        if bytes.len() < HEADER_SIZE + CHECKSUM_SIZE {
+            return Err(WalletError::InvalidLength("template"));
+        }
+
+        let (bytes, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+        if bytes[..MAGIC.len()] != MAGIC
+            || Sha256::digest(bytes)[..CHECKSUM_SIZE] != *checksum
+        {
+            return Err(WalletError::Decode("template"));
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(WalletError::InvalidValue("version"));
+        }
+
+        // the archived template must be aligned, and the header shifts it
+        let template = &bytes[HEADER_SIZE..];
+        let mut aligned = AlignedVec::with_capacity(template.len());
+        aligned.extend_from_slice(template);
+
+        rkyv::from_bytes(&aligned).map_err(|_| WalletError::Decode("template"))
+    }
+}
+
+/// Builds the template of a transaction spending the given notes.
+///
+/// The inputs are picked among the `notes` by the `selector` to cover the
+/// outputs, the gas and the crossover, and only then their openings are
+/// queried by position from `opening`. The change is sent back to `refund` in
+/// a note of `refund_type`, and must fit within [`MAX_OUTPUT_NOTES`] along
+/// with the `outputs`. If no `fee` is given, one refunding `refund` is created.
+#[allow(clippy::too_many_arguments)]
+pub fn template<Rng, Sel, F, E>(
+    rng: &mut Rng,
+    notes: Vec<utils::Node>,
+    selector: &Sel,
+    mut opening: F,
+    refund: &PublicKey,
+    refund_type: types::OutputType,
+    outputs: Vec<types::ExecuteOutput>,
+    gas_limit: u64,
+    gas_price: u64,
+    fee: Option<Fee>,
+    crossover: Option<types::CrossoverType>,
+    call: Option<types::ExecuteCall>,
+) -> Result<Template, E>
+where
+    Rng: RngCore + CryptoRng,
+    Sel: CoinSelector,
+    F: FnMut(u64) -> Result<Opening, E>,
+    E: From<WalletError>,
+{
+    if outputs.len() > MAX_OUTPUT_NOTES {
+        return Err(WalletError::TooManyOutputs.into());
+    }
+
+    // an undecodable crossover is an error rather than left out
+    if let Some(crossover) = &crossover {
+        WasmCrossover::try_from(crossover.clone())?;
+    }
+
+    // the total value of the outputs must be representable
+    let value = outputs
+        .iter()
+        .try_fold(0u64, |sum, o| sum.checked_add(o.value))
+        .ok_or(WalletError::InvalidValue("outputs"))?;
+    let total_output = gas_limit
+        .saturating_mul(gas_price)
+        .saturating_add(value)
+        .saturating_add(
+            crossover.as_ref().map(|c| c.value).unwrap_or_default(),
+        );
+
+    // optimizes the inputs given the total amount
+    let inputs = selector
+        .select(rng, notes, total_output)
+        .ok_or(WalletError::InsufficientBalance)?;
+
+    let inputs = inputs
+        .into_iter()
+        .map(|(note, value, blinder)| {
+            Ok(TemplateInput {
+                opening: opening(*note.pos())?,
+                note,
+                value,
+                blinder,
+            })
+        })
+        .collect::<Result<Vec<_>, E>>()?;
+
+    let total_input: u64 = inputs.iter().map(|i| i.value).sum();
+    let total_refund = total_input.saturating_sub(total_output);
+
+    let mut tx_outputs = Vec::with_capacity(outputs.len() + 1);
+    if total_refund > 0 {
+        if outputs.len() == MAX_OUTPUT_NOTES {
+            return Err(WalletError::TooManyOutputs.into());
+        }
+
+        tx_outputs.push(types::ExecuteOutput {
+            note_type: refund_type,
+            receiver: bs58::encode(refund.to_bytes()).into_string(),
+            ref_id: None,
+            value: total_refund,
+        });
+    }
+    tx_outputs.extend(outputs);
+
+    let fee =
+        fee.unwrap_or_else(|| Fee::new(rng, gas_limit, gas_price, refund));
+
+    let template = Template::new(rng, inputs, tx_outputs, fee, crossover, call)
+        .ok_or(WalletError::Transaction)?;
+
+    Ok(template)
+}
@@ -0,0 +1,219 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Summaries of unproven transactions, for the user to review them before
+//! they are proven and sent.
+
+use alloc::{string::String, vec::Vec};
+
+use dusk_bytes::Serializable;
+use phoenix_core::{NoteType, Ownable, StealthAddress, ViewKey};
+use rusk_abi::ContractId;
+
+use crate::{tx::UnprovenTransaction, types};
+
+/// A note spent by the transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputPreview {
+    /// The position of the note in the tree.
+    pub pos: u64,
+    /// The type of the note.
+    pub note_type: NoteType,
+    /// The value of the note.
+    pub value: u64,
+}
+
+/// A note created by the transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputPreview {
+    /// The type of the note.
+    pub note_type: NoteType,
+    /// The value of the note.
+    pub value: u64,
+    /// The one-time address of the receiver.
+    pub stealth_address: StealthAddress,
+    /// If the note is the change, sent back to the sender.
+    pub change: bool,
+}
+
+/// Something the user should be warned about before confirming.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anomaly {
+    /// The fee is greater than the value sent to others. Not raised for self
+    /// transfers, which send nothing to others.
+    FeeExceedsValue,
+    /// An output is transparent, exposing its value and receiver.
+    TransparentOutput,
+    /// The inputs don't match the outputs, fee and crossover.
+    Unbalanced,
+}
+
+impl From<Anomaly> for types::TxAnomaly {
+    fn from(anomaly: Anomaly) -> Self {
+        match anomaly {
+            Anomaly::FeeExceedsValue => Self::FeeExceedsValue,
+            Anomaly::TransparentOutput => Self::TransparentOutput,
+            Anomaly::Unbalanced => Self::Unbalanced,
+        }
+    }
+}
+
+/// The summary of an unproven transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preview {
+    /// The notes spent.
+    pub inputs: Vec<InputPreview>,
+    /// The notes created.
+    pub outputs: Vec<OutputPreview>,
+    /// The value of the notes spent.
+    pub total_input: u64,
+    /// The value sent to others, crossover included.
+    pub sent: u64,
+    /// The value sent back to the sender.
+    pub change: u64,
+    /// If the transaction sends no value to others, moving it between notes
+    /// of the sender, as a consolidation does.
+    pub self_transfer: bool,
+    /// The gas limit of the transaction.
+    pub gas_limit: u64,
+    /// The gas price of the transaction.
+    pub gas_price: u64,
+    /// The maximum fee paid, if all the gas is used.
+    pub fee: u64,
+    /// The value of the crossover, if any.
+    pub crossover: Option<u64>,
+    /// The contract called, if any.
+    pub contract: Option<ContractId>,
+    /// The method called, if any.
+    pub method: Option<String>,
+    /// The anomalies found.
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl Preview {
+    /// Summarizes the transaction. If the view key of the sender is given, the
+    /// outputs it owns are told apart as change.
+    pub fn new(tx: &UnprovenTransaction, vk: Option<&ViewKey>) -> Self {
+        let inputs: Vec<InputPreview> = tx
+            .inputs
+            .iter()
+            .map(|input| InputPreview {
+                pos: *input.note.pos(),
+                note_type: input.note.note(),
+                value: input.value,
+            })
+            .collect();
+
+        let outputs: Vec<OutputPreview> = tx
+            .outputs
+            .iter()
+            .map(|output| OutputPreview {
+                note_type: output.note.note(),
+                value: output.value,
+                stealth_address: *output.note.stealth_address(),
+                change: vk.map(|vk| vk.owns(&output.note)).unwrap_or(false),
+            })
+            .collect();
+
+        let sum = |values: &mut dyn Iterator<Item = u64>| {
+            values.fold(0u64, |sum, v| sum.saturating_add(v))
+        };
+
+        let crossover = tx.crossover.as_ref().map(|c| c.value);
+        let total_input = sum(&mut inputs.iter().map(|i| i.value));
+        let change =
+            sum(&mut outputs.iter().filter(|o| o.change).map(|o| o.value));
+        let sent =
+            sum(&mut outputs.iter().filter(|o| !o.change).map(|o| o.value))
+                .saturating_add(crossover.unwrap_or_default());
+
+        let gas_limit = tx.fee.gas_limit;
+        let gas_price = tx.fee.gas_price;
+        let fee = gas_limit.saturating_mul(gas_price);
+
+        // a self transfer is paid for to reorganize the notes of the sender,
+        // so there's no value sent to weigh its fee against
+        let self_transfer = sent == 0 && tx.call.is_none();
+
+        let mut anomalies = Vec::new();
+        if !self_transfer && fee > sent {
+            anomalies.push(Anomaly::FeeExceedsValue);
+        }
+        if outputs.iter().any(|o| o.note_type == NoteType::Transparent) {
+            anomalies.push(Anomaly::TransparentOutput);
+        }
+        if total_input != sent.saturating_add(change).saturating_add(fee) {
+            anomalies.push(Anomaly::Unbalanced);
+        }
+
+        Self {
+            inputs,
+            outputs,
+            total_input,
+            sent,
+            change,
+            self_transfer,
+            gas_limit,
+            gas_price,
+            fee,
+            crossover,
+            contract: tx.call.as_ref().map(|call| call.contract),
+            method: tx.call.as_ref().map(|call| call.method.clone()),
+            anomalies,
+        }
+    }
+}
+
+impl From<Preview> for types::PreviewTxResponse {
+    fn from(preview: Preview) -> Self {
+        let note_type = |note_type| match note_type {
+            NoteType::Transparent => types::OutputType::Transparent,
+            NoteType::Obfuscated => types::OutputType::Obfuscated,
+        };
+
+        let inputs = preview
+            .inputs
+            .iter()
+            .map(|input| types::PreviewInputType {
+                note_type: note_type(input.note_type),
+                pos: input.pos,
+                value: input.value,
+            })
+            .collect();
+
+        let outputs = preview
+            .outputs
+            .iter()
+            .map(|output| types::PreviewOutputType {
+                change: output.change,
+                note_type: note_type(output.note_type),
+                stealth_address: bs58::encode(
+                    output.stealth_address.to_bytes(),
+                )
+                .into_string(),
+                value: output.value,
+            })
+            .collect();
+
+        Self {
+            anomalies: preview.anomalies.into_iter().map(Into::into).collect(),
+            change: preview.change,
+            contract: preview
+                .contract
+                .map(|c| bs58::encode(c.as_bytes()).into_string()),
+            crossover: preview.crossover,
+            fee: preview.fee,
+            gas_limit: preview.gas_limit,
+            gas_price: preview.gas_price,
+            inputs,
+            method: preview.method,
+            outputs,
+            self_transfer: preview.self_transfer,
+            sent: preview.sent,
+            total_input: preview.total_input,
+        }
+    }
+}
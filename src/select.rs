@@ -0,0 +1,274 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Strategies to select the notes spent by a transaction.
+//!
+//! A transaction spends at most [`MAX_INPUT_NOTES`] notes, so every strategy
+//! fails when the target can't be covered by that many notes, even if the
+//! total balance is enough.
+
+use alloc::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{types, utils, utils::Node, MAX_INPUT_NOTES};
+
+/// The maximum number of branches explored by the branch-and-bound search,
+/// before falling back to largest-first.
+const BNB_TRIES: usize = 100_000;
+
+/// Selects the notes to spend to cover a target value.
+pub trait CoinSelector {
+    /// Picks among the `nodes` the inputs covering `target`, returning `None`
+    /// if no selection covers it.
+    fn select<Rng>(
+        &self,
+        rng: &mut Rng,
+        nodes: Vec<Node>,
+        target: u64,
+    ) -> Option<Vec<Node>>
+    where
+        Rng: RngCore + CryptoRng;
+}
+
+/// The coin selection strategies provided by the wallet.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Picks the first combination covering the target, going through them
+    /// in lexicographic order over the notes sorted by ascending value. This
+    /// favors spending the small notes, but scales poorly with the number of
+    /// notes.
+    #[default]
+    Lexicographic,
+    /// Picks the largest notes first, spending as few notes as possible.
+    LargestFirst,
+    /// Searches for notes matching the target exactly, so no change is
+    /// left, falling back to largest-first if there are none.
+    BranchAndBound,
+    /// Picks notes at random, so the spent notes tell little about the
+    /// wallet.
+    Random,
+    /// Spends as many of the smallest notes as possible, filling in with the
+    /// largest notes to cover the target.
+    Consolidate,
+}
+
+impl From<types::CoinSelection> for Strategy {
+    fn from(selection: types::CoinSelection) -> Self {
+        match selection {
+            types::CoinSelection::Lexicographic => Self::Lexicographic,
+            types::CoinSelection::LargestFirst => Self::LargestFirst,
+            types::CoinSelection::BranchAndBound => Self::BranchAndBound,
+            types::CoinSelection::Random => Self::Random,
+            types::CoinSelection::Consolidate => Self::Consolidate,
+        }
+    }
+}
+
+impl CoinSelector for Strategy {
+    fn select<Rng>(
+        &self,
+        rng: &mut Rng,
+        nodes: Vec<Node>,
+        target: u64,
+    ) -> Option<Vec<Node>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        if nodes.is_empty() {
+            return None;
+        }
+
+        match self {
+            Self::Lexicographic => utils::inputs(nodes, target),
+            Self::LargestFirst => largest_first(nodes, target),
+            Self::BranchAndBound => branch_and_bound(nodes, target),
+            Self::Random => random(rng, nodes, target),
+            Self::Consolidate => consolidate(nodes, target),
+        }
+    }
+}
+
+/// Takes the first nodes covering the target, if they are few enough.
+fn take_covering(mut nodes: Vec<Node>, target: u64) -> Option<Vec<Node>> {
+    let mut sum = 0u64;
+    let count = nodes.iter().take(MAX_INPUT_NOTES).position(|(_, v, _)| {
+        sum = sum.saturating_add(*v);
+        sum >= target
+    })?;
+
+    nodes.truncate(count + 1);
+    Some(nodes)
+}
+
+fn largest_first(mut nodes: Vec<Node>, target: u64) -> Option<Vec<Node>> {
+    nodes.sort_by(|(_, a, _), (_, b, _)| b.cmp(a));
+    take_covering(nodes, target)
+}
+
+fn random<Rng: RngCore>(
+    rng: &mut Rng,
+    mut nodes: Vec<Node>,
+    target: u64,
+) -> Option<Vec<Node>> {
+    // Fisher-Yates shuffle
+    for i in (1..nodes.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        nodes.swap(i, j);
+    }
+
+    match take_covering(nodes.clone(), target) {
+        Some(picked) => Some(picked),
+        None => largest_first(nodes, target),
+    }
+}
+
+fn consolidate(mut nodes: Vec<Node>, target: u64) -> Option<Vec<Node>> {
+    nodes.sort_by_key(|(_, value, _)| *value);
+
+    let len = nodes.len();
+
+    // try with as many of the smallest notes as possible, filling the
+    // remaining slots with the largest ones
+    for dust in (0..=MAX_INPUT_NOTES.min(len)).rev() {
+        let mut picked: Vec<usize> = (0..dust).collect();
+        let mut sum = nodes[..dust]
+            .iter()
+            .fold(0u64, |sum, (_, v, _)| sum.saturating_add(*v));
+
+        for i in (dust..len).rev().take(MAX_INPUT_NOTES - dust) {
+            if sum >= target {
+                break;
+            }
+            sum = sum.saturating_add(nodes[i].1);
+            picked.push(i);
+        }
+
+        if sum >= target && !picked.is_empty() {
+            return Some(picked.into_iter().map(|i| nodes[i]).collect());
+        }
+    }
+
+    None
+}
+
+fn branch_and_bound(mut nodes: Vec<Node>, target: u64) -> Option<Vec<Node>> {
+    nodes.sort_by(|(_, a, _), (_, b, _)| b.cmp(a));
+
+    let values: Vec<u64> = nodes.iter().map(|(_, v, _)| *v).collect();
+
+    // the value left in the nodes from each index on, to prune the branches
+    // that can't reach the target
+    let mut remaining = alloc::vec![0u64; values.len() + 1];
+    for i in (0..values.len()).rev() {
+        remaining[i] = remaining[i + 1].saturating_add(values[i]);
+    }
+
+    let mut picked = Vec::with_capacity(MAX_INPUT_NOTES);
+    let mut tries = BNB_TRIES;
+
+    if target > 0
+        && search_exact(&values, &remaining, 0, target, &mut picked, &mut tries)
+    {
+        return Some(picked.into_iter().map(|i| nodes[i]).collect());
+    }
+
+    take_covering(nodes, target)
+}
+
+/// Searches depth-first for values summing exactly to the target, among the
+/// values sorted in descending order.
+fn search_exact(
+    values: &[u64],
+    remaining: &[u64],
+    from: usize,
+    target: u64,
+    picked: &mut Vec<usize>,
+    tries: &mut usize,
+) -> bool {
+    if target == 0 {
+        return true;
+    }
+    if picked.len() == MAX_INPUT_NOTES || remaining[from] < target {
+        return false;
+    }
+
+    for i in from..values.len() {
+        if *tries == 0 || remaining[i] < target {
+            return false;
+        }
+        // the values too large, or equal to one already tried at this depth,
+        // lead nowhere new
+        if values[i] > target || (i > from && values[i] == values[i - 1]) {
+            continue;
+        }
+
+        *tries -= 1;
+        picked.push(i);
+
+        let rest = target - values[i];
+        if search_exact(values, remaining, i + 1, rest, picked, tries) {
+            return true;
+        }
+
+        picked.pop();
+    }
+
+    false
+}
+
+#[test]
+fn strategies_work() {
+    use dusk_jubjub::JubJubScalar;
+    use phoenix_core::{Note, PublicKey, SecretKey};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let pk = PublicKey::from(&SecretKey::random(rng));
+
+    let nodes: Vec<Node> = [5, 70, 3, 400, 20, 1, 250, 8]
+        .into_iter()
+        .map(|value| {
+            let note = Note::transparent(rng, &pk, value);
+            (note, value, JubJubScalar::zero())
+        })
+        .collect();
+
+    let values = |selected: Option<Vec<Node>>| {
+        let mut values: Vec<u64> =
+            selected.unwrap().into_iter().map(|(_, v, _)| v).collect();
+        values.sort();
+        values
+    };
+
+    let select = |strategy: Strategy, target| {
+        strategy.select(&mut StdRng::seed_from_u64(7), nodes.clone(), target)
+    };
+
+    assert_eq!(values(select(Strategy::LargestFirst, 500)), [250, 400]);
+    assert_eq!(values(select(Strategy::BranchAndBound, 273)), [3, 20, 250]);
+    assert_eq!(
+        values(select(Strategy::BranchAndBound, 700)),
+        [70, 250, 400]
+    );
+    assert_eq!(values(select(Strategy::Consolidate, 10)), [1, 3, 5, 8]);
+    assert_eq!(values(select(Strategy::Consolidate, 300)), [1, 3, 5, 400]);
+
+    let random: u64 = values(select(Strategy::Random, 300)).iter().sum();
+    assert!(random >= 300);
+
+    for strategy in [
+        Strategy::Lexicographic,
+        Strategy::LargestFirst,
+        Strategy::BranchAndBound,
+        Strategy::Random,
+        Strategy::Consolidate,
+    ] {
+        // the four largest notes don't cover it
+        assert_eq!(select(strategy, 741), None);
+        assert_eq!(strategy.select(rng, Vec::new(), 1), None);
+    }
+}
@@ -0,0 +1,358 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Payloads to interact with the stake contract.
+//!
+//! Staking and unstaking are done in two steps: first the bytes of a
+//! [`Stct`] or [`Wfct`] are sent to a prover, and then the returned proof is
+//! used to build the call to the contract. Withdrawing the reward only takes
+//! a single step.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use bls12_381_bls::{PublicKey as StakePublicKey, SecretKey as StakeSecretKey};
+use dusk_bls12_381::BlsScalar;
+use dusk_bytes::{Serializable, Write};
+use dusk_jubjub::{JubJubAffine, JubJubScalar};
+use dusk_plonk::prelude::Proof;
+use ff::Field;
+use jubjub_schnorr::Signature;
+use phoenix_core::{
+    transaction::stct_signature_message, Crossover, Fee, Note, Ownable,
+    PublicKey, SecretKey, StealthAddress,
+};
+use rand_core::{CryptoRng, RngCore};
+use stake_contract_types::{
+    stake_signature_message, unstake_signature_message,
+    withdraw_signature_message, Stake, Unstake, Withdraw,
+};
+
+use crate::{types, WalletError, MAX_LEN};
+
+/// The size of the bytes of a [`Stct`] sent to the prover.
+pub const STCT_INPUT_SIZE: usize = Fee::SIZE
+    + Crossover::SIZE
+    + u64::SIZE
+    + JubJubScalar::SIZE
+    + BlsScalar::SIZE
+    + Signature::SIZE;
+
+/// The size of the bytes of a [`Wfct`] sent to the prover.
+pub const WFCT_INPUT_SIZE: usize =
+    JubJubAffine::SIZE + u64::SIZE + JubJubScalar::SIZE;
+
+/// A send to contract transparent, moving value to the stake contract.
+#[derive(Debug, Clone)]
+pub struct Stct {
+    /// The fee of the transaction.
+    pub fee: Fee,
+    /// The crossover carrying the value to the contract.
+    pub crossover: Crossover,
+    /// The blinding factor of the crossover.
+    pub blinder: JubJubScalar,
+    /// The value to be staked.
+    pub value: u64,
+    /// The signature of the sender over the crossover.
+    pub signature: Signature,
+}
+
+impl Stct {
+    /// Creates a new STCT of `value`, refunding the crossover to `refund`.
+    pub fn new<Rng>(
+        rng: &mut Rng,
+        sender: &SecretKey,
+        refund: &PublicKey,
+        value: u64,
+        gas_limit: u64,
+        gas_price: u64,
+    ) -> Self
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let blinder = JubJubScalar::random(&mut *rng);
+        let note = Note::obfuscated(rng, refund, value, blinder);
+        let (mut fee, crossover) = note
+            .try_into()
+            .expect("Obfuscated notes should always yield crossovers");
+
+        fee.gas_limit = gas_limit;
+        fee.gas_price = gas_price;
+
+        let address = rusk_abi::contract_to_scalar(&rusk_abi::STAKE_CONTRACT);
+
+        let message = stct_signature_message(&crossover, value, address);
+        let message = dusk_poseidon::sponge::hash(&message);
+
+        let note_sk = sender.sk_r(fee.stealth_address());
+        let signature = note_sk.sign(rng, message);
+
+        Self {
+            fee,
+            crossover,
+            blinder,
+            value,
+            signature,
+        }
+    }
+
+    /// Returns the bytes to be sent to the prover.
+    pub fn to_bytes(&self) -> [u8; STCT_INPUT_SIZE] {
+        let address = rusk_abi::contract_to_scalar(&rusk_abi::STAKE_CONTRACT);
+
+        let mut buf = [0u8; STCT_INPUT_SIZE];
+        let mut writer = &mut buf[..];
+
+        // the buffer is sized to fit all the fields
+        let _ = writer.write(&self.fee.to_bytes());
+        let _ = writer.write(&self.crossover.to_bytes());
+        let _ = writer.write(&self.value.to_bytes());
+        let _ = writer.write(&self.blinder.to_bytes());
+        let _ = writer.write(&address.to_bytes());
+        let _ = writer.write(&self.signature.to_bytes());
+
+        buf
+    }
+
+    /// Returns the call to stake the value with the given proof of the STCT.
+    pub fn call(
+        &self,
+        stake_sk: &StakeSecretKey,
+        counter: u64,
+        proof: &Proof,
+    ) -> Result<types::ExecuteCall, WalletError> {
+        stake_call(stake_sk, self.value, counter, proof)
+    }
+}
+
+/// Returns the call to stake `value`, moved to the contract by a STCT of the
+/// given proof.
+pub fn stake_call(
+    stake_sk: &StakeSecretKey,
+    value: u64,
+    counter: u64,
+    proof: &Proof,
+) -> Result<types::ExecuteCall, WalletError> {
+    let stake_pk = StakePublicKey::from(stake_sk);
+
+    let msg = stake_signature_message(counter, value);
+    let signature = stake_sk.sign(&stake_pk, &msg);
+
+    let stake = Stake {
+        public_key: stake_pk,
+        signature,
+        value,
+        proof: proof.to_bytes().to_vec(),
+    };
+
+    call("stake", &stake)
+}
+
+/// A withdraw from contract transparent, moving the staked value back to the
+/// wallet.
+#[derive(Debug, Clone)]
+pub struct Wfct {
+    /// The fee of the transaction.
+    pub fee: Fee,
+    /// The valueless crossover of the transaction.
+    pub crossover: Crossover,
+    /// The blinding factor of the crossover.
+    pub blinder: JubJubScalar,
+    /// The transparent note receiving the unstaked value.
+    pub unstake_note: Note,
+}
+
+impl Wfct {
+    /// Creates a new WFCT of `value`, to be received by `sender`.
+    pub fn new<Rng>(
+        rng: &mut Rng,
+        sender: &SecretKey,
+        refund: &PublicKey,
+        value: u64,
+        gas_limit: u64,
+        gas_price: u64,
+    ) -> Self
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let blinder = JubJubScalar::random(&mut *rng);
+        let note = Note::obfuscated(rng, refund, 0, blinder);
+        let (mut fee, crossover) = note
+            .try_into()
+            .expect("Obfuscated notes should always yield crossovers");
+
+        fee.gas_limit = gas_limit;
+        fee.gas_price = gas_price;
+
+        let unstake_note =
+            Note::transparent(rng, &PublicKey::from(sender), value);
+
+        Self {
+            fee,
+            crossover,
+            blinder,
+            unstake_note,
+        }
+    }
+
+    /// Returns the bytes to be sent to the prover.
+    pub fn to_bytes(&self) -> [u8; WFCT_INPUT_SIZE] {
+        let value = self.unstake_note.value(None).unwrap_or_default();
+        let blinder = self
+            .unstake_note
+            .blinding_factor(None)
+            .expect("Note is transparent so blinding factor is unencrypted");
+        let commitment: JubJubAffine =
+            self.unstake_note.value_commitment().into();
+
+        let mut buf = [0u8; WFCT_INPUT_SIZE];
+        let mut writer = &mut buf[..];
+
+        // the buffer is sized to fit all the fields
+        let _ = writer.write(&commitment.to_bytes());
+        let _ = writer.write(&value.to_bytes());
+        let _ = writer.write(&blinder.to_bytes());
+
+        buf
+    }
+
+    /// Returns the call to unstake with the given proof of the WFCT.
+    pub fn call(
+        &self,
+        stake_sk: &StakeSecretKey,
+        counter: u64,
+        proof: &Proof,
+    ) -> Result<types::ExecuteCall, WalletError> {
+        unstake_call(stake_sk, counter, &self.unstake_note, proof)
+    }
+}
+
+/// Returns the call to unstake, receiving the value in `unstake_note`.
+pub fn unstake_call(
+    stake_sk: &StakeSecretKey,
+    counter: u64,
+    unstake_note: &Note,
+    proof: &Proof,
+) -> Result<types::ExecuteCall, WalletError> {
+    let stake_pk = StakePublicKey::from(stake_sk);
+
+    let note = unstake_note.to_bytes();
+    let msg = unstake_signature_message(counter, note);
+    let signature = stake_sk.sign(&stake_pk, &msg);
+
+    let unstake = Unstake {
+        public_key: stake_pk,
+        signature,
+        note: note.to_vec(),
+        proof: proof.to_bytes().to_vec(),
+    };
+
+    call("unstake", &unstake)
+}
+
+/// A withdrawal of the reward of a stake.
+#[derive(Debug, Clone)]
+pub struct Withdrawal {
+    /// The fee of the transaction.
+    pub fee: Fee,
+    /// The valueless crossover of the transaction.
+    pub crossover: Crossover,
+    /// The blinding factor of the crossover.
+    pub blinder: JubJubScalar,
+    /// The call to the stake contract.
+    pub call: types::ExecuteCall,
+}
+
+impl Withdrawal {
+    /// Creates a new withdrawal of the reward of `stake_sk`, to be received by
+    /// `sender`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new<Rng>(
+        rng: &mut Rng,
+        sender: &SecretKey,
+        stake_sk: &StakeSecretKey,
+        refund: &PublicKey,
+        counter: u64,
+        gas_limit: u64,
+        gas_price: u64,
+    ) -> Result<Self, WalletError>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let sender_pk = PublicKey::from(sender);
+        let stake_pk = StakePublicKey::from(stake_sk);
+
+        let withdraw_r = JubJubScalar::random(&mut *rng);
+        let address: StealthAddress =
+            sender_pk.gen_stealth_address(&withdraw_r);
+        let nonce = BlsScalar::random(&mut *rng);
+
+        let msg = withdraw_signature_message(counter, address, nonce);
+        let signature = stake_sk.sign(&stake_pk, &msg);
+
+        // Since we're not transferring value *to* the contract the crossover
+        // shouldn't contain a value. As such the note used to created it
+        // should be valueless as well.
+        let blinder = JubJubScalar::random(&mut *rng);
+        let note = Note::obfuscated(rng, refund, 0, blinder);
+        let (mut fee, crossover) = note
+            .try_into()
+            .expect("Obfuscated notes should always yield crossovers");
+
+        fee.gas_limit = gas_limit;
+        fee.gas_price = gas_price;
+
+        let withdraw = Withdraw {
+            public_key: stake_pk,
+            signature,
+            address,
+            nonce,
+        };
+
+        Ok(Self {
+            fee,
+            crossover,
+            blinder,
+            call: call("withdraw", &withdraw)?,
+        })
+    }
+}
+
+fn call<T>(method: &str, payload: &T) -> Result<types::ExecuteCall, WalletError>
+where
+    T: rkyv::Serialize<rkyv::ser::serializers::AllocSerializer<MAX_LEN>>,
+{
+    let payload = rkyv::to_bytes::<_, MAX_LEN>(payload)
+        .map_err(|_| WalletError::Encode("payload"))?
+        .to_vec();
+
+    Ok(types::ExecuteCall {
+        contract: bs58::encode(rusk_abi::STAKE_CONTRACT).into_string(),
+        method: String::from(method),
+        payload,
+    })
+}
+
+/// Serializes the crossover of a stake contract transaction, to be used as
+/// argument of [`crate::tx::UnprovenTransaction::new`].
+pub fn crossover(
+    crossover: &Crossover,
+    blinder: &JubJubScalar,
+    value: u64,
+) -> Result<types::CrossoverType, WalletError> {
+    let crossover: Vec<u8> = rkyv::to_bytes::<_, MAX_LEN>(crossover)
+        .map_err(|_| WalletError::Encode("crossover"))?
+        .to_vec();
+    let blinder: Vec<u8> = rkyv::to_bytes::<_, MAX_LEN>(blinder)
+        .map_err(|_| WalletError::Encode("blinder"))?
+        .to_vec();
+
+    Ok(types::CrossoverType {
+        blinder,
+        crossover,
+        value,
+    })
+}
@@ -0,0 +1,398 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Incremental synchronization of the notes owned by the wallet.
+//!
+//! The [`NoteSync`] consumes the tree leaves streamed from the node in
+//! batches, keeping the notes owned by the wallet along with their nullifiers
+//! and spent status. Its state can be persisted with [`NoteSync::checkpoint`]
+//! and resumed later with [`NoteSync::from_checkpoint`].
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use bytecheck::CheckBytes;
+use dusk_bls12_381::BlsScalar;
+use phoenix_core::{
+    transaction::{ArchivedTreeLeaf, TreeLeaf},
+    Note, SecretKey, ViewKey,
+};
+use rkyv::{AlignedVec, Archive, Deserialize, Serialize};
+
+use crate::{key, BalanceInfo, WalletError, MAX_LEN, RNG_SEED};
+
+/// The size of a rkyv serialized [`TreeLeaf`].
+pub const TREE_LEAF_SIZE: usize = size_of::<ArchivedTreeLeaf>();
+
+/// The version of the checkpoint format, prepended to the checkpoint.
+const CHECKPOINT_VERSION: u8 = 2;
+
+/// The number of the latest blocks whose last leaf position is remembered,
+/// to roll back to exactly.
+pub const MAX_ROLLBACK_BLOCKS: usize = 256;
+
+/// Decodes concatenated rkyv serialized tree leaves, as streamed from the
+/// node.
+pub fn leaves(bytes: &[u8]) -> Result<Vec<TreeLeaf>, WalletError> {
+    let leaves = bytes.chunks_exact(TREE_LEAF_SIZE);
+
+    if !leaves.remainder().is_empty() {
+        return Err(WalletError::InvalidLength("leaves"));
+    }
+
+    leaves
+        .map(|leaf| {
+            rkyv::from_bytes(leaf).map_err(|_| WalletError::Decode("leaves"))
+        })
+        .collect()
+}
+
+/// A note owned by the wallet, as found by the [`NoteSync`].
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct OwnedNote {
+    /// The owned note.
+    pub note: Note,
+    /// The height of the block the note was created in.
+    pub block_height: u64,
+    /// The index of the account owning the note.
+    pub index: u64,
+    /// The decrypted value of the note.
+    pub value: u64,
+    /// The nullifier of the note.
+    pub nullifier: BlsScalar,
+    /// Whether the note was found to be spent.
+    pub spent: bool,
+}
+
+/// The state of the synchronization of the notes of the wallet.
+#[derive(Debug, Clone, Default, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct NoteSync {
+    account_count: u64,
+    last_pos: Option<u64>,
+    block_height: u64,
+    blocks: Vec<(u64, u64)>,
+    notes: Vec<OwnedNote>,
+}
+
+/// The state of the synchronization as laid out in checkpoints of version 1,
+/// before the positions of the latest blocks were remembered.
+#[derive(Debug, Clone, Default, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub(crate) struct NoteSyncV1 {
+    account_count: u64,
+    last_pos: Option<u64>,
+    block_height: u64,
+    notes: Vec<OwnedNote>,
+}
+
+impl From<NoteSyncV1> for NoteSync {
+    fn from(state: NoteSyncV1) -> Self {
+        Self {
+            account_count: state.account_count,
+            last_pos: state.last_pos,
+            block_height: state.block_height,
+            blocks: Vec::new(),
+            notes: state.notes,
+        }
+    }
+}
+
+impl NoteSync {
+    /// Creates a new synchronization from the start of the tree, scanning
+    /// for the notes of the first `account_count` accounts.
+    pub fn new(account_count: usize) -> Self {
+        Self {
+            account_count: account_count as u64,
+            ..Self::default()
+        }
+    }
+
+    /// Resumes a synchronization from a checkpoint, of the current or of an
+    /// earlier version.
+    pub fn from_checkpoint(bytes: &[u8]) -> Result<Self, WalletError> {
+        let (version, state) = match bytes.split_first() {
+            Some((&v, state)) if (1..=CHECKPOINT_VERSION).contains(&v) => {
+                (v, state)
+            }
+            _ => return Err(WalletError::Decode("checkpoint")),
+        };
+
+        // the archived state must be aligned, and the version byte shifts it
+        let mut aligned = AlignedVec::with_capacity(state.len());
+        aligned.extend_from_slice(state);
+
+        match version {
+            1 => rkyv::from_bytes::<NoteSyncV1>(&aligned).map(Self::from),
+            _ => rkyv::from_bytes(&aligned),
+        }
+        .map_err(|_| WalletError::Decode("checkpoint"))
+    }
+
+    /// Serializes the state of the synchronization into a checkpoint.
+    pub fn checkpoint(&self) -> Result<Vec<u8>, WalletError> {
+        let state = rkyv::to_bytes::<_, MAX_LEN>(self)
+            .map_err(|_| WalletError::Encode("checkpoint"))?;
+
+        let mut bytes = Vec::with_capacity(state.len() + 1);
+        bytes.push(CHECKPOINT_VERSION);
+        bytes.extend_from_slice(&state);
+
+        Ok(bytes)
+    }
+
+    /// Returns the number of accounts scanned for notes.
+    pub fn account_count(&self) -> u64 {
+        self.account_count
+    }
+
+    /// Scans for the notes of the first `account_count` accounts from now on,
+    /// returning whether accounts were added.
+    ///
+    /// Since the leaves already processed were not scanned for the notes of
+    /// the added accounts, the cursor is then moved back to the start of the
+    /// tree. The notes already found are kept. Accounts are never removed, so
+    /// a smaller count is ignored.
+    pub fn add_accounts(&mut self, account_count: usize) -> bool {
+        if account_count as u64 <= self.account_count {
+            return false;
+        }
+
+        self.account_count = account_count as u64;
+        self.last_pos = None;
+        self.blocks.clear();
+
+        true
+    }
+
+    /// Returns the position of the last leaf processed, if any.
+    pub fn last_pos(&self) -> Option<u64> {
+        self.last_pos
+    }
+
+    /// Returns the position of the first leaf to fetch from the node.
+    pub fn next_pos(&self) -> u64 {
+        self.last_pos.map(|pos| pos + 1).unwrap_or(0)
+    }
+
+    /// Returns the height of the last block processed.
+    pub fn block_height(&self) -> u64 {
+        self.block_height
+    }
+
+    /// Returns all the notes found, in the order they were found.
+    pub fn notes(&self) -> &[OwnedNote] {
+        &self.notes
+    }
+
+    /// Returns the notes not known to be spent.
+    pub fn unspent_notes(&self) -> impl Iterator<Item = &OwnedNote> {
+        self.notes.iter().filter(|n| !n.spent)
+    }
+
+    /// Returns the nullifiers of the notes not known to be spent, to be
+    /// checked against the node.
+    pub fn unspent_nullifiers(&self) -> Vec<BlsScalar> {
+        self.unspent_notes().map(|n| n.nullifier).collect()
+    }
+
+    /// Computes the balance of the account of the given index, from the notes
+    /// not known to be spent.
+    pub fn balance(&self, index: u64) -> BalanceInfo {
+        let values = self
+            .unspent_notes()
+            .filter(|n| n.index == index)
+            .map(|n| n.value)
+            .collect();
+
+        BalanceInfo::from_values(values)
+    }
+
+    /// Processes a batch of tree leaves, returning the number of owned notes
+    /// found in it.
+    ///
+    /// Leaves at positions already processed are skipped, so batches may
+    /// overlap.
+    pub fn sync<I>(&mut self, seed: &[u8; RNG_SEED], leaves: I) -> usize
+    where
+        I: IntoIterator<Item = TreeLeaf>,
+    {
+        let sks: Vec<SecretKey> = (0..self.account_count)
+            .map(|idx| key::derive_sk(seed, idx))
+            .collect();
+        let vks: Vec<ViewKey> = sks.iter().map(ViewKey::from).collect();
+
+        let synced = self.last_pos;
+        let mut found = 0;
+
+        for TreeLeaf { block_height, note } in leaves {
+            let pos = *note.pos();
+
+            if synced.is_some_and(|last| pos <= last) {
+                continue;
+            }
+
+            self.last_pos = Some(self.last_pos.map_or(pos, |p| p.max(pos)));
+            self.block_height = self.block_height.max(block_height);
+
+            match self.blocks.last_mut() {
+                Some((height, last)) if *height == block_height => {
+                    *last = (*last).max(pos);
+                }
+                _ => self.blocks.push((block_height, pos)),
+            }
+
+            let Some(idx) = key::owner_index(&vks, &note) else {
+                continue;
+            };
+            let Ok(value) = note.value(Some(&vks[idx])) else {
+                continue;
+            };
+
+            if self.notes.iter().any(|n| n.note.pos() == note.pos()) {
+                continue;
+            }
+
+            self.notes.push(OwnedNote {
+                nullifier: note.gen_nullifier(&sks[idx]),
+                note,
+                block_height,
+                index: idx as u64,
+                value,
+                spent: false,
+            });
+            found += 1;
+        }
+
+        if self.blocks.len() > MAX_ROLLBACK_BLOCKS {
+            let excess = self.blocks.len() - MAX_ROLLBACK_BLOCKS;
+            self.blocks.drain(..excess);
+        }
+
+        found
+    }
+
+    /// Marks the notes of the given nullifiers as spent, returning the number
+    /// of notes newly marked.
+    pub fn spend(&mut self, existing_nullifiers: &[BlsScalar]) -> usize {
+        let mut spent = 0;
+
+        for note in self.notes.iter_mut().filter(|n| !n.spent) {
+            if existing_nullifiers.contains(&note.nullifier) {
+                note.spent = true;
+                spent += 1;
+            }
+        }
+
+        spent
+    }
+
+    /// Rolls the synchronization back to the given block height, after a
+    /// reorganization of the chain.
+    ///
+    /// The notes found in later blocks are dropped, and the cursor is moved
+    /// back to the last leaf of the given block, so the leaves after it are
+    /// scanned again. If the block is older than the last
+    /// [`MAX_ROLLBACK_BLOCKS`] processed, the cursor is moved back to the last
+    /// note kept instead, rescanning more leaves than needed. Since the
+    /// transactions spending the kept notes may have been reverted as well,
+    /// their spent status is reset and should be checked against the node
+    /// again.
+    pub fn rollback(&mut self, block_height: u64) {
+        if block_height >= self.block_height {
+            return;
+        }
+
+        self.notes.retain(|n| n.block_height <= block_height);
+        self.notes.iter_mut().for_each(|n| n.spent = false);
+
+        self.blocks.retain(|(height, _)| *height <= block_height);
+
+        self.last_pos = match self.blocks.last() {
+            Some((_, pos)) => Some(*pos),
+            None => self.notes.iter().map(|n| *n.note.pos()).max(),
+        };
+        self.block_height = block_height;
+    }
+}
+
+#[test]
+fn sync_works() {
+    use phoenix_core::PublicKey;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let seed = [0xfa; RNG_SEED];
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    let own = PublicKey::from(&key::derive_sk(&seed, 1));
+    let other = PublicKey::from(&SecretKey::random(rng));
+
+    let leaves: Vec<_> = [(&own, 10), (&other, 20), (&own, 30), (&own, 40)]
+        .into_iter()
+        .enumerate()
+        .map(|(pos, (pk, value))| {
+            let mut note = Note::transparent(rng, pk, value);
+            note.set_pos(pos as u64);
+            TreeLeaf {
+                block_height: pos as u64 + 1,
+                note,
+            }
+        })
+        .collect();
+
+    let bytes: Vec<u8> = leaves
+        .iter()
+        .flat_map(|leaf| rkyv::to_bytes::<_, MAX_LEN>(leaf).unwrap().to_vec())
+        .collect();
+    assert_eq!(self::leaves(&bytes).unwrap(), leaves);
+
+    let mut sync = NoteSync::new(2);
+    assert_eq!(sync.sync(&seed, leaves[..3].to_vec()), 2);
+    assert_eq!(sync.next_pos(), 3);
+
+    // checkpoints of version 1 are still read, remembering no blocks
+    let v1 = NoteSyncV1 {
+        account_count: sync.account_count,
+        last_pos: sync.last_pos,
+        block_height: sync.block_height,
+        notes: sync.notes.clone(),
+    };
+    let v1 = rkyv::to_bytes::<_, MAX_LEN>(&v1).unwrap();
+    let resumed = NoteSync::from_checkpoint(&[&[1], &v1[..]].concat()).unwrap();
+    assert_eq!(resumed.next_pos(), 3);
+    assert_eq!(resumed.notes(), sync.notes());
+    assert!(resumed.blocks.is_empty());
+
+    // resume from the checkpoint, with an overlapping batch
+    let checkpoint = sync.checkpoint().unwrap();
+    let mut sync = NoteSync::from_checkpoint(&checkpoint).unwrap();
+    assert_eq!(sync.sync(&seed, leaves[1..].to_vec()), 1);
+    assert_eq!(sync.block_height(), 4);
+    assert_eq!(sync.balance(1).value, 80);
+
+    let spent = sync.notes()[0].nullifier;
+    assert_eq!(sync.spend(&[spent]), 1);
+    assert_eq!(sync.balance(1).value, 70);
+
+    sync.rollback(3);
+    assert_eq!(sync.next_pos(), 3);
+    assert_eq!(sync.balance(1).value, 40);
+
+    // the cursor is moved to the last leaf of the block, not of the last note
+    sync.rollback(2);
+    assert_eq!(sync.next_pos(), 2);
+    assert_eq!(sync.balance(1).value, 10);
+
+    // adding accounts scans the tree again from the start
+    assert!(!sync.add_accounts(1));
+    assert!(sync.add_accounts(3));
+    assert_eq!(sync.next_pos(), 0);
+    assert_eq!(sync.sync(&seed, leaves.clone()), 2);
+    assert_eq!(sync.notes().len(), 3);
+
+    assert!(NoteSync::from_checkpoint(&checkpoint[1..]).is_err());
+}
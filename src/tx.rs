@@ -12,19 +12,27 @@ use core::mem;
 
 use bytecheck::CheckBytes;
 use dusk_bls12_381::BlsScalar;
-use dusk_jubjub::{JubJubExtended, JubJubScalar, GENERATOR_NUMS_EXTENDED};
+use dusk_bytes::Serializable;
+use dusk_jubjub::{
+    JubJubAffine, JubJubExtended, JubJubScalar, GENERATOR_EXTENDED,
+    GENERATOR_NUMS_EXTENDED,
+};
 use ff::Field;
-use jubjub_schnorr::SignatureDouble;
+use jubjub_schnorr::{PublicKeyDouble, SignatureDouble};
 use phoenix_core::{
     Crossover as PhoenixCrossover, Fee, Note, NoteType, Ownable, PublicKey,
     SecretKey, Transaction, ViewKey,
 };
+use poseidon_merkle::Item;
 use rand_core::{CryptoRng, RngCore};
 use rkyv::{Archive, Deserialize, Serialize};
 use rusk_abi::hash::Hasher;
 use rusk_abi::{ContractId, POSEIDON_TREE_DEPTH};
 
-use crate::{types, types::CrossoverType, utils};
+use crate::{
+    memo, offline, select::CoinSelector, types, types::CrossoverType, utils,
+    WalletError,
+};
 
 /// Chosen arity for the Notes tree implementation.
 pub const POSEIDON_TREE_ARITY: usize = 4;
@@ -115,6 +123,94 @@ pub struct CallData {
     pub payload: Vec<u8>,
 }
 
+impl Output {
+    /// Creates the output note for the given output, with random blinder and
+    /// nonce. A reference is carried by the nonce instead, see [`memo`].
+    ///
+    /// Returns `None` if the receiver is not a valid Base58 public key.
+    pub fn new<Rng>(rng: &mut Rng, output: types::ExecuteOutput) -> Option<Self>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let types::ExecuteOutput {
+            note_type,
+            receiver,
+            ref_id,
+            value,
+        } = output;
+
+        let r#type = match note_type {
+            types::OutputType::Transparent => NoteType::Transparent,
+            types::OutputType::Obfuscated => NoteType::Obfuscated,
+        };
+
+        let r = JubJubScalar::random(&mut *rng);
+        let blinder = JubJubScalar::random(&mut *rng);
+        let receiver = utils::bs58_to_pk(&receiver)?;
+
+        // the reference, if any, is carried by the nonce
+        let nonce = match ref_id {
+            Some(ref_id) => memo::nonce(&r, &receiver, ref_id),
+            None => BlsScalar::random(&mut *rng),
+        };
+        let note =
+            Note::deterministic(r#type, &r, nonce, &receiver, value, blinder);
+
+        Some(Self {
+            note,
+            value,
+            blinder,
+        })
+    }
+}
+
+impl TryFrom<CrossoverType> for WasmCrossover {
+    type Error = WalletError;
+
+    fn try_from(crossover: CrossoverType) -> Result<Self, WalletError> {
+        let CrossoverType {
+            blinder,
+            crossover,
+            value,
+        } = crossover;
+
+        Ok(Self {
+            crossover: rkyv::from_bytes::<PhoenixCrossover>(&crossover)
+                .map_err(|_| WalletError::Decode("crossover"))?,
+            value,
+            blinder: rkyv::from_bytes::<JubJubScalar>(&blinder)
+                .map_err(|_| WalletError::Decode("crossover"))?,
+        })
+    }
+}
+
+impl TryFrom<types::ExecuteCall> for CallData {
+    type Error = WalletError;
+
+    fn try_from(call: types::ExecuteCall) -> Result<Self, WalletError> {
+        let types::ExecuteCall {
+            contract,
+            method,
+            payload,
+        } = call;
+
+        let decoded = bs58::decode(contract)
+            .into_vec()
+            .map_err(|_| WalletError::Decode("contract"))?;
+        if decoded.len() != mem::size_of::<ContractId>() {
+            return Err(WalletError::InvalidLength("contract"));
+        }
+        let mut contract = ContractId::uninitialized();
+        contract.as_bytes_mut().copy_from_slice(&decoded);
+
+        Ok(Self {
+            contract,
+            method,
+            payload,
+        })
+    }
+}
+
 /// A transaction that is yet to be proven.
 #[derive(Debug, Clone, Archive, Serialize, Deserialize)]
 #[archive_attr(derive(CheckBytes))]
@@ -162,82 +258,20 @@ impl UnprovenTransaction {
 
         let anchor = inputs.first().map(|i| i.opening.root().hash)?;
 
-        let mut output_notes = Vec::with_capacity(4);
-        let mut outputs_values = Vec::with_capacity(4);
-
-        for types::ExecuteOutput {
-            note_type,
-            receiver,
-            ref_id: _,
-            value,
-        } in outputs.into_iter()
-        {
-            let r#type = match note_type {
-                types::OutputType::Transparent => NoteType::Transparent,
-                types::OutputType::Obfuscated => NoteType::Obfuscated,
-            };
-
-            let r = JubJubScalar::random(&mut *rng);
-            let blinder = JubJubScalar::random(&mut *rng);
-            let nonce = BlsScalar::random(&mut *rng);
-            let receiver = utils::bs58_to_pk(&receiver)?;
-            let note = Note::deterministic(
-                r#type, &r, nonce, &receiver, value, blinder,
-            );
-
-            output_notes.push(note);
-            outputs_values.push(Output {
-                note,
-                value,
-                blinder,
-            });
-        }
+        let outputs = outputs
+            .into_iter()
+            .map(|output| Output::new(rng, output))
+            .collect::<Option<Vec<_>>>()?;
+        let output_notes: Vec<Note> =
+            outputs.iter().map(|output| output.note).collect();
 
-        let outputs = outputs_values;
-
-        let call = match call {
-            Some(types::ExecuteCall {
-                contract,
-                method,
-                payload,
-            }) => {
-                let decoded = bs58::decode(contract).into_vec().ok()?;
-                if decoded.len() != mem::size_of::<ContractId>() {
-                    return None;
-                }
-                let mut contract = ContractId::uninitialized();
-                contract.as_bytes_mut().copy_from_slice(&decoded);
-                Some(CallData {
-                    contract,
-                    method,
-                    payload,
-                })
-            }
-            None => None,
-        };
+        let call = call.map(CallData::try_from).transpose().ok()?;
         let call_phoenix = call.as_ref().map(|c| {
             (c.contract.to_bytes(), c.method.clone(), c.payload.clone())
         });
 
-        let crossover = crossover.and_then(
-            |CrossoverType {
-                 blinder,
-                 crossover,
-                 value,
-             }| {
-                Some({
-                    WasmCrossover {
-                        crossover: rkyv::from_bytes::<PhoenixCrossover>(
-                            &crossover,
-                        )
-                        .ok()?,
-                        value,
-                        blinder: rkyv::from_bytes::<JubJubScalar>(&blinder)
-                            .ok()?,
-                    }
-                })
-            },
-        );
+        let crossover =
+            crossover.map(WasmCrossover::try_from).transpose().ok()?;
 
         let tx_hash = Transaction::hash_input_bytes_from_components(
             &nullifiers,
@@ -251,7 +285,7 @@ impl UnprovenTransaction {
 
         let inputs = inputs
             .into_iter()
-            .zip(nullifiers.into_iter())
+            .zip(nullifiers)
             .map(
                 |(
                     PreInput {
@@ -294,4 +328,386 @@ impl UnprovenTransaction {
             call,
         })
     }
+
+    /// Computes the hash of the transaction, as signed by the inputs.
+    pub fn hash(&self) -> BlsScalar {
+        let nullifiers: Vec<BlsScalar> =
+            self.inputs.iter().map(|input| input.nullifier).collect();
+        let outputs: Vec<Note> =
+            self.outputs.iter().map(|output| output.note).collect();
+        let call = self.call.as_ref().map(|c| {
+            (c.contract.to_bytes(), c.method.clone(), c.payload.clone())
+        });
+
+        Hasher::digest(Transaction::hash_input_bytes_from_components(
+            &nullifiers,
+            &outputs,
+            &self.anchor,
+            &self.fee,
+            &self.crossover.as_ref().map(|c| c.crossover),
+            &call,
+        ))
+    }
+
+    /// Verifies the transaction spends notes of `sk` correctly, so it can be
+    /// proven.
+    ///
+    /// Checks that the openings of the inputs open to the anchor, that the
+    /// nullifiers and signatures were made by `sk`, that the commitments open
+    /// to the values and blinders, and that the value of the inputs covers
+    /// exactly the outputs, the fee and the crossover. The contract id of the
+    /// call is always of the right length, since [`ContractId`] is sized.
+    ///
+    /// Fails with [`WalletError::InvalidOpening`] for an opening not matching
+    /// the anchor, and with [`WalletError::InvalidTransaction`] naming the
+    /// failing part otherwise.
+    pub fn verify(&self, sk: &SecretKey) -> Result<(), WalletError> {
+        if self.inputs.is_empty() {
+            return Err(WalletError::InvalidTransaction("inputs"));
+        }
+
+        let hash = self.hash();
+
+        for input in &self.inputs {
+            let note = &input.note;
+
+            if input.opening.root().hash != self.anchor
+                || !input.opening.verify(Item::new(note.hash(), ()))
+            {
+                return Err(WalletError::InvalidOpening);
+            }
+
+            let note_sk = sk.sk_r(note.stealth_address());
+            let note_pk_prime = GENERATOR_NUMS_EXTENDED * note_sk.as_ref();
+            if input.nullifier != note.gen_nullifier(sk)
+                || input.note_pk_prime != note_pk_prime
+            {
+                return Err(WalletError::InvalidTransaction("nullifiers"));
+            }
+
+            let pk = PublicKeyDouble::from_raw_unchecked(
+                *note.stealth_address().pk_r().as_ref(),
+                input.note_pk_prime,
+            );
+            if !pk.verify(&input.sig, hash) {
+                return Err(WalletError::InvalidTransaction("signatures"));
+            }
+
+            if !opens(note.value_commitment(), input.value, &input.blinder) {
+                return Err(WalletError::InvalidTransaction("commitments"));
+            }
+        }
+
+        for output in &self.outputs {
+            let commitment = output.note.value_commitment();
+            if !opens(commitment, output.value, &output.blinder) {
+                return Err(WalletError::InvalidTransaction("commitments"));
+            }
+        }
+
+        let mut crossover = 0;
+        if let Some(c) = &self.crossover {
+            let commitment = c.crossover.value_commitment();
+            if !opens(commitment, c.value, &c.blinder) {
+                return Err(WalletError::InvalidTransaction("commitments"));
+            }
+            crossover = c.value;
+        }
+
+        fn sum(mut values: impl Iterator<Item = u64>) -> Option<u64> {
+            values.try_fold(0u64, |sum, v| sum.checked_add(v))
+        }
+
+        let input = sum(self.inputs.iter().map(|i| i.value));
+        let output = sum(self.outputs.iter().map(|o| o.value))
+            .zip(self.fee.gas_limit.checked_mul(self.fee.gas_price))
+            .and_then(|(output, fee)| output.checked_add(fee))
+            .and_then(|output| output.checked_add(crossover));
+
+        match (input, output) {
+            (Some(input), Some(output)) if input == output => Ok(()),
+            _ => Err(WalletError::InvalidTransaction("value")),
+        }
+    }
+
+    /// Serializes the transaction into the variable length encoding expected
+    /// by the prover.
+    ///
+    /// The inputs and the outputs are prefixed by their count, and each input
+    /// by its length. The crossover and the call are prefixed by a `u64` flag
+    /// telling if they are present, and the call is left last since its
+    /// payload has no length prefix.
+    pub fn to_var_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&(self.inputs.len() as u64).to_bytes());
+        for input in &self.inputs {
+            let input = input.to_var_bytes();
+            bytes.extend_from_slice(&(input.len() as u64).to_bytes());
+            bytes.extend(input);
+        }
+
+        bytes.extend_from_slice(&(self.outputs.len() as u64).to_bytes());
+        for output in &self.outputs {
+            bytes.extend_from_slice(&output.note.to_bytes());
+            bytes.extend_from_slice(&output.value.to_bytes());
+            bytes.extend_from_slice(&output.blinder.to_bytes());
+        }
+
+        bytes.extend_from_slice(&self.anchor.to_bytes());
+        bytes.extend_from_slice(&self.fee.to_bytes());
+
+        match &self.crossover {
+            Some(crossover) => {
+                bytes.extend_from_slice(&1u64.to_bytes());
+                bytes.extend_from_slice(&crossover.crossover.to_bytes());
+                bytes.extend_from_slice(&crossover.value.to_bytes());
+                bytes.extend_from_slice(&crossover.blinder.to_bytes());
+            }
+            None => bytes.extend_from_slice(&0u64.to_bytes()),
+        }
+
+        match &self.call {
+            Some(call) => {
+                bytes.extend_from_slice(&1u64.to_bytes());
+                bytes.extend_from_slice(call.contract.as_bytes());
+                bytes.extend_from_slice(&(call.method.len() as u64).to_bytes());
+                bytes.extend_from_slice(call.method.as_bytes());
+                bytes.extend_from_slice(&call.payload);
+            }
+            None => bytes.extend_from_slice(&0u64.to_bytes()),
+        }
+
+        bytes
+    }
+
+    /// Deserializes a transaction from the variable length encoding expected
+    /// by the prover, as produced by [`Self::to_var_bytes`].
+    ///
+    /// Fails with [`WalletError::InvalidLength`] if the bytes are cut short,
+    /// or followed by trailing bytes when there's no call, and with
+    /// [`WalletError::Decode`] if any of the parts is malformed.
+    ///
+    /// The curve points are decoded in affine form, so they may differ in
+    /// representation from the ones encoded, while being the same points.
+    pub fn from_var_bytes(bytes: &[u8]) -> Result<Self, WalletError> {
+        let mut bytes = bytes;
+        let buf = &mut bytes;
+
+        let count: u64 = read(buf)?;
+        let mut inputs = Vec::new();
+        for _ in 0..count {
+            let len: u64 = read(buf)?;
+            let len = usize::try_from(len)
+                .map_err(|_| WalletError::InvalidLength("tx"))?;
+            inputs.push(Input::from_var_bytes(take(buf, len)?)?);
+        }
+
+        let count: u64 = read(buf)?;
+        let mut outputs = Vec::new();
+        for _ in 0..count {
+            outputs.push(Output {
+                note: read(buf)?,
+                value: read(buf)?,
+                blinder: read(buf)?,
+            });
+        }
+
+        let anchor = read(buf)?;
+        let fee = read(buf)?;
+
+        let crossover = match flag(buf)? {
+            true => Some(WasmCrossover {
+                crossover: read(buf)?,
+                value: read(buf)?,
+                blinder: read(buf)?,
+            }),
+            false => None,
+        };
+
+        let call = match flag(buf)? {
+            true => {
+                let contract: [u8; 32] = take(buf, 32)?
+                    .try_into()
+                    .map_err(|_| WalletError::InvalidLength("tx"))?;
+                let len: u64 = read(buf)?;
+                let len = usize::try_from(len)
+                    .map_err(|_| WalletError::InvalidLength("tx"))?;
+                let method = String::from_utf8(take(buf, len)?.to_vec())
+                    .map_err(|_| WalletError::Decode("tx"))?;
+
+                // the payload takes the remaining bytes
+                let payload = mem::take(buf).to_vec();
+
+                Some(CallData {
+                    contract: ContractId::from_bytes(contract),
+                    method,
+                    payload,
+                })
+            }
+            false => None,
+        };
+
+        if !buf.is_empty() {
+            return Err(WalletError::InvalidLength("tx"));
+        }
+
+        Ok(Self {
+            inputs,
+            outputs,
+            anchor,
+            fee,
+            crossover,
+            call,
+        })
+    }
+}
+
+impl Input {
+    /// Serializes the input into the variable length encoding expected by the
+    /// prover, the opening being rkyv serialized last.
+    pub fn to_var_bytes(&self) -> Vec<u8> {
+        let note_pk_prime = JubJubAffine::from(&self.note_pk_prime);
+
+        let opening = rkyv::to_bytes::<_, 256>(&self.opening)
+            .expect("Rkyv serialization should always succeed for an opening");
+
+        let mut bytes = Vec::with_capacity(
+            BlsScalar::SIZE
+                + Note::SIZE
+                + u64::SIZE
+                + JubJubScalar::SIZE
+                + JubJubAffine::SIZE
+                + SignatureDouble::SIZE
+                + opening.len(),
+        );
+
+        bytes.extend_from_slice(&self.nullifier.to_bytes());
+        bytes.extend_from_slice(&self.note.to_bytes());
+        bytes.extend_from_slice(&self.value.to_bytes());
+        bytes.extend_from_slice(&self.blinder.to_bytes());
+        bytes.extend_from_slice(&note_pk_prime.to_bytes());
+        bytes.extend_from_slice(&self.sig.to_bytes());
+        bytes.extend_from_slice(&opening);
+
+        bytes
+    }
+
+    /// Deserializes an input from the variable length encoding expected by
+    /// the prover, taking the whole of `bytes`.
+    pub fn from_var_bytes(bytes: &[u8]) -> Result<Self, WalletError> {
+        let mut bytes = bytes;
+        let buf = &mut bytes;
+
+        let nullifier = read(buf)?;
+        let note = read(buf)?;
+        let value = read(buf)?;
+        let blinder = read(buf)?;
+        let note_pk_prime: JubJubAffine = read(buf)?;
+        let sig = read(buf)?;
+
+        // rkyv expects the archive to be aligned
+        let mut opening = rkyv::AlignedVec::with_capacity(buf.len());
+        opening.extend_from_slice(buf);
+        let opening = rkyv::from_bytes::<Opening>(&opening)
+            .map_err(|_| WalletError::Decode("tx"))?;
+
+        Ok(Self {
+            nullifier,
+            opening,
+            note,
+            value,
+            blinder,
+            note_pk_prime: JubJubExtended::from(note_pk_prime),
+            sig,
+        })
+    }
+}
+
+/// Checks the commitment opens to the value and blinder.
+fn opens(
+    commitment: &JubJubExtended,
+    value: u64,
+    blinder: &JubJubScalar,
+) -> bool {
+    let opening = (GENERATOR_EXTENDED * JubJubScalar::from(value))
+        + (GENERATOR_NUMS_EXTENDED * blinder);
+
+    JubJubAffine::from(commitment) == JubJubAffine::from(opening)
+}
+
+/// Takes the next `len` bytes from the buffer.
+fn take<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], WalletError> {
+    if buf.len() < len {
+        return Err(WalletError::InvalidLength("tx"));
+    }
+
+    let (taken, rest) = buf.split_at(len);
+    *buf = rest;
+
+    Ok(taken)
+}
+
+/// Reads the next serialized value from the buffer.
+fn read<T, const N: usize>(buf: &mut &[u8]) -> Result<T, WalletError>
+where
+    T: Serializable<N>,
+{
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(take(buf, N)?);
+
+    T::from_bytes(&bytes).map_err(|_| WalletError::Decode("tx"))
+}
+
+/// Reads the next `u64` flag from the buffer, telling if a part is present.
+fn flag(buf: &mut &[u8]) -> Result<bool, WalletError> {
+    match read::<u64, 8>(buf)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(WalletError::Decode("tx")),
+    }
+}
+
+/// Builds an unproven transaction spending the notes of `sk`.
+///
+/// The transaction is built as an [`offline::template`] and signed right
+/// away, see there for how the inputs and outputs are picked.
+#[allow(clippy::too_many_arguments)]
+pub fn execute<Rng, Sel, F, E>(
+    rng: &mut Rng,
+    sk: &SecretKey,
+    notes: Vec<utils::Node>,
+    selector: &Sel,
+    opening: F,
+    refund: &PublicKey,
+    refund_type: types::OutputType,
+    outputs: Vec<types::ExecuteOutput>,
+    gas_limit: u64,
+    gas_price: u64,
+    fee: Option<Fee>,
+    crossover: Option<CrossoverType>,
+    call: Option<types::ExecuteCall>,
+) -> Result<UnprovenTransaction, E>
+where
+    Rng: RngCore + CryptoRng,
+    Sel: CoinSelector,
+    F: FnMut(u64) -> Result<Opening, E>,
+    E: From<WalletError>,
+{
+    let template = offline::template(
+        rng,
+        notes,
+        selector,
+        opening,
+        refund,
+        refund_type,
+        outputs,
+        gas_limit,
+        gas_price,
+        fee,
+        crossover,
+        call,
+    )?;
+
+    Ok(template.sign_unverified(rng, sk)?)
 }
@@ -14,9 +14,28 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
+#[doc = " The balance of a single account of the wallet"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct AccountBalanceType {
+    #[doc = " The index of the account in the seed"]
+    pub index: u64,
+    #[doc = " Maximum value per transaction sent by this account"]
+    pub maximum: u64,
+    #[doc = " Balance of the obfuscated notes of this account"]
+    pub obfuscated: u64,
+    #[doc = " Balance of the transparent notes of this account"]
+    pub transparent: u64,
+    #[doc = " Total computed balance of this account"]
+    pub value: u64,
+}
+#[doc = " The index of an account of the wallet"]
+pub type AccountIndex = u64;
 #[doc = " The arguments of the balance function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct BalanceArgs {
+    #[doc = " The number of accounts, starting from index 0, to derive keys for. Defaults to 1"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_count: Option<u64>,
     #[doc = " A rkyv serialized [Vec<phoenix_core::Note>]; all notes should have their keys derived from "]
     #[doc = " `seed`"]
     pub notes: Vec<u8>,
@@ -26,11 +45,28 @@ pub struct BalanceArgs {
 #[doc = " The response of the balance function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct BalanceResponse {
-    #[doc = " Maximum value per transaction"]
+    #[doc = " The balance of each account, in index order"]
+    pub accounts: Vec<AccountBalanceType>,
+    #[doc = " Maximum value per transaction, from any single account"]
     pub maximum: u64,
+    #[doc = " Balance of the obfuscated notes"]
+    pub obfuscated: u64,
+    #[doc = " Balance of the transparent notes"]
+    pub transparent: u64,
     #[doc = " Total computed balance"]
     pub value: u64,
 }
+#[doc = " The arguments of the check_accounts_ownership function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct CheckAccountsOwnershipArgs {
+    #[doc = " The number of accounts, starting from index 0, to derive keys for. Defaults to 1"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_count: Option<u64>,
+    #[doc = " The rkyv serialized tree leaves to scan, concatenated"]
+    pub leaves: Vec<u8>,
+    #[doc = " Seed used to derive the keys of the wallet"]
+    pub seed: Vec<u8>,
+}
 #[doc = " Response of check_note_ownership function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct CheckNoteOwnershipResponse {
@@ -44,6 +80,52 @@ pub struct CheckNoteOwnershipResponse {
     pub nullifiers: Vec<Vec<u8>>,
     #[doc = " The public spend keys of the notes in the same order the notes were returned"]
     pub public_spend_keys: Vec<String>,
+    #[doc = " The payment references carried by the notes in the same order the notes were returned, or "]
+    #[doc = " null if the note carries none"]
+    pub ref_ids: Vec<Option<u64>>,
+}
+#[doc = " A strategy to select the notes spent by a transaction"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub enum CoinSelection {
+    Lexicographic,
+    LargestFirst,
+    BranchAndBound,
+    Random,
+    Consolidate,
+}
+#[doc = " Arguments of the consolidate function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ConsolidateArgs {
+    #[doc = " The gas limit of each transaction"]
+    pub gas_limit: u64,
+    #[doc = " The gas price per unit for each transaction"]
+    pub gas_price: u64,
+    #[doc = " A rkyv serialized [Vec<phoenix_core::Note>] of the unspent notes of the sender"]
+    pub inputs: Vec<u8>,
+    #[doc = " A rkyv serialized [Vec<(tx::Opening, u64)>] of the openings of the inputs, along with the "]
+    #[doc = " positions of the notes, see rkyv.rs/rkyv_openings_array"]
+    pub openings: Vec<u8>,
+    #[doc = " Seed used to derive the entropy for the notes"]
+    pub rng_seed: Vec<u8>,
+    #[doc = " Seed used to derive the keys of the wallet"]
+    pub seed: Vec<u8>,
+    #[doc = " The index of the sender in the seed"]
+    pub sender_index: u64,
+}
+#[doc = " Response of the consolidate function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ConsolidateResponse {
+    #[doc = " The number of notes left out, since merging them costs more than they are worth"]
+    pub dust: u64,
+    #[doc = " The projected fee over all the rounds, if all the gas is used"]
+    pub fee: u64,
+    #[doc = " The number of rounds needed to make the whole balance spendable. The transactions of a "]
+    #[doc = " round must be executed before the next round can be planned"]
+    pub rounds: u64,
+    #[doc = " The number of transactions over all the rounds"]
+    pub transactions: u64,
+    #[doc = " The rkyv serialized unproven transactions of the first round, in order"]
+    pub txs: Vec<Vec<u8>>,
 }
 #[doc = " The value of the Crossover and the blinder"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
@@ -55,6 +137,81 @@ pub struct CrossoverType {
     #[doc = " The value of the crossover"]
     pub value: u64,
 }
+#[doc = " Arguments of the decode_tx function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct DecodeTxArgs {
+    #[doc = " The bytes of a proven transaction, as returned by prove_tx"]
+    pub bytes: Vec<u8>,
+}
+#[doc = " Response of the decode_tx function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct DecodeTxResponse {
+    #[doc = " The hex encoded Merkle root of the state for the openings of the inputs"]
+    pub anchor: String,
+    #[doc = " The bs58 encoded contract called, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+    #[doc = " The hex encoded crossover, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crossover: Option<String>,
+    #[doc = " The gas limit of the transaction"]
+    pub gas_limit: u64,
+    #[doc = " The gas price of the transaction"]
+    pub gas_price: u64,
+    #[doc = " The hex encoded hash of the transaction"]
+    pub hash: String,
+    #[doc = " The method called, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[doc = " The hex encoded nullifiers of the notes spent"]
+    pub nullifiers: Vec<String>,
+    #[doc = " The notes created"]
+    pub outputs: Vec<DecodedNoteType>,
+    #[doc = " The length of the payload of the call, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_len: Option<u64>,
+    #[doc = " The length of the proof"]
+    pub proof_len: u64,
+}
+#[doc = " A note created by a proven transaction"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct DecodedNoteType {
+    #[doc = " The hex encoded hash of the note"]
+    pub hash: String,
+    #[doc = " The type of the note"]
+    pub note_type: OutputType,
+    #[doc = " The bs58 encoded one-time address of the receiver"]
+    pub stealth_address: String,
+    #[doc = " The value of the note, if transparent"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<u64>,
+}
+#[doc = " Arguments of the discover_accounts function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct DiscoverAccountsArgs {
+    #[doc = " The number of accounts already known to be in use, as returned by a previous call. Defaults "]
+    #[doc = " to 0"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_count: Option<u64>,
+    #[doc = " The number of consecutive unused accounts after which the scan stops. Defaults to 20"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap_limit: Option<u64>,
+    #[doc = " The concatenated rkyv serialized [phoenix_core::transaction::TreeLeaf]s, as streamed from "]
+    #[doc = " the node"]
+    pub leaves: Vec<u8>,
+    #[doc = " Seed used to derive the keys of the wallet"]
+    pub seed: Vec<u8>,
+}
+#[doc = " Response of the discover_accounts function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct DiscoverAccountsResponse {
+    #[doc = " The number of accounts in use, that is the highest used index plus one"]
+    pub account_count: u64,
+    #[doc = " The indexes of the accounts owning at least one of the given leaves"]
+    pub indexes: Vec<AccountIndex>,
+    #[doc = " The last position of the notes in the given leaves"]
+    pub last_pos: u64,
+}
 #[doc = " Arguments of the dusk_to_lux function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct DuskToLuxArgs {
@@ -67,12 +224,75 @@ pub struct DuskToLuxResponse {
     #[doc = " The amount of lux that was converted from dusk"]
     pub lux: f64,
 }
+#[doc = " The response of a failed function call, pointed to by the result when its status is set"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ErrorResponse {
+    #[doc = " The stable numeric code of the error"]
+    pub code: u64,
+    #[doc = " The name of the argument that caused the error, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[doc = " A human readable description of the error"]
+    pub message: String,
+}
+#[doc = " Arguments of the estimate_fee function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct EstimateFeeArgs {
+    #[doc = " The strategy to select the notes to spend, defaulting to Lexicographic"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coin_selection: Option<CoinSelection>,
+    #[doc = " The minimum gas price of the network, defaulting to the lowest accepted"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<u64>,
+    #[doc = " How much to pay over the minimum gas price, defaulting to Minimum"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price_policy: Option<GasPricePolicy>,
+    #[doc = " The gas spent by each part of the transaction, as charged by the network"]
+    pub gas_schedule: GasSchedule,
+    #[doc = " A rkyv serialized [Vec<phoenix_core::Note>] of the unspent notes of the sender"]
+    pub inputs: Vec<u8>,
+    #[doc = " The operation performed by the transaction"]
+    pub operation: FeeOperation,
+    #[doc = " The number of outputs, change excluded, defaulting to 1 for a transfer and 0 otherwise"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_count: Option<u64>,
+    #[doc = " The length of the payload of a contract call, in bytes"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_len: Option<u64>,
+    #[doc = " Seed used to derive the entropy for the Random coin selection, which requires it"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rng_seed: Option<Vec<u8>>,
+    #[doc = " The value to send. If missing, the estimate is for sending the spendable value"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<u64>,
+    #[doc = " A rkyv serialized [phoenix_core::ViewKey] of the sender, as returned by view_keys"]
+    pub view_key: Vec<u8>,
+}
+#[doc = " Response of the estimate_fee function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct EstimateFeeResponse {
+    #[doc = " The maximum fee paid, in lux"]
+    pub fee: u64,
+    #[doc = " The recommended gas limit"]
+    pub gas_limit: u64,
+    #[doc = " The gas price per unit"]
+    pub gas_price: u64,
+    #[doc = " The number of notes spent"]
+    pub input_count: u64,
+    #[doc = " The number of notes created, change included"]
+    pub output_count: u64,
+    #[doc = " The largest value that can be sent after paying the fee"]
+    pub spendable: u64,
+}
 #[doc = " The arguments of the execute function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct ExecuteArgs {
     #[doc = " A call to a contract method"]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub call: Option<ExecuteCall>,
+    #[doc = " The strategy to select the input notes with. Defaults to Lexicographic"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coin_selection: Option<CoinSelection>,
     #[doc = " The crossover value"]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub crossover: Option<CrossoverType>,
@@ -89,15 +309,28 @@ pub struct ExecuteArgs {
     #[doc = " positions of the notes the openings are of in a tuple (opening, position) rkyv serialized, "]
     #[doc = " see rkyv.rs/rkyv_openings_array"]
     pub openings: Vec<u8>,
-    #[doc = " The transfer output note"]
+    #[doc = " The transfer output note. Deprecated in favor of outputs, and put before them if both are "]
+    #[doc = " given"]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<ExecuteOutput>,
+    #[doc = " The transfer output notes. Together with the change, they can't be more than the circuit "]
+    #[doc = " supports"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<ExecuteOutput>>,
     #[doc = " The refund addressin Base58 format"]
     pub refund: String,
+    #[doc = " The type of the note the change is refunded in. Defaults to Obfuscated"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refund_note_type: Option<OutputType>,
     #[doc = " Seed used to derive the entropy for the notes"]
     pub rng_seed: Vec<u8>,
     #[doc = " Seed used to derive the keys of the wallet"]
     pub seed: Vec<u8>,
+    #[doc = " Send the maximum spendable value, net of the fee, to the single output, overriding its "]
+    #[doc = " value and leaving no change. The fee is computed from the gas limit and price, so it cannot "]
+    #[doc = " be combined with a given fee"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_all: Option<bool>,
     #[doc = " The index of the sender in the seed"]
     pub sender_index: u64,
 }
@@ -130,6 +363,69 @@ pub struct ExecuteResponse {
     #[doc = " The rkyv serialized unproven transaction"]
     pub tx: Vec<u8>,
 }
+#[doc = " The arguments of the execute_template function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ExecuteTemplateArgs {
+    #[doc = " A call to a contract method"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub call: Option<ExecuteCall>,
+    #[doc = " The strategy to select the input notes with. Defaults to Lexicographic"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coin_selection: Option<CoinSelection>,
+    #[doc = " The crossover value"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crossover: Option<CrossoverType>,
+    #[doc = " A rkyv serialized Fee"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Vec<u8>>,
+    #[doc = " The gas limit of the transaction"]
+    pub gas_limit: u64,
+    #[doc = " The gas price per unit for the transaction"]
+    pub gas_price: u64,
+    #[doc = " A rkyv serialized [Vec<phoenix_core::Note>] to be used as inputs"]
+    pub inputs: Vec<u8>,
+    #[doc = " A rkyv serialized [Vec<tx::Opening>] to open the inputs to a Merkle root, along with the "]
+    #[doc = " positions of the notes the openings are of in a tuple (opening, position) rkyv serialized, "]
+    #[doc = " see rkyv.rs/rkyv_openings_array"]
+    pub openings: Vec<u8>,
+    #[doc = " The transfer output note. Deprecated in favor of outputs, and put before them if both are "]
+    #[doc = " given"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<ExecuteOutput>,
+    #[doc = " The transfer output notes. Together with the change, they can't be more than the circuit "]
+    #[doc = " supports"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<ExecuteOutput>>,
+    #[doc = " The refund addressin Base58 format"]
+    pub refund: String,
+    #[doc = " The type of the note the change is refunded in. Defaults to Obfuscated"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refund_note_type: Option<OutputType>,
+    #[doc = " Seed used to derive the entropy for the notes"]
+    pub rng_seed: Vec<u8>,
+    #[doc = " Send the maximum spendable value, net of the fee, to the single output, overriding its "]
+    #[doc = " value and leaving no change. The fee is computed from the gas limit and price, so it cannot "]
+    #[doc = " be combined with a given fee"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_all: Option<bool>,
+    #[doc = " A rkyv serialized [phoenix_core::ViewKey] of the sender, as returned by view_keys"]
+    pub view_key: Vec<u8>,
+}
+#[doc = " Response of the execute_template function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ExecuteTemplateResponse {
+    #[doc = " The unsigned transaction template, in its transport encoding"]
+    pub template: Vec<u8>,
+}
+#[doc = " The operation performed by a transaction"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub enum FeeOperation {
+    Transfer,
+    Stake,
+    Unstake,
+    Withdraw,
+    Call,
+}
 #[doc = " The arguments of the filter_notes function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct FilterNotesArgs {
@@ -148,6 +444,31 @@ pub struct FilterNulifierNotesArgs {
     #[doc = " The seed to generate the view keys from"]
     pub seed: Vec<u8>,
 }
+#[doc = " How much to pay for the gas, over the minimum price of the network"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub enum GasPricePolicy {
+    Minimum,
+    Standard,
+    Priority,
+}
+#[doc = " The gas spent by each part of a transaction, as charged by the network"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct GasSchedule {
+    #[doc = " The gas spent by a contract call, besides its payload"]
+    pub call: u64,
+    #[doc = " The gas spent for each input note"]
+    pub input: u64,
+    #[doc = " The gas spent for each output note"]
+    pub output: u64,
+    #[doc = " The gas spent for each byte of the payload of a contract call"]
+    pub payload_byte: u64,
+    #[doc = " The gas spent by the stake contract to verify a STCT or WFCT proof"]
+    pub stake: u64,
+    #[doc = " The gas spent by any transaction, verifying its proof"]
+    pub tx: u64,
+    #[doc = " The gas spent by the stake contract to withdraw the reward"]
+    pub withdraw: u64,
+}
 #[doc = " Arguments for get_allow_call_data function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct GetAllowCallDataArgs {
@@ -205,6 +526,9 @@ pub struct GetHistoryResponse {
 #[doc = " Retrieve the seed bytes from the mnemonic and passphrase"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct GetMnemonicSeedArgs {
+    #[doc = " The language of the mnemonic. Detected from its words if not given"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<MnemonicLanguage>,
     #[doc = " The mnemonic string"]
     pub mnemonic: String,
     #[doc = " The passphrase tied to that mnemonic"]
@@ -340,11 +664,57 @@ pub struct MergeNotesArgs {
     #[doc = " All serialized list of notes to be merged"]
     pub notes: Vec<Vec<u8>>,
 }
+#[doc = " The language of the word list of a mnemonic. Only English is available unless built with the "]
+#[doc = " all-languages feature"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub enum MnemonicLanguage {
+    English,
+    ChineseSimplified,
+    ChineseTraditional,
+    Czech,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Portuguese,
+    Spanish,
+}
 #[doc = " The arguments of the mnemonic_new function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct MnemonicNewArgs {
+    #[doc = " The language of the word list. Defaults to English"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<MnemonicLanguage>,
     #[doc = " Cryptographically secure [u8; 64]"]
     pub rng_seed: Vec<u8>,
+    #[doc = " The number of words of the mnemonic, one of 12, 15, 18, 21 or 24. Defaults to 24"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub word_count: Option<u64>,
+}
+#[doc = " The arguments of the mnemonic_suggestions function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct MnemonicSuggestionsArgs {
+    #[doc = " The language of the word list. Defaults to English"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<MnemonicLanguage>,
+    #[doc = " The beginning of the word being typed"]
+    pub prefix: String,
+}
+#[doc = " The response of the mnemonic_suggestions function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct MnemonicSuggestionsResponse {
+    #[doc = " Up to 8 words of the word list starting with the prefix"]
+    pub words: Vec<String>,
+}
+#[doc = " An unknown word of a mnemonic"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct MnemonicWordType {
+    #[doc = " The position of the word in the mnemonic, starting from 0"]
+    pub position: u64,
+    #[doc = " The words of the word list sharing the longest prefix with the unknown word"]
+    pub suggestions: Vec<String>,
+    #[doc = " The unknown word"]
+    pub word: String,
 }
 #[doc = " Response of the new_mnemonic function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
@@ -365,10 +735,16 @@ pub struct NoteInfoType {
     pub pk: String,
     #[doc = " position of the note"]
     pub pos: u64,
+    #[doc = " The payment reference carried by the note, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ref_id: Option<u64>,
 }
 #[doc = " The arguments of the nullifiers function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct NullifiersArgs {
+    #[doc = " The number of accounts, starting from index 0, to derive keys for. Defaults to 1"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_count: Option<u64>,
     #[doc = " A rkyv serialized [Vec<phoenix_core::Note>] to have nullifiers generated"]
     pub notes: Vec<u8>,
     #[doc = " Seed used to derive the keys of the wallet"]
@@ -388,6 +764,119 @@ pub enum OutputType {
     Transparent,
     Obfuscated,
 }
+#[doc = " The arguments of the payment_output function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PaymentOutputArgs {
+    #[doc = " The current UNIX time, in seconds, to reject expired requests with"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub now: Option<u64>,
+    #[doc = " The `dusk:` URI of the payment request"]
+    pub uri: String,
+    #[doc = " The value to pay, in LUX, if the request doesn't specify one"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<u64>,
+}
+#[doc = " The response of the payment_output function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PaymentOutputResponse {
+    #[doc = " The UNIX time, in seconds, the request expires at, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<u64>,
+    #[doc = " The output paying the request, to be passed to execute"]
+    pub output: ExecuteOutput,
+}
+#[doc = " The arguments of the payment_uri function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PaymentUriArgs {
+    #[doc = " The UNIX time, in seconds, the request expires at"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<u64>,
+    #[doc = " The type of the note to pay with. Defaults to obfuscated"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note_type: Option<OutputType>,
+    #[doc = " The address of the receiver in Base58 format, plain or checksummed"]
+    pub receiver: String,
+    #[doc = " The payment reference to be carried by the note"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ref_id: Option<u64>,
+    #[doc = " The value requested, in LUX"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<u64>,
+}
+#[doc = " The response of the payment_uri function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PaymentUriResponse {
+    #[doc = " The `dusk:` URI of the payment request"]
+    pub uri: String,
+}
+#[doc = " A note spent by a transaction"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PreviewInputType {
+    #[doc = " The type of the note"]
+    pub note_type: OutputType,
+    #[doc = " The position of the note in the tree"]
+    pub pos: u64,
+    #[doc = " The value of the note"]
+    pub value: u64,
+}
+#[doc = " A note created by a transaction"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PreviewOutputType {
+    #[doc = " If the note is the change, sent back to the sender"]
+    pub change: bool,
+    #[doc = " The type of the note"]
+    pub note_type: OutputType,
+    #[doc = " The bs58 encoded one-time address of the receiver"]
+    pub stealth_address: String,
+    #[doc = " The value of the note"]
+    pub value: u64,
+}
+#[doc = " Arguments of the preview_tx function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PreviewTxArgs {
+    #[doc = " Seed used to derive the keys of the wallet, to tell the change apart"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<Vec<u8>>,
+    #[doc = " The index of the sender in the seed, defaulting to 0"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_index: Option<u64>,
+    #[doc = " A rkyv serialized [crate::tx::UnprovenTransaction]"]
+    pub tx: Vec<u8>,
+}
+#[doc = " Response of the preview_tx function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PreviewTxResponse {
+    #[doc = " The anomalies found"]
+    pub anomalies: Vec<TxAnomaly>,
+    #[doc = " The value sent back to the sender"]
+    pub change: u64,
+    #[doc = " The bs58 encoded contract called, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+    #[doc = " The value of the crossover, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crossover: Option<u64>,
+    #[doc = " The maximum fee paid, if all the gas is used"]
+    pub fee: u64,
+    #[doc = " The gas limit of the transaction"]
+    pub gas_limit: u64,
+    #[doc = " The gas price of the transaction"]
+    pub gas_price: u64,
+    #[doc = " The notes spent"]
+    pub inputs: Vec<PreviewInputType>,
+    #[doc = " The method called, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[doc = " The notes created"]
+    pub outputs: Vec<PreviewOutputType>,
+    #[doc = " If the transaction sends no value to others, moving it between notes of the sender, as a "]
+    #[doc = " consolidation does"]
+    pub self_transfer: bool,
+    #[doc = " The value sent to others, crossover included"]
+    pub sent: u64,
+    #[doc = " The value of the notes spent"]
+    pub total_input: u64,
+}
 #[doc = " Arguments of the prove_tx function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct ProveTxArgs {
@@ -415,6 +904,12 @@ pub struct PublicKeysAndNotesType {
 #[doc = " The arguments of the public_keys function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct PublicKeysArgs {
+    #[doc = " The number of accounts, starting from index 0, to derive keys for. Defaults to 1"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_count: Option<u64>,
+    #[doc = " Whether to append a CB58 checksum to the keys. Defaults to false"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<bool>,
     #[doc = " Seed used to derive the keys of the wallet"]
     pub seed: Vec<u8>,
 }
@@ -468,6 +963,118 @@ pub struct SeedArgs {
     #[doc = " An arbitrary sequence of bytes used to generate a secure seed"]
     pub passphrase: Vec<u8>,
 }
+#[doc = " The arguments of the shamir_combine function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ShamirCombineArgs {
+    #[doc = " The language of the mnemonic, if the secret is its entropy. Defaults to English"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<MnemonicLanguage>,
+    #[doc = " The passphrase of the mnemonic, if the secret is its entropy. Defaults to empty, and is "]
+    #[doc = " rejected if the secret is a seed"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+    #[doc = " The word shares to combine, of any group"]
+    pub shares: Vec<String>,
+}
+#[doc = " The response of the shamir_combine function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ShamirCombineResponse {
+    #[doc = " The recovered secret"]
+    pub secret: Vec<u8>,
+    #[doc = " The seed of the wallet, to derive its keys with"]
+    pub seed: Vec<u8>,
+}
+#[doc = " A group of Shamir's secret shares"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ShamirGroupType {
+    #[doc = " The number of shares of the group, up to 16"]
+    pub count: u64,
+    #[doc = " The number of shares of the group needed to recover the group share"]
+    pub threshold: u64,
+}
+#[doc = " The word shares of a group"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ShamirSharesType {
+    #[doc = " The shares, as words of the English BIP-39 word list"]
+    pub shares: Vec<String>,
+}
+#[doc = " The arguments of the shamir_split function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ShamirSplitArgs {
+    #[doc = " The number of groups needed to recover the secret"]
+    pub group_threshold: u64,
+    #[doc = " The groups to split the secret into, up to 16"]
+    pub groups: Vec<ShamirGroupType>,
+    #[doc = " Seed used to derive the random coefficients of the shares"]
+    pub rng_seed: Vec<u8>,
+    #[doc = " The secret to split, either the seed of the wallet or the entropy of its mnemonic"]
+    pub secret: Vec<u8>,
+}
+#[doc = " The response of the shamir_split function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ShamirSplitResponse {
+    #[doc = " The shares of every group, in the order the groups were given"]
+    pub groups: Vec<ShamirSharesType>,
+}
+#[doc = " Arguments of the sign_template function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SignTemplateArgs {
+    #[doc = " Seed used to derive the entropy for the notes"]
+    pub rng_seed: Vec<u8>,
+    #[doc = " Seed used to derive the keys of the wallet"]
+    pub seed: Vec<u8>,
+    #[doc = " The index of the sender in the seed"]
+    pub sender_index: u64,
+    #[doc = " The unsigned transaction template, in its transport encoding"]
+    pub template: Vec<u8>,
+}
+#[doc = " Response of the sign_template function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SignTemplateResponse {
+    #[doc = " The summary of the signed transaction, to be confirmed by the user before it is proven"]
+    pub preview: PreviewTxResponse,
+    #[doc = " The rkyv serialized unproven transaction"]
+    pub tx: Vec<u8>,
+}
+#[doc = " Arguments of the sync_notes function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SyncNotesArgs {
+    #[doc = " The number of accounts to scan for notes. Defaults to 1. If greater than the one of the "]
+    #[doc = " checkpoint, the synchronization starts over from the beginning of the tree to scan for the "]
+    #[doc = " notes of the added accounts, and the given leaves are ignored"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_count: Option<u64>,
+    #[doc = " The checkpoint returned by a previous call. If absent, the synchronization starts from the "]
+    #[doc = " beginning of the tree"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<Vec<u8>>,
+    #[doc = " The rkyv serialized nullifiers of the unspent notes, as found to exist by the node"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub existing_nullifiers: Option<Vec<u8>>,
+    #[doc = " The concatenated rkyv serialized [phoenix_core::transaction::TreeLeaf]s, as streamed from "]
+    #[doc = " the node starting at the next position of the checkpoint"]
+    pub leaves: Vec<u8>,
+    #[doc = " The height of the last block kept after a reorganization of the chain, rolled back to "]
+    #[doc = " before the leaves are processed"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollback_height: Option<u64>,
+    #[doc = " Seed used to derive the keys of the wallet"]
+    pub seed: Vec<u8>,
+}
+#[doc = " Response of the sync_notes function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SyncNotesResponse {
+    #[doc = " The height of the last block processed"]
+    pub block_height: u64,
+    #[doc = " The checkpoint to persist and pass to the next call"]
+    pub checkpoint: Vec<u8>,
+    #[doc = " The position of the first leaf to fetch in the next call"]
+    pub next_pos: u64,
+    #[doc = " The rkyv serialized nullifiers of the unspent notes, to be checked against the node"]
+    pub nullifiers: Vec<u8>,
+    #[doc = " The owned notes not known to be spent"]
+    pub unspent_notes: Vec<NoteInfoType>,
+}
 #[doc = " The direction of the transaction"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub enum TransactionDirectionType {
@@ -487,9 +1094,22 @@ pub struct TransactionHistoryType {
     pub fee: u64,
     #[doc = " The hash of the transaction"]
     pub id: String,
+    #[doc = " Transparent if the notes of the account in the transaction are all transparent, exposing "]
+    #[doc = " the amount"]
+    pub note_type: OutputType,
+    #[doc = " The payment reference carried by the notes received, if any"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ref_id: Option<u64>,
     #[doc = " The type of the transaction"]
     pub tx_type: String,
 }
+#[doc = " Something the user should be warned about before confirming a transaction"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub enum TxAnomaly {
+    FeeExceedsValue,
+    TransparentOutput,
+    Unbalanced,
+}
 #[doc = " Metadata of the transaction, used in calculating history"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct TxDataType {
@@ -535,9 +1155,175 @@ pub struct UnspentSpentNotesArgs {
     #[doc = " Array of bs58 encoded string to be sent with the response of the function"]
     pub pks: Vec<String>,
 }
+#[doc = " The arguments of the validate_address function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ValidateAddressArgs {
+    #[doc = " The Base58 address to validate, plain or checksummed"]
+    pub address: String,
+}
+#[doc = " The response of the validate_address function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ValidateAddressResponse {
+    #[doc = " Whether the address carries a valid checksum"]
+    pub checksummed: bool,
+    #[doc = " The position of the first character out of the Base58 alphabet, if that is the reason"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u64>,
+    #[doc = " Why the address is invalid, if it is; one of `alphabet`, `length`, `checksum` or `point`"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[doc = " Whether the address is a valid public key"]
+    pub valid: bool,
+}
+#[doc = " The arguments of the validate_mnemonic function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ValidateMnemonicArgs {
+    #[doc = " The language of the mnemonic. Detected from its words if not given"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<MnemonicLanguage>,
+    #[doc = " The mnemonic, possibly incomplete"]
+    pub mnemonic: String,
+}
+#[doc = " The response of the validate_mnemonic function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct ValidateMnemonicResponse {
+    #[doc = " The words not in the word list of the language"]
+    pub invalid_words: Vec<MnemonicWordType>,
+    #[doc = " The language the mnemonic was validated in"]
+    pub language: MnemonicLanguage,
+    #[doc = " Why the mnemonic is invalid, if it is; one of `unknown_word`, `word_count` or `checksum`"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[doc = " Whether the mnemonic is complete and valid"]
+    pub valid: bool,
+    #[doc = " The number of words of the mnemonic"]
+    pub word_count: u64,
+}
+#[doc = " Arguments of the verify_unproven_tx function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct VerifyUnprovenTxArgs {
+    #[doc = " Seed used to derive the keys of the wallet"]
+    pub seed: Vec<u8>,
+    #[doc = " The index of the sender in the seed"]
+    pub sender_index: u64,
+    #[doc = " The rkyv serialized [crate::tx::UnprovenTransaction]"]
+    pub unproven_tx: Vec<u8>,
+}
+#[doc = " Response of the verify_unproven_tx function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct VerifyUnprovenTxResponse {
+    #[doc = " The hex encoded hash of the transaction, as signed by the inputs"]
+    pub hash: String,
+}
 #[doc = " The arguments of the view_keys function"]
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct ViewKeysArgs {
+    #[doc = " The number of accounts, starting from index 0, to derive keys for. Defaults to 1"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_count: Option<u64>,
+    #[doc = " Seed used to derive the keys of the wallet"]
+    pub seed: Vec<u8>,
+}
+#[doc = " The metadata of an account of the wallet"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct WalletAccountType {
+    #[doc = " The index the keys of the account are derived with, lower than 1024"]
+    pub index: u64,
+    #[doc = " The name given to the account by the user"]
+    pub label: String,
+}
+#[doc = " A labeled entry of the address book of the wallet"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct WalletContactType {
+    #[doc = " The Base58 address, plain or checksummed"]
+    pub address: String,
+    #[doc = " The name given to the address by the user"]
+    pub label: String,
+}
+#[doc = " Arguments of the wallet_export function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct WalletExportArgs {
+    #[doc = " The accounts in use"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accounts: Option<Vec<WalletAccountType>>,
+    #[doc = " The checkpoint of the note synchronization, as returned by sync_notes"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<Vec<u8>>,
+    #[doc = " The address book"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contacts: Option<Vec<WalletContactType>>,
+    #[doc = " The password to encrypt the wallet file with"]
+    pub password: String,
+    #[doc = " Seed used to derive the salt and the nonce of the encryption"]
+    pub rng_seed: Vec<u8>,
+    #[doc = " Seed used to derive the keys of the wallet"]
+    pub seed: Vec<u8>,
+}
+#[doc = " Response of the wallet_export function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct WalletExportResponse {
+    #[doc = " The encrypted wallet file"]
+    pub wallet: Vec<u8>,
+}
+#[doc = " Arguments of the wallet_import function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct WalletImportArgs {
+    #[doc = " The password the wallet file was encrypted with"]
+    pub password: String,
+    #[doc = " The encrypted wallet file"]
+    pub wallet: Vec<u8>,
+}
+#[doc = " Response of the wallet_import function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct WalletImportResponse {
+    #[doc = " The accounts in use"]
+    pub accounts: Vec<WalletAccountType>,
+    #[doc = " The checkpoint of the note synchronization, to be passed to sync_notes"]
+    pub checkpoint: Vec<u8>,
+    #[doc = " The address book"]
+    pub contacts: Vec<WalletContactType>,
     #[doc = " Seed used to derive the keys of the wallet"]
     pub seed: Vec<u8>,
 }
+#[doc = " The arguments of the watch_balance function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct WatchBalanceArgs {
+    #[doc = " A rkyv serialized [Vec<phoenix_core::Note>]; all notes should be owned by one of "]
+    #[doc = " `view_keys`"]
+    pub notes: Vec<u8>,
+    #[doc = " A rkyv serialized [Vec<phoenix_core::ViewKey>] of the accounts, as returned by view_keys"]
+    pub view_keys: Vec<u8>,
+}
+#[doc = " The arguments of the watch_history function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct WatchHistoryArgs {
+    #[doc = " The notes of the account, along with their nullifiers"]
+    pub notes: Vec<NoteInfoType>,
+    #[doc = " The tx data of the wallet"]
+    pub tx_data: Vec<TxsDataType>,
+    #[doc = " A rkyv serialized [phoenix_core::ViewKey] of the account the notes belong to"]
+    pub view_key: Vec<u8>,
+}
+#[doc = " The arguments of the watch_note_ownership function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct WatchNoteOwnershipArgs {
+    #[doc = " The rkyv serialized tree leaves to scan, concatenated"]
+    pub leaves: Vec<u8>,
+    #[doc = " A rkyv serialized [Vec<phoenix_core::ViewKey>] of the accounts, as returned by view_keys"]
+    pub view_keys: Vec<u8>,
+}
+#[doc = " Response of the watch_note_ownership function"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct WatchNoteOwnershipResponse {
+    #[doc = " The block heights of the notes in the same order the notes were returned seperated by comma"]
+    pub block_heights: String,
+    #[doc = " The last position of the note"]
+    pub last_pos: u64,
+    #[doc = " The raw owned note"]
+    pub notes: Vec<Vec<u8>>,
+    #[doc = " The public spend keys of the notes in the same order the notes were returned"]
+    pub public_spend_keys: Vec<String>,
+    #[doc = " The payment references carried by the notes in the same order the notes were returned, or "]
+    #[doc = " null if the note carries none"]
+    pub ref_ids: Vec<Option<u64>>,
+}
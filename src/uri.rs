@@ -0,0 +1,268 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Payment request URIs.
+//!
+//! A payment request is laid out as
+//! `dusk:<address>?amount=<DUSK>&type=<note type>&ref=<ref id>&expiry=<time>`,
+//! where every parameter is optional:
+//!
+//! - `amount` is the value requested in DUSK, with up to nine decimals. The
+//!   value can be given in LUX instead, with `lux`, but not both.
+//! - `type` is either `obfuscated`, the default, or `transparent`.
+//! - `ref` is the payment reference carried by the note, see [`crate::memo`].
+//! - `expiry` is the UNIX time, in seconds, the request expires at.
+//!
+//! Unknown parameters are ignored, unless prefixed by `req-`, in which case
+//! the request is rejected.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+
+use rusk_abi::dusk::{dusk, Dusk, LUX};
+
+use crate::{address, types, WalletError};
+
+/// The scheme of a payment request.
+pub const SCHEME: &str = "dusk:";
+
+/// The number of decimals of DUSK.
+const DECIMALS: usize = 9;
+
+/// The value of one DUSK.
+const ONE_DUSK: Dusk = dusk(1.0);
+
+/// A request for a payment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentRequest {
+    /// The address of the receiver, plain or checksummed.
+    pub receiver: String,
+    /// The value requested, if any.
+    pub value: Option<Dusk>,
+    /// The type of the note to pay with.
+    pub note_type: types::OutputType,
+    /// The payment reference, if any.
+    pub ref_id: Option<u64>,
+    /// The UNIX time, in seconds, the request expires at, if any.
+    pub expiry: Option<u64>,
+}
+
+impl PaymentRequest {
+    /// Encodes the request into a URI.
+    pub fn to_uri(&self) -> String {
+        let mut params = alloc::vec::Vec::new();
+
+        if let Some(value) = self.value {
+            params.push(format!("amount={}", format_dusk(value)));
+        }
+        if self.note_type == types::OutputType::Transparent {
+            params.push("type=transparent".to_string());
+        }
+        if let Some(ref_id) = self.ref_id {
+            params.push(format!("ref={ref_id}"));
+        }
+        if let Some(expiry) = self.expiry {
+            params.push(format!("expiry={expiry}"));
+        }
+
+        match params.is_empty() {
+            true => format!("{SCHEME}{}", self.receiver),
+            false => format!("{SCHEME}{}?{}", self.receiver, params.join("&")),
+        }
+    }
+
+    /// Decodes a request from a URI.
+    pub fn from_uri(uri: &str) -> Result<Self, WalletError> {
+        let rest = match uri.get(..SCHEME.len()) {
+            Some(s) if s.eq_ignore_ascii_case(SCHEME) => &uri[SCHEME.len()..],
+            _ => return Err(WalletError::Decode("uri")),
+        };
+
+        let (receiver, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+        if address::decode(receiver).is_err() {
+            return Err(WalletError::InvalidAddress("uri"));
+        }
+
+        let mut request = Self {
+            receiver: receiver.to_string(),
+            value: None,
+            note_type: types::OutputType::Obfuscated,
+            ref_id: None,
+            expiry: None,
+        };
+
+        for param in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) =
+                param.split_once('=').ok_or(WalletError::Decode("uri"))?;
+
+            match key {
+                "amount" | "lux" if request.value.is_some() => {
+                    return Err(WalletError::InvalidValue("amount"));
+                }
+                "amount" => request.value = Some(parse_dusk(value)?),
+                "lux" => request.value = Some(parse_int(value, "amount")?),
+                "type" => {
+                    request.note_type = match value {
+                        "obfuscated" => types::OutputType::Obfuscated,
+                        "transparent" => types::OutputType::Transparent,
+                        _ => return Err(WalletError::InvalidValue("type")),
+                    }
+                }
+                "ref" => request.ref_id = Some(parse_int(value, "ref")?),
+                "expiry" => request.expiry = Some(parse_int(value, "expiry")?),
+                _ if key.starts_with("req-") => {
+                    return Err(WalletError::InvalidValue("uri"));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(request)
+    }
+
+    /// Builds the output paying the request.
+    ///
+    /// The `value` is used when the request doesn't specify one, and the
+    /// request is rejected if it is expired at the UNIX time `now`, if given.
+    pub fn output(
+        self,
+        value: Option<Dusk>,
+        now: Option<u64>,
+    ) -> Result<types::ExecuteOutput, WalletError> {
+        if let (Some(expiry), Some(now)) = (self.expiry, now) {
+            if now >= expiry {
+                return Err(WalletError::InvalidValue("expiry"));
+            }
+        }
+
+        let value = self
+            .value
+            .or(value)
+            .filter(|v| *v > 0)
+            .ok_or(WalletError::InvalidValue("value"))?;
+
+        Ok(types::ExecuteOutput {
+            note_type: self.note_type,
+            receiver: self.receiver,
+            ref_id: self.ref_id,
+            value,
+        })
+    }
+}
+
+/// Formats a value in DUSK, with no trailing zeros.
+fn format_dusk(value: Dusk) -> String {
+    let int = value / ONE_DUSK;
+    let frac = value % ONE_DUSK;
+
+    if frac == 0 {
+        return int.to_string();
+    }
+
+    let frac = format!("{frac:0DECIMALS$}");
+    format!("{int}.{}", frac.trim_end_matches('0'))
+}
+
+/// Parses a value in DUSK, with up to nine decimals, without rounding.
+fn parse_dusk(value: &str) -> Result<Dusk, WalletError> {
+    let err = WalletError::InvalidValue("amount");
+
+    let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+    if frac.len() > DECIMALS || (int.is_empty() && frac.is_empty()) {
+        return Err(err);
+    }
+
+    let int = match int.is_empty() {
+        true => 0,
+        false => parse_int(int, "amount")?,
+    };
+    let frac = match frac.is_empty() {
+        true => 0,
+        false => {
+            parse_int(frac, "amount")?
+                * 10u64.pow((DECIMALS - frac.len()) as u32)
+        }
+    };
+
+    int.checked_mul(ONE_DUSK)
+        .and_then(|v| v.checked_add(frac * LUX))
+        .ok_or(err)
+}
+
+/// Parses a decimal integer, with no sign.
+fn parse_int(value: &str, field: &'static str) -> Result<u64, WalletError> {
+    if !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(WalletError::InvalidValue(field));
+    }
+    value.parse().map_err(|_| WalletError::InvalidValue(field))
+}
+
+#[test]
+fn payment_request_works() {
+    use dusk_jubjub::JubJubScalar;
+    use phoenix_core::{PublicKey, SecretKey};
+
+    let sk =
+        SecretKey::new(JubJubScalar::from(42u64), JubJubScalar::from(7u64));
+    let receiver = address::encode(&PublicKey::from(&sk), true);
+
+    let request = PaymentRequest {
+        receiver: receiver.clone(),
+        value: Some(dusk(12.5) + 3 * LUX),
+        note_type: types::OutputType::Transparent,
+        ref_id: Some(1234),
+        expiry: Some(1_700_000_000),
+    };
+
+    let uri = request.to_uri();
+    assert_eq!(
+        uri,
+        format!(
+            "dusk:{receiver}?amount=12.500000003&type=transparent\
+             &ref=1234&expiry=1700000000"
+        )
+    );
+    assert_eq!(PaymentRequest::from_uri(&uri), Ok(request.clone()));
+
+    let lux = format!("dusk:{receiver}?lux=42&foo=bar");
+    let output = PaymentRequest::from_uri(&lux)
+        .unwrap()
+        .output(None, None)
+        .unwrap();
+    assert_eq!(output.value, 42);
+    assert_eq!(output.note_type, types::OutputType::Obfuscated);
+
+    // the request carries no value, so it is taken from the payer
+    let bare = PaymentRequest::from_uri(&format!("dusk:{receiver}")).unwrap();
+    assert_eq!(bare.clone().output(Some(7), None).unwrap().value, 7);
+    assert!(bare.output(None, None).is_err());
+
+    assert_eq!(
+        request.output(None, Some(1_700_000_000)),
+        Err(WalletError::InvalidValue("expiry"))
+    );
+
+    for (uri, err) in [
+        ("bitcoin:abc", WalletError::Decode("uri")),
+        ("dusk:abc", WalletError::InvalidAddress("uri")),
+    ] {
+        assert_eq!(PaymentRequest::from_uri(uri), Err(err));
+    }
+
+    for query in [
+        "amount=1.0000000001",
+        "amount=-1",
+        "amount=1&lux=1",
+        "type=shielded",
+        "req-fee=1",
+    ] {
+        let uri = format!("dusk:{receiver}?{query}");
+        assert!(PaymentRequest::from_uri(&uri).is_err(), "{query}");
+    }
+}
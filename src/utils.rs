@@ -6,20 +6,24 @@
 
 //! Misc utilities required by the library implementation.
 
-use crate::{ffi, tx, MAX_INPUT_NOTES, MAX_LEN, RNG_SEED};
+use crate::{
+    address, ffi, types, WalletError, MAX_ACCOUNTS, MAX_INPUT_NOTES, MAX_KEY,
+    MAX_LEN, RNG_SEED,
+};
 
 use alloc::vec::Vec;
 use core::ptr;
 
-use dusk_bytes::DeserializableSlice;
 use dusk_jubjub::JubJubScalar;
-use phoenix_core::{Note, PublicKey};
+use phoenix_core::{Note, PublicKey, ViewKey};
 use rand_chacha::ChaCha12Rng;
 use rand_core::SeedableRng;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-type Node = (Note, tx::Opening, u64, JubJubScalar);
+/// A note spendable as input, along with its decrypted value and blinding
+/// factor.
+pub type Node = (Note, u64, JubJubScalar);
 const MAX_ALLOC_LEN: u32 = 2u32.pow(24);
 
 /// Composes a `i64` from the provided arguments. This will be returned from the
@@ -52,15 +56,16 @@ pub fn decompose(result: i64) -> (bool, u32, u32) {
 
 /// Takes a JSON string from the memory slice and deserializes it into the
 /// provided type.
-pub fn take_args<T>(args: i32, len: i32) -> Option<T>
+pub fn take_args<T>(args: i32, len: i32) -> Result<T, WalletError>
 where
     T: for<'a> Deserialize<'a>,
 {
     let args = args as *mut u8;
     let len = len as usize;
     let args: Vec<u8> = unsafe { Vec::from_raw_parts(args, len, len) };
-    let args = alloc::string::String::from_utf8(args).ok()?;
-    serde_json::from_str(&args).ok()
+    let args = alloc::string::String::from_utf8(args)
+        .map_err(|_| WalletError::InvalidArgs)?;
+    serde_json::from_str(&args).map_err(|_| WalletError::InvalidArgs)
 }
 
 /// Sanitizes arbitrary bytes into well-formed seed.
@@ -72,6 +77,26 @@ pub fn sanitize_seed(bytes: Vec<u8>) -> Option<[u8; RNG_SEED]> {
     })
 }
 
+/// Sanitizes an optional number of accounts, defaulting to [`MAX_KEY`].
+///
+/// Fails if the number is zero or greater than [`MAX_ACCOUNTS`].
+pub fn sanitize_account_count(count: Option<u64>) -> Option<usize> {
+    match count {
+        None => Some(MAX_KEY),
+        Some(c) if c > 0 && c <= MAX_ACCOUNTS as u64 => Some(c as usize),
+        Some(_) => None,
+    }
+}
+
+/// Sanitizes a rkyv serialized list of view keys, as returned by
+/// [`ffi::view_keys`].
+///
+/// Fails if the list is empty or longer than [`MAX_ACCOUNTS`].
+pub fn sanitize_view_keys(bytes: &[u8]) -> Option<Vec<ViewKey>> {
+    let keys: Vec<ViewKey> = rkyv::from_bytes(bytes).ok()?;
+    (!keys.is_empty() && keys.len() <= MAX_ACCOUNTS).then_some(keys)
+}
+
 /// Sanitizes arbitrary bytes into well-formed seed.
 pub fn sanitize_rng_seed(bytes: Vec<u8>) -> Option<[u8; 32]> {
     (bytes.len() == 32).then(|| {
@@ -81,9 +106,13 @@ pub fn sanitize_rng_seed(bytes: Vec<u8>) -> Option<[u8; 32]> {
     })
 }
 
-/// Fails the operation
-pub fn fail() -> i64 {
-    compose(false, 0, 0)
+/// Fails the operation, returning a pointer to a JSON string representing
+/// [types::ErrorResponse].
+pub fn fail(err: WalletError) -> i64 {
+    let response = types::ErrorResponse::from(err);
+    let response = serde_json::to_string(&response).unwrap_or_default();
+    let (ptr, len) = allocated_copy(response);
+    compose(false, ptr, len)
 }
 
 /// Converts the provided response into an allocated pointer and returns the
@@ -104,7 +133,7 @@ where
 {
     let bytes = match rkyv::to_bytes(&value) {
         Ok(t) => t.into_vec(),
-        Err(_) => return fail(),
+        Err(_) => return fail(WalletError::Encode("response")),
     };
 
     let (ptr, len) = allocated_copy(bytes);
@@ -154,11 +183,9 @@ pub fn sanitize_notes(mut notes: Vec<Note>) -> Vec<Note> {
     notes
 }
 
-/// Converts a Base58 string into a [`PublicKey`].
+/// Converts a Base58 address, plain or checksummed, into a [`PublicKey`].
 pub fn bs58_to_pk(pk: &str) -> Option<PublicKey> {
-    // TODO this should be defined in phoenix-core
-    let bytes = bs58::decode(pk).into_vec().ok()?;
-    PublicKey::from_reader(&mut &bytes[..]).ok()
+    address::decode(pk).ok().map(|(pk, _)| pk)
 }
 
 /// Calculate the inputs for a transaction.
@@ -170,7 +197,7 @@ pub fn inputs(nodes: Vec<Node>, target_sum: u64) -> Option<Vec<Node>> {
     let mut i = 0;
     let mut sum = 0;
     while sum < target_sum && i < nodes.len() {
-        sum = sum.saturating_add(nodes[i].2);
+        sum = sum.saturating_add(nodes[i].1);
         i += 1;
     }
 
@@ -180,7 +207,8 @@ pub fn inputs(nodes: Vec<Node>, target_sum: u64) -> Option<Vec<Node>> {
 
     let inputs = pick_notes(target_sum, nodes);
 
-    Some(inputs)
+    // no combination of few enough notes covers the target
+    (!inputs.is_empty()).then_some(inputs)
 }
 
 /// Pick the notes to be used in a transaction from a vector of notes.
@@ -201,12 +229,12 @@ fn pick_notes(value: u64, notes_and_values: Vec<Node>) -> Vec<Node> {
         return notes_and_values;
     }
 
-    notes_and_values.sort_by(|(_, _, aval, _), (_, _, bval, _)| aval.cmp(bval));
+    notes_and_values.sort_by_key(|(_, value, _)| *value);
 
     pick_lexicographic(notes_and_values.len(), |indices| {
         indices
             .iter()
-            .map(|index| notes_and_values[*index].2)
+            .map(|index| notes_and_values[*index].1)
             .sum::<u64>()
             >= value
     })
@@ -268,14 +296,11 @@ fn compose_works() {
 
 #[test]
 fn knapsack_works() {
-    use core::mem;
     use dusk_jubjub::JubJubScalar;
     use ff::Field;
     use phoenix_core::{PublicKey, SecretKey};
     use rand::{rngs::StdRng, SeedableRng};
 
-    // openings are not checked here; no point in setting them up properly
-    let o = unsafe { mem::zeroed() };
     let rng = &mut StdRng::seed_from_u64(0xbeef);
 
     // sanity check
@@ -286,7 +311,7 @@ fn knapsack_works() {
     let pk = PublicKey::from(&sk);
     let blinder = JubJubScalar::random(&mut *rng);
     let note = Note::obfuscated(rng, &pk, 100, blinder);
-    let available = vec![(note, o, 100, blinder)];
+    let available = vec![(note, 100, blinder)];
     let inputs_notes = available.clone();
     assert_eq!(inputs(available, 70), Some(inputs_notes));
 
@@ -295,7 +320,7 @@ fn knapsack_works() {
     let pk = PublicKey::from(&sk);
     let blinder = JubJubScalar::random(&mut *rng);
     let note = Note::obfuscated(rng, &pk, 100, blinder);
-    let available = vec![(note, o, 100, blinder)];
+    let available = vec![(note, 100, blinder)];
     assert_eq!(inputs(available, 101), None);
 
     // multiple inputs check
@@ -316,9 +341,9 @@ fn knapsack_works() {
     // shouldn't this note be created with blinder3?
     let note3 = Note::obfuscated(rng, &pk, 300, blinder);
     let available = vec![
-        (note1, o, 100, blinder1),
-        (note2, o, 500, blinder2),
-        (note3, o, 300, blinder3),
+        (note1, 100, blinder1),
+        (note2, 500, blinder2),
+        (note3, 300, blinder3),
     ];
 
     assert_eq!(inputs(available.clone(), 600), Some(available));
@@ -340,9 +365,9 @@ fn knapsack_works() {
     // shouldn't this note be created with blinder3?
     let note3 = Note::obfuscated(rng, &pk, 300, blinder);
     let available = vec![
-        (note1, o, 100, blinder1),
-        (note2, o, 500, blinder2),
-        (note3, o, 300, blinder3),
+        (note1, 100, blinder1),
+        (note2, 500, blinder2),
+        (note3, 300, blinder3),
     ];
     assert_eq!(inputs(available, 901), None);
 }
@@ -0,0 +1,643 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Native Rust interface of the wallet.
+//!
+//! The [`Wallet`] pulls its keys from a [`Store`] and the state of the network
+//! from a [`StateClient`], and builds the same transactions the FFI does.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use bls12_381_bls::{PublicKey as StakePublicKey, SecretKey as StakeSecretKey};
+use dusk_bls12_381::BlsScalar;
+use dusk_bytes::Serializable;
+use dusk_plonk::prelude::Proof;
+use phoenix_core::{
+    transaction::StakeData, Fee, Note, PublicKey, SecretKey, ViewKey,
+};
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{
+    consolidate::{self, Consolidation},
+    key,
+    select::Strategy,
+    stake,
+    stake::{Stct, Wfct, Withdrawal},
+    tx::{self, Opening, UnprovenTransaction},
+    types, utils, WalletError, MAX_INPUT_NOTES, RNG_SEED,
+};
+
+/// Stores the cryptographic material necessary to derive cryptographic keys.
+pub trait Store {
+    /// The error type returned from the store.
+    type Error: fmt::Debug;
+
+    /// Retrieves the seed used to derive keys.
+    fn get_seed(&self) -> Result<[u8; RNG_SEED], Self::Error>;
+
+    /// Retrieves a derived secret key from the store.
+    ///
+    /// The provided implementation simply gets the seed and regenerates the
+    /// key every time with [`key::derive_sk`]. It may be reimplemented to
+    /// provide a cache for keys.
+    fn retrieve_sk(&self, index: u64) -> Result<SecretKey, Self::Error> {
+        let seed = self.get_seed()?;
+        Ok(key::derive_sk(&seed, index))
+    }
+
+    /// Retrieves a derived stake secret key from the store.
+    ///
+    /// The provided implementation simply gets the seed and regenerates the
+    /// key every time with [`key::derive_stake_sk`]. It may be reimplemented
+    /// to provide a cache for keys.
+    fn retrieve_stake_sk(
+        &self,
+        index: u64,
+    ) -> Result<StakeSecretKey, Self::Error> {
+        let seed = self.get_seed()?;
+        Ok(key::derive_stake_sk(&seed, index))
+    }
+}
+
+/// Types that are client of the state of the network.
+pub trait StateClient {
+    /// The error type returned from the state client.
+    type Error: fmt::Debug;
+
+    /// Finds the notes of the given view key, starting from the position
+    /// `since` of the tree.
+    ///
+    /// Notes not owned by the key and spent notes may be returned as well,
+    /// since the wallet filters them out.
+    fn fetch_notes(
+        &self,
+        vk: &ViewKey,
+        since: u64,
+    ) -> Result<Vec<Note>, Self::Error>;
+
+    /// Queries the node for the opening of the note at the given position to
+    /// the Merkle root of the state.
+    fn fetch_opening(&self, pos: u64) -> Result<Opening, Self::Error>;
+
+    /// Queries the node for the current Merkle root of the state.
+    fn fetch_anchor(&self) -> Result<BlsScalar, Self::Error>;
+
+    /// Asks the node to return the nullifiers that already exist among the
+    /// given ones.
+    fn fetch_existing_nullifiers(
+        &self,
+        nullifiers: &[BlsScalar],
+    ) -> Result<Vec<BlsScalar>, Self::Error>;
+
+    /// Queries the node for the stake of a key, if it has one.
+    fn fetch_stake(
+        &self,
+        pk: &StakePublicKey,
+    ) -> Result<Option<StakeData>, Self::Error>;
+}
+
+/// The error returned by the [`Wallet`].
+pub enum Error<S: Store, C: StateClient> {
+    /// Error originating from the store.
+    Store(S::Error),
+    /// Error originating from the state client.
+    State(C::Error),
+    /// Error originating from the wallet logic.
+    Wallet(WalletError),
+}
+
+impl<S: Store, C: StateClient> Error<S, C> {
+    fn from_store(err: S::Error) -> Self {
+        Self::Store(err)
+    }
+
+    fn from_state(err: C::Error) -> Self {
+        Self::State(err)
+    }
+}
+
+impl<S: Store, C: StateClient> From<WalletError> for Error<S, C> {
+    fn from(err: WalletError) -> Self {
+        Self::Wallet(err)
+    }
+}
+
+impl<S: Store, C: StateClient> fmt::Debug for Error<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Store(e) => f.debug_tuple("Store").field(e).finish(),
+            Self::State(e) => f.debug_tuple("State").field(e).finish(),
+            Self::Wallet(e) => f.debug_tuple("Wallet").field(e).finish(),
+        }
+    }
+}
+
+impl<S: Store, C: StateClient> fmt::Display for Error<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Store(e) => write!(f, "store error: {e:?}"),
+            Self::State(e) => write!(f, "state error: {e:?}"),
+            Self::Wallet(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl<S: Store, C: StateClient> std::error::Error for Error<S, C> {}
+
+/// The balance of a key of the wallet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BalanceInfo {
+    /// Total computed balance.
+    pub value: u64,
+    /// Maximum value per transaction.
+    pub maximum: u64,
+}
+
+impl BalanceInfo {
+    /// Computes the balance from the values of the notes of a key.
+    ///
+    /// The maximum is the sum of the largest values, given the circuit
+    /// accepts up to [`MAX_INPUT_NOTES`] inputs.
+    pub fn from_values(mut values: Vec<u64>) -> Self {
+        values.sort_by(|a, b| b.cmp(a));
+
+        let maximum = values.iter().take(MAX_INPUT_NOTES).sum::<u64>();
+        let value = values.iter().fold(0u64, |sum, v| sum.saturating_add(*v));
+
+        Self { value, maximum }
+    }
+}
+
+/// A wallet implementation.
+///
+/// This is responsible for holding the keys, and performing operations like
+/// creating transactions.
+pub struct Wallet<S, C> {
+    store: S,
+    state: C,
+    coin_selection: Strategy,
+    note_type: types::OutputType,
+}
+
+impl<S, C> Wallet<S, C> {
+    /// Creates a new wallet from the given store and state client.
+    pub const fn new(store: S, state: C) -> Self {
+        Self {
+            store,
+            state,
+            coin_selection: Strategy::Lexicographic,
+            note_type: types::OutputType::Obfuscated,
+        }
+    }
+
+    /// Sets the strategy to select the notes spent by the transactions.
+    pub fn with_coin_selection(mut self, coin_selection: Strategy) -> Self {
+        self.coin_selection = coin_selection;
+        self
+    }
+
+    /// Sets the type of the notes created by the transfers and of the change
+    /// of all transactions. Defaults to obfuscated notes.
+    pub fn with_note_type(mut self, note_type: types::OutputType) -> Self {
+        self.note_type = note_type;
+        self
+    }
+
+    /// Returns the store of the wallet.
+    pub const fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Returns the state client of the wallet.
+    pub const fn state(&self) -> &C {
+        &self.state
+    }
+}
+
+impl<S, C> Wallet<S, C>
+where
+    S: Store,
+    C: StateClient,
+{
+    /// Returns the public key of the given index.
+    pub fn public_key(&self, index: u64) -> Result<PublicKey, Error<S, C>> {
+        let sk = self.store.retrieve_sk(index).map_err(Error::from_store)?;
+        Ok(PublicKey::from(&sk))
+    }
+
+    /// Returns the view key of the given index.
+    pub fn view_key(&self, index: u64) -> Result<ViewKey, Error<S, C>> {
+        let sk = self.store.retrieve_sk(index).map_err(Error::from_store)?;
+        Ok(ViewKey::from(&sk))
+    }
+
+    /// Returns the stake public key of the given index.
+    pub fn stake_public_key(
+        &self,
+        index: u64,
+    ) -> Result<StakePublicKey, Error<S, C>> {
+        let sk = self
+            .store
+            .retrieve_stake_sk(index)
+            .map_err(Error::from_store)?;
+        Ok(StakePublicKey::from(&sk))
+    }
+
+    /// Returns the stake of the key of the given index, if it has one.
+    pub fn stake_info(
+        &self,
+        index: u64,
+    ) -> Result<Option<StakeData>, Error<S, C>> {
+        let pk = self.stake_public_key(index)?;
+        self.state.fetch_stake(&pk).map_err(Error::from_state)
+    }
+
+    /// Computes the balance of the key of the given index.
+    pub fn balance(&self, index: u64) -> Result<BalanceInfo, Error<S, C>> {
+        let sk = self.store.retrieve_sk(index).map_err(Error::from_store)?;
+        let vk = ViewKey::from(&sk);
+
+        let values = self
+            .unspent_notes(&sk)?
+            .iter()
+            .map(|note| note.value(Some(&vk)))
+            .collect::<Result<_, _>>()
+            .map_err(|_| WalletError::NoteOwnership("notes"))?;
+
+        Ok(BalanceInfo::from_values(values))
+    }
+
+    /// Creates a transaction calling a contract, with the given outputs.
+    ///
+    /// The inputs are picked among the unspent notes of the sender, and the
+    /// change is sent back to `refund`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute<Rng>(
+        &self,
+        rng: &mut Rng,
+        sender_index: u64,
+        refund: &PublicKey,
+        outputs: Vec<types::ExecuteOutput>,
+        gas_limit: u64,
+        gas_price: u64,
+        crossover: Option<types::CrossoverType>,
+        call: Option<types::ExecuteCall>,
+    ) -> Result<UnprovenTransaction, Error<S, C>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        self.execute_with_fee(
+            rng,
+            sender_index,
+            refund,
+            outputs,
+            gas_limit,
+            gas_price,
+            None,
+            crossover,
+            call,
+        )
+    }
+
+    /// Creates a transfer of `value` to `receiver` in a note of the wallet's
+    /// configured note type.
+    #[allow(clippy::too_many_arguments)]
+    pub fn transfer<Rng>(
+        &self,
+        rng: &mut Rng,
+        sender_index: u64,
+        refund: &PublicKey,
+        receiver: &PublicKey,
+        value: u64,
+        ref_id: u64,
+        gas_limit: u64,
+        gas_price: u64,
+    ) -> Result<UnprovenTransaction, Error<S, C>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let output = types::ExecuteOutput {
+            note_type: self.note_type.clone(),
+            receiver: bs58::encode(receiver.to_bytes()).into_string(),
+            ref_id: Some(ref_id),
+            value,
+        };
+
+        self.execute(
+            rng,
+            sender_index,
+            refund,
+            alloc::vec![output],
+            gas_limit,
+            gas_price,
+            None,
+            None,
+        )
+    }
+
+    /// Creates the STCT to stake `value`, to be proven before calling
+    /// [`Wallet::stake`].
+    pub fn stct<Rng>(
+        &self,
+        rng: &mut Rng,
+        sender_index: u64,
+        refund: &PublicKey,
+        value: u64,
+        gas_limit: u64,
+        gas_price: u64,
+    ) -> Result<Stct, Error<S, C>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let sender = self
+            .store
+            .retrieve_sk(sender_index)
+            .map_err(Error::from_store)?;
+
+        Ok(Stct::new(rng, &sender, refund, value, gas_limit, gas_price))
+    }
+
+    /// Creates a transaction staking the value of the given STCT, using the
+    /// proof of it.
+    pub fn stake<Rng>(
+        &self,
+        rng: &mut Rng,
+        sender_index: u64,
+        staker_index: u64,
+        refund: &PublicKey,
+        stct: &Stct,
+        proof: &Proof,
+    ) -> Result<UnprovenTransaction, Error<S, C>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let stake_sk = self
+            .store
+            .retrieve_stake_sk(staker_index)
+            .map_err(Error::from_store)?;
+
+        let counter = self.stake_info(staker_index)?.map(|s| s.counter);
+        let call = stct.call(&stake_sk, counter.unwrap_or_default(), proof)?;
+        let crossover =
+            stake::crossover(&stct.crossover, &stct.blinder, stct.value)?;
+
+        self.spend(rng, sender_index, refund, stct.fee, crossover, call)
+    }
+
+    /// Creates the WFCT to unstake the full stake of the key, to be proven
+    /// before calling [`Wallet::unstake`].
+    pub fn wfct<Rng>(
+        &self,
+        rng: &mut Rng,
+        sender_index: u64,
+        staker_index: u64,
+        refund: &PublicKey,
+        gas_limit: u64,
+        gas_price: u64,
+    ) -> Result<Wfct, Error<S, C>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let sender = self
+            .store
+            .retrieve_sk(sender_index)
+            .map_err(Error::from_store)?;
+
+        let (value, _) = self
+            .stake_info(staker_index)?
+            .and_then(|s| s.amount)
+            .ok_or(WalletError::NoStake)?;
+
+        Ok(Wfct::new(rng, &sender, refund, value, gas_limit, gas_price))
+    }
+
+    /// Creates a transaction unstaking the value of the given WFCT, using the
+    /// proof of it.
+    pub fn unstake<Rng>(
+        &self,
+        rng: &mut Rng,
+        sender_index: u64,
+        staker_index: u64,
+        refund: &PublicKey,
+        wfct: &Wfct,
+        proof: &Proof,
+    ) -> Result<UnprovenTransaction, Error<S, C>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let stake_sk = self
+            .store
+            .retrieve_stake_sk(staker_index)
+            .map_err(Error::from_store)?;
+
+        let counter = self
+            .stake_info(staker_index)?
+            .ok_or(WalletError::NoStake)?
+            .counter;
+        let call = wfct.call(&stake_sk, counter, proof)?;
+        let crossover = stake::crossover(&wfct.crossover, &wfct.blinder, 0)?;
+
+        self.spend(rng, sender_index, refund, wfct.fee, crossover, call)
+    }
+
+    /// Creates a transaction withdrawing the reward of the stake of the key.
+    pub fn withdraw<Rng>(
+        &self,
+        rng: &mut Rng,
+        sender_index: u64,
+        staker_index: u64,
+        refund: &PublicKey,
+        gas_limit: u64,
+        gas_price: u64,
+    ) -> Result<UnprovenTransaction, Error<S, C>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let sender = self
+            .store
+            .retrieve_sk(sender_index)
+            .map_err(Error::from_store)?;
+        let stake_sk = self
+            .store
+            .retrieve_stake_sk(staker_index)
+            .map_err(Error::from_store)?;
+
+        let counter = self
+            .stake_info(staker_index)?
+            .ok_or(WalletError::NoStake)?
+            .counter;
+
+        let Withdrawal {
+            fee,
+            crossover,
+            blinder,
+            call,
+        } = Withdrawal::new(
+            rng, &sender, &stake_sk, refund, counter, gas_limit, gas_price,
+        )?;
+        let crossover = stake::crossover(&crossover, &blinder, 0)?;
+
+        self.spend(rng, sender_index, refund, fee, crossover, call)
+    }
+
+    /// Creates the transactions of the first round of the consolidation of
+    /// the notes of the key, along with the plan for the whole of it.
+    pub fn consolidate<Rng>(
+        &self,
+        rng: &mut Rng,
+        sender_index: u64,
+        gas_limit: u64,
+        gas_price: u64,
+    ) -> Result<Consolidation, Error<S, C>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let sk = self
+            .store
+            .retrieve_sk(sender_index)
+            .map_err(Error::from_store)?;
+        let notes = self.inputs(&sk)?;
+
+        let anchor = self.state.fetch_anchor().map_err(Error::from_state)?;
+        let opening = |pos| self.opening(pos, &anchor);
+
+        consolidate::consolidate(rng, &sk, notes, opening, gas_limit, gas_price)
+    }
+
+    /// Spends the notes of the sender to pay for a call to the stake
+    /// contract.
+    fn spend<Rng>(
+        &self,
+        rng: &mut Rng,
+        sender_index: u64,
+        refund: &PublicKey,
+        fee: Fee,
+        crossover: types::CrossoverType,
+        call: types::ExecuteCall,
+    ) -> Result<UnprovenTransaction, Error<S, C>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        self.execute_with_fee(
+            rng,
+            sender_index,
+            refund,
+            Vec::new(),
+            fee.gas_limit,
+            fee.gas_price,
+            Some(fee),
+            Some(crossover),
+            Some(call),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_with_fee<Rng>(
+        &self,
+        rng: &mut Rng,
+        sender_index: u64,
+        refund: &PublicKey,
+        outputs: Vec<types::ExecuteOutput>,
+        gas_limit: u64,
+        gas_price: u64,
+        fee: Option<Fee>,
+        crossover: Option<types::CrossoverType>,
+        call: Option<types::ExecuteCall>,
+    ) -> Result<UnprovenTransaction, Error<S, C>>
+    where
+        Rng: RngCore + CryptoRng,
+    {
+        let sk = self
+            .store
+            .retrieve_sk(sender_index)
+            .map_err(Error::from_store)?;
+        let notes = self.inputs(&sk)?;
+
+        // only the openings of the picked inputs are fetched
+        let anchor = self.state.fetch_anchor().map_err(Error::from_state)?;
+        let opening = |pos| self.opening(pos, &anchor);
+
+        tx::execute(
+            rng,
+            &sk,
+            notes,
+            &self.coin_selection,
+            opening,
+            refund,
+            self.note_type.clone(),
+            outputs,
+            gas_limit,
+            gas_price,
+            fee,
+            crossover,
+            call,
+        )
+    }
+
+    /// Fetches the unspent notes owned by the secret key.
+    fn unspent_notes(&self, sk: &SecretKey) -> Result<Vec<Note>, Error<S, C>> {
+        let vk = ViewKey::from(sk);
+
+        let mut notes =
+            self.state.fetch_notes(&vk, 0).map_err(Error::from_state)?;
+        notes.retain(|note| vk.owns(note));
+
+        let nullifiers: Vec<_> =
+            notes.iter().map(|note| note.gen_nullifier(sk)).collect();
+        let existing = self
+            .state
+            .fetch_existing_nullifiers(&nullifiers)
+            .map_err(Error::from_state)?;
+
+        let notes = notes
+            .into_iter()
+            .zip(nullifiers)
+            .filter(|(_, nullifier)| !existing.contains(nullifier))
+            .map(|(note, _)| note)
+            .collect();
+
+        Ok(notes)
+    }
+
+    /// Fetches the unspent notes of the key, along with their values and
+    /// blinding factors.
+    fn inputs(&self, sk: &SecretKey) -> Result<Vec<utils::Node>, Error<S, C>> {
+        let vk = ViewKey::from(sk);
+
+        self.unspent_notes(sk)?
+            .into_iter()
+            .map(|note| {
+                let value = note
+                    .value(Some(&vk))
+                    .map_err(|_| WalletError::NoteOwnership("notes"))?;
+                let blinder = note
+                    .blinding_factor(Some(&vk))
+                    .map_err(|_| WalletError::NoteOwnership("notes"))?;
+
+                Ok((note, value, blinder))
+            })
+            .collect()
+    }
+
+    /// Fetches the opening of the note at the given position, checking it
+    /// opens to the `anchor`.
+    fn opening(
+        &self,
+        pos: u64,
+        anchor: &BlsScalar,
+    ) -> Result<Opening, Error<S, C>> {
+        let opening =
+            self.state.fetch_opening(pos).map_err(Error::from_state)?;
+
+        if opening.root().hash != *anchor {
+            return Err(WalletError::InvalidOpening.into());
+        }
+
+        Ok(opening)
+    }
+}
@@ -5,7 +5,7 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use dusk_bytes::Serializable;
-use dusk_wallet_core::key::{derive_sk, derive_stake_sk};
+use dusk_wallet_core::key::{derive_sk, derive_sk_at, derive_stake_sk};
 
 const SEED: [u8; 64] = [0; 64];
 const INDEX: u64 = 42;
@@ -34,3 +34,22 @@ fn test_derive_stake_sk() {
     ];
     assert_eq!(derive_stake_sk(&SEED, INDEX).to_bytes(), sk_bytes);
 }
+
+#[test]
+fn test_derive_sk_at() {
+    // index-only keys are the ones of subaccount 0, and other subaccounts must
+    // always derive the same keys as well
+    assert_eq!(
+        derive_sk_at(&SEED, 0, INDEX).to_bytes(),
+        derive_sk(&SEED, INDEX).to_bytes()
+    );
+
+    let sk_bytes = [
+        22, 85, 74, 165, 0, 6, 210, 211, 123, 131, 137, 52, 62, 31, 171, 156,
+        136, 152, 49, 172, 14, 13, 167, 194, 119, 125, 231, 154, 199, 102, 90,
+        5, 212, 40, 153, 239, 158, 146, 49, 126, 71, 208, 203, 163, 67, 118,
+        12, 252, 123, 80, 227, 126, 57, 36, 184, 244, 1, 89, 57, 165, 103, 205,
+        54, 2,
+    ];
+    assert_eq!(derive_sk_at(&SEED, 1, INDEX).to_bytes(), sk_bytes);
+}
@@ -0,0 +1,419 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Native wallet API tests.
+
+use core::cell::Cell;
+use core::convert::Infallible;
+
+use bls12_381_bls::PublicKey as StakePublicKey;
+use dusk_bls12_381::BlsScalar;
+use dusk_bytes::Serializable;
+use dusk_wallet_core::{
+    key,
+    mock::MockStateClient,
+    preview::{Anomaly, Preview},
+    select::Strategy,
+    tx::Opening,
+    types, BalanceInfo, Error, StateClient, Store, Wallet, WalletError,
+    RNG_SEED,
+};
+use phoenix_core::{transaction::StakeData, Note, NoteType, ViewKey};
+use rand::{rngs::StdRng, SeedableRng};
+
+const SEED: [u8; RNG_SEED] = [0xfa; RNG_SEED];
+
+#[test]
+fn balance_works() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [10, 250, 15, 39, 55]);
+    push_notes(&mut state, 1, [1000]);
+
+    let wallet = Wallet::new(TestStore, state);
+
+    let balance = wallet.balance(0).unwrap();
+    assert_eq!(
+        balance,
+        BalanceInfo {
+            value: 369,
+            maximum: 359
+        }
+    );
+
+    let balance = wallet.balance(1).unwrap();
+    assert_eq!(balance.value, 1000);
+}
+
+#[test]
+fn balance_skips_spent_notes() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [10, 250, 15]);
+
+    let sk = key::derive_sk(&SEED, 0);
+    let spent = state.notes()[1];
+    state.spend(spent.gen_nullifier(&sk));
+
+    let wallet = Wallet::new(TestStore, state);
+
+    assert_eq!(wallet.balance(0).unwrap().value, 25);
+}
+
+#[test]
+fn transfer_works() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [10, 250, 15, 7500]);
+
+    let anchor = state.fetch_anchor().unwrap();
+    let wallet = Wallet::new(TestStore, state);
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let refund = wallet.public_key(0).unwrap();
+    let receiver = wallet.public_key(1).unwrap();
+
+    let tx = wallet
+        .transfer(rng, 0, &refund, &receiver, 100, 15, 100, 2)
+        .unwrap();
+
+    let total_input: u64 = tx.inputs.iter().map(|i| i.value).sum();
+    let total_output: u64 = tx.outputs.iter().map(|o| o.value).sum();
+
+    assert_eq!(tx.anchor, anchor);
+    assert_eq!(total_input, total_output + 200);
+    assert!(tx.outputs.iter().any(|o| o.value == 100));
+
+    let err = wallet
+        .transfer(rng, 0, &refund, &receiver, 10_000, 15, 100, 2)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Wallet(WalletError::InsufficientBalance)
+    ));
+}
+
+#[test]
+fn transfer_fetches_picked_openings() {
+    let mut inner = MockStateClient::new();
+    push_notes(&mut inner, 0, [10, 250, 15, 39, 55, 7500, 3, 8]);
+
+    let state = CountingState {
+        inner,
+        openings: Cell::new(0),
+    };
+    let wallet = Wallet::new(TestStore, state);
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let refund = wallet.public_key(0).unwrap();
+    let receiver = wallet.public_key(1).unwrap();
+
+    let tx = wallet
+        .transfer(rng, 0, &refund, &receiver, 5000, 15, 100, 2)
+        .unwrap();
+
+    assert_eq!(wallet.state().openings.get(), tx.inputs.len());
+}
+
+#[test]
+fn transfer_uses_coin_selection() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, (1..=200).map(|value| value * 10));
+
+    let wallet = Wallet::new(TestStore, state)
+        .with_coin_selection(Strategy::LargestFirst);
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let refund = wallet.public_key(0).unwrap();
+    let receiver = wallet.public_key(1).unwrap();
+
+    let tx = wallet
+        .transfer(rng, 0, &refund, &receiver, 3000, 15, 100, 2)
+        .unwrap();
+
+    let mut values: Vec<u64> = tx.inputs.iter().map(|i| i.value).collect();
+    values.sort();
+    assert_eq!(values, [1990, 2000]);
+}
+
+#[test]
+fn transfer_uses_note_type() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [10, 250, 15, 7500]);
+
+    let wallet = Wallet::new(TestStore, state)
+        .with_note_type(types::OutputType::Transparent);
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let refund = wallet.public_key(0).unwrap();
+    let receiver = wallet.public_key(1).unwrap();
+
+    let tx = wallet
+        .transfer(rng, 0, &refund, &receiver, 100, 15, 100, 2)
+        .unwrap();
+
+    // both the transfer and the change are transparent
+    assert_eq!(tx.outputs.len(), 2);
+    assert!(tx
+        .outputs
+        .iter()
+        .all(|o| o.note.note() == NoteType::Transparent));
+}
+
+#[test]
+fn execute_pays_several_receivers() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [1300]);
+
+    let wallet = Wallet::new(TestStore, state);
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let refund = wallet.public_key(0).unwrap();
+
+    let output = |index, value| types::ExecuteOutput {
+        note_type: types::OutputType::Obfuscated,
+        receiver: bs58::encode(wallet.public_key(index).unwrap().to_bytes())
+            .into_string(),
+        ref_id: None,
+        value,
+    };
+    let execute = |rng: &mut StdRng, outputs| {
+        wallet.execute(rng, 0, &refund, outputs, 100, 2, None, None)
+    };
+
+    // the outputs spend the whole note, so no change is needed
+    let tx = execute(rng, vec![output(1, 600), output(2, 500)]).unwrap();
+    let values: Vec<u64> = tx.outputs.iter().map(|o| o.value).collect();
+    assert_eq!(values, [600, 500]);
+
+    // the change doesn't fit along with the outputs
+    let err = execute(rng, vec![output(1, 600), output(2, 400)]).unwrap_err();
+    assert!(matches!(err, Error::Wallet(WalletError::TooManyOutputs)));
+
+    let err = execute(rng, vec![output(1, 10), output(2, 10), output(3, 10)])
+        .unwrap_err();
+    assert!(matches!(err, Error::Wallet(WalletError::TooManyOutputs)));
+
+    let err =
+        execute(rng, vec![output(1, u64::MAX), output(2, 1)]).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Wallet(WalletError::InvalidValue("outputs"))
+    ));
+}
+
+#[test]
+fn preview_works() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [10, 250, 15, 7500]);
+
+    let wallet = Wallet::new(TestStore, state);
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let refund = wallet.public_key(0).unwrap();
+    let receiver = wallet.public_key(1).unwrap();
+    let vk = wallet.view_key(0).unwrap();
+
+    let mut tx = wallet
+        .transfer(rng, 0, &refund, &receiver, 100, 15, 100, 2)
+        .unwrap();
+
+    let preview = Preview::new(&tx, Some(&vk));
+    assert_eq!(preview.sent, 100);
+    assert_eq!(preview.fee, 200);
+    assert_eq!(preview.total_input, preview.sent + preview.change + 200);
+    assert_eq!(preview.outputs.iter().filter(|o| o.change).count(), 1);
+    assert_eq!(preview.anomalies, [Anomaly::FeeExceedsValue]);
+    assert!(!preview.self_transfer);
+
+    // without the view key, the change can't be told apart
+    let preview = Preview::new(&tx, None);
+    assert_eq!(preview.change, 0);
+    assert!(preview.anomalies.is_empty());
+
+    tx.outputs[0].value += 1;
+    let preview = Preview::new(&tx, Some(&vk));
+    assert!(preview.anomalies.contains(&Anomaly::Unbalanced));
+}
+
+#[test]
+fn preview_consolidation_works() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [1, 2, 50, 60, 70, 80, 90, 100, 110, 120]);
+
+    let wallet = Wallet::new(TestStore, state);
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let vk = wallet.view_key(0).unwrap();
+
+    let consolidation = wallet.consolidate(rng, 0, 200, 1).unwrap();
+    let tx = &consolidation.transactions[0];
+
+    // the notes are merged back to the sender, so the fee isn't weighed
+    // against the nothing sent
+    let preview = Preview::new(tx, Some(&vk));
+    assert!(preview.self_transfer);
+    assert_eq!(preview.sent, 0);
+    assert_eq!(preview.change, 60);
+    assert!(preview.anomalies.is_empty());
+
+    // without the view key, the merged note looks sent away
+    let preview = Preview::new(tx, None);
+    assert!(!preview.self_transfer);
+    assert_eq!(preview.sent, 60);
+}
+
+#[test]
+fn verify_works() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [10, 250, 15, 7500]);
+
+    let wallet = Wallet::new(TestStore, state);
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let refund = wallet.public_key(0).unwrap();
+    let receiver = wallet.public_key(1).unwrap();
+    let sk = key::derive_sk(&SEED, 0);
+
+    let tx = wallet
+        .transfer(rng, 0, &refund, &receiver, 100, 15, 100, 2)
+        .unwrap();
+    tx.verify(&sk).unwrap();
+
+    assert_eq!(
+        tx.verify(&key::derive_sk(&SEED, 1)),
+        Err(WalletError::InvalidTransaction("nullifiers"))
+    );
+
+    let mut bad = tx.clone();
+    bad.anchor = BlsScalar::one();
+    assert_eq!(bad.verify(&sk), Err(WalletError::InvalidOpening));
+
+    let mut bad = tx.clone();
+    bad.fee.gas_limit += 1;
+    assert_eq!(
+        bad.verify(&sk),
+        Err(WalletError::InvalidTransaction("signatures"))
+    );
+
+    let mut bad = tx;
+    bad.outputs[0].value += 1;
+    assert_eq!(
+        bad.verify(&sk),
+        Err(WalletError::InvalidTransaction("commitments"))
+    );
+}
+
+#[test]
+fn consolidate_works() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [1, 2, 50, 60, 70, 80, 90, 100, 110, 120]);
+
+    let wallet = Wallet::new(TestStore, state);
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    let consolidation = wallet.consolidate(rng, 0, 200, 1).unwrap();
+
+    // the dust is left out, and the remaining eight notes merged in two
+    assert_eq!(consolidation.dust, 2);
+    assert_eq!(consolidation.rounds, 1);
+    assert_eq!(consolidation.total_transactions, 2);
+    assert_eq!(consolidation.fee, 400);
+
+    let tx = &consolidation.transactions[0];
+    let values: Vec<u64> = tx.inputs.iter().map(|i| i.value).collect();
+
+    assert_eq!(values, [50, 60, 70, 80]);
+    assert_eq!(tx.outputs.len(), 1);
+    assert_eq!(tx.outputs[0].value, 60);
+}
+
+#[test]
+fn withdraw_works() {
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [10, 250, 15, 7500]);
+
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+    let wallet = Wallet::new(TestStore, state);
+    let refund = wallet.public_key(0).unwrap();
+
+    let err = wallet.withdraw(rng, 0, 0, &refund, 100, 2).unwrap_err();
+    assert!(matches!(err, Error::Wallet(WalletError::NoStake)));
+
+    let mut state = MockStateClient::new();
+    push_notes(&mut state, 0, [10, 250, 15, 7500]);
+    state.set_stake(
+        wallet.stake_public_key(0).unwrap(),
+        StakeData {
+            amount: Some((1000, 0)),
+            reward: 10,
+            counter: 1,
+        },
+    );
+    let wallet = Wallet::new(TestStore, state);
+
+    let tx = wallet.withdraw(rng, 0, 0, &refund, 100, 2).unwrap();
+    let call = tx.call.expect("the withdrawal should call the contract");
+
+    assert_eq!(call.method, "withdraw");
+    assert_eq!(call.contract, rusk_abi::STAKE_CONTRACT);
+}
+
+fn push_notes<Values>(state: &mut MockStateClient, index: u64, values: Values)
+where
+    Values: IntoIterator<Item = u64>,
+{
+    let rng = &mut StdRng::seed_from_u64(index);
+    let pk = key::derive_pk(&SEED, index);
+
+    for value in values {
+        state.push_note(Note::transparent(rng, &pk, value));
+    }
+}
+
+struct TestStore;
+
+impl Store for TestStore {
+    type Error = Infallible;
+
+    fn get_seed(&self) -> Result<[u8; RNG_SEED], Self::Error> {
+        Ok(SEED)
+    }
+}
+
+/// Counts the openings queried from the inner state.
+struct CountingState {
+    inner: MockStateClient,
+    openings: Cell<usize>,
+}
+
+impl StateClient for CountingState {
+    type Error = WalletError;
+
+    fn fetch_notes(
+        &self,
+        vk: &ViewKey,
+        since: u64,
+    ) -> Result<Vec<Note>, Self::Error> {
+        self.inner.fetch_notes(vk, since)
+    }
+
+    fn fetch_opening(&self, pos: u64) -> Result<Opening, Self::Error> {
+        self.openings.set(self.openings.get() + 1);
+        self.inner.fetch_opening(pos)
+    }
+
+    fn fetch_anchor(&self) -> Result<BlsScalar, Self::Error> {
+        self.inner.fetch_anchor()
+    }
+
+    fn fetch_existing_nullifiers(
+        &self,
+        nullifiers: &[BlsScalar],
+    ) -> Result<Vec<BlsScalar>, Self::Error> {
+        self.inner.fetch_existing_nullifiers(nullifiers)
+    }
+
+    fn fetch_stake(
+        &self,
+        pk: &StakePublicKey,
+    ) -> Result<Option<StakeData>, Self::Error> {
+        self.inner.fetch_stake(pk)
+    }
+}
@@ -0,0 +1,299 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Unproven transaction encoding and signing tests.
+
+use dusk_bytes::Serializable;
+use dusk_jubjub::{JubJubAffine, JubJubScalar};
+use dusk_wallet_core::{
+    key,
+    offline::{self, Template},
+    select::Strategy,
+    tx::{PreInput, UnprovenTransaction, POSEIDON_TREE_ARITY},
+    types, utils, WalletError, MAX_LEN, RNG_SEED,
+};
+use ff::Field;
+use phoenix_core::{Crossover, Fee, Note, ViewKey};
+use poseidon_merkle::{Item, Tree};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+use rusk_abi::POSEIDON_TREE_DEPTH;
+
+const SEED: [u8; RNG_SEED] = [0xfa; RNG_SEED];
+
+#[test]
+fn var_bytes_roundtrip() {
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    for _ in 0..32 {
+        let utx = random_tx(rng);
+        let bytes = utx.to_var_bytes();
+
+        let decoded = UnprovenTransaction::from_var_bytes(&bytes).unwrap();
+
+        assert_eq!(rkyv_bytes(&decoded), rkyv_bytes(&normalize(utx)));
+        assert_eq!(decoded.to_var_bytes(), bytes);
+    }
+}
+
+#[test]
+fn var_bytes_checks_length() {
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    let utx = loop {
+        let utx = random_tx(rng);
+        if utx.call.is_none() {
+            break utx;
+        }
+    };
+    let bytes = utx.to_var_bytes();
+
+    // every truncation fails, as does a trailing byte with no call to
+    // take it as payload
+    for len in 0..bytes.len() {
+        assert_eq!(
+            UnprovenTransaction::from_var_bytes(&bytes[..len]).unwrap_err(),
+            WalletError::InvalidLength("tx")
+        );
+    }
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        UnprovenTransaction::from_var_bytes(&trailing).unwrap_err(),
+        WalletError::InvalidLength("tx")
+    );
+
+    // the call flag is neither 0 nor 1
+    let flag = bytes.len() - 8;
+    let mut bad_flag = bytes;
+    bad_flag[flag] = 2;
+    assert_eq!(
+        UnprovenTransaction::from_var_bytes(&bad_flag).unwrap_err(),
+        WalletError::Decode("tx")
+    );
+}
+
+#[test]
+fn template_signs_offline() {
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    let sk = key::derive_sk(&SEED, 0);
+    let vk = ViewKey::from(&sk);
+    let pk = key::derive_pk(&SEED, 0);
+
+    let mut tree = Tree::<(), POSEIDON_TREE_DEPTH, POSEIDON_TREE_ARITY>::new();
+    let notes: Vec<utils::Node> = [100, 250, 7500]
+        .into_iter()
+        .enumerate()
+        .map(|(pos, value)| {
+            let blinder = JubJubScalar::random(&mut *rng);
+            let mut note = Note::obfuscated(rng, &pk, value, blinder);
+            note.set_pos(pos as u64);
+            tree.insert(pos as u64, Item::new(note.hash(), ()));
+            (note, value, blinder)
+        })
+        .collect();
+
+    let receiver = key::derive_pk(&SEED, 1);
+    let outputs = vec![types::ExecuteOutput {
+        note_type: types::OutputType::Obfuscated,
+        receiver: bs58::encode(receiver.to_bytes()).into_string(),
+        ref_id: None,
+        value: 300,
+    }];
+
+    // the online host only knows the view key of the sender
+    assert!(notes.iter().all(|(note, _, _)| vk.owns(note)));
+    let template = offline::template(
+        rng,
+        notes,
+        &Strategy::LargestFirst,
+        |pos| tree.opening(pos).ok_or(WalletError::MissingOpening),
+        &pk,
+        types::OutputType::Obfuscated,
+        outputs,
+        100,
+        1,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+
+    let bytes = template.to_transport_bytes().unwrap();
+    assert_eq!(&bytes[..4], &offline::MAGIC);
+
+    let crossover = types::CrossoverType {
+        blinder: vec![1, 2, 3],
+        crossover: vec![1, 2, 3],
+        value: 0,
+    };
+    let result = offline::template(
+        rng,
+        Vec::new(),
+        &Strategy::LargestFirst,
+        |pos| tree.opening(pos).ok_or(WalletError::MissingOpening),
+        &pk,
+        types::OutputType::Obfuscated,
+        Vec::new(),
+        100,
+        1,
+        None,
+        Some(crossover),
+        None,
+    );
+    assert_eq!(result.unwrap_err(), WalletError::Decode("crossover"));
+
+    let mut corrupted = bytes.clone();
+    corrupted[10] ^= 1;
+    assert_eq!(
+        Template::from_transport_bytes(&corrupted).unwrap_err(),
+        WalletError::Decode("template")
+    );
+    assert_eq!(
+        Template::from_transport_bytes(&bytes[..8]).unwrap_err(),
+        WalletError::InvalidLength("template")
+    );
+
+    let template = Template::from_transport_bytes(&bytes).unwrap();
+    assert_eq!(
+        template
+            .clone()
+            .sign(rng, &key::derive_sk(&SEED, 1))
+            .unwrap_err(),
+        WalletError::NoteOwnership("inputs")
+    );
+
+    // a template tampered with by the online host is not signed
+    let mut tampered = template.clone();
+    tampered.outputs[1].value += 100;
+    assert_eq!(
+        tampered.sign(rng, &sk).unwrap_err(),
+        WalletError::InvalidTransaction("commitments")
+    );
+
+    let mut tampered = template.clone();
+    tampered.fee.gas_price = 2;
+    assert_eq!(
+        tampered.sign(rng, &sk).unwrap_err(),
+        WalletError::InvalidTransaction("value")
+    );
+
+    let tx = template.sign(rng, &sk).unwrap();
+    tx.verify(&sk).unwrap();
+
+    let values: Vec<u64> = tx.inputs.iter().map(|i| i.value).collect();
+    assert_eq!(values, [7500]);
+    assert_eq!(tx.outputs[0].value, 7100);
+    assert_eq!(tx.outputs[1].value, 300);
+}
+
+/// Builds a transaction of random shape, spending notes of random values.
+fn random_tx(rng: &mut StdRng) -> UnprovenTransaction {
+    let sk = key::derive_sk(&SEED, 0);
+    let pk = key::derive_pk(&SEED, 0);
+
+    let mut tree = Tree::<(), POSEIDON_TREE_DEPTH, POSEIDON_TREE_ARITY>::new();
+    let notes: Vec<(Note, u64)> = (0..rng.gen_range(1..=4))
+        .map(|pos| {
+            let value = rng.gen_range(1..1_000_000);
+            let mut note = match rng.gen() {
+                true => Note::transparent(rng, &pk, value),
+                false => {
+                    let blinder = JubJubScalar::random(&mut *rng);
+                    Note::obfuscated(rng, &pk, value, blinder)
+                }
+            };
+            note.set_pos(pos);
+            tree.insert(pos, Item::new(note.hash(), ()));
+            (note, value)
+        })
+        .collect();
+
+    let inputs = notes.iter().map(|(note, value)| PreInput {
+        note: *note,
+        opening: tree.opening(*note.pos()).unwrap(),
+        value: *value,
+        sk: &sk,
+    });
+
+    let receiver = bs58::encode(pk.to_bytes()).into_string();
+    let outputs: Vec<types::ExecuteOutput> = (0..rng.gen_range(0..=2))
+        .map(|_| types::ExecuteOutput {
+            note_type: match rng.gen() {
+                true => types::OutputType::Transparent,
+                false => types::OutputType::Obfuscated,
+            },
+            receiver: receiver.clone(),
+            ref_id: rng.gen(),
+            value: rng.gen_range(1..1000),
+        })
+        .collect();
+
+    let crossover = rng.gen::<bool>().then(|| types::CrossoverType {
+        blinder: rkyv_bytes(&JubJubScalar::random(&mut *rng)),
+        crossover: rkyv_bytes(&Crossover::default()),
+        value: rng.gen_range(0..1000),
+    });
+
+    let call = rng.gen::<bool>().then(|| {
+        let mut payload = vec![0; rng.gen_range(0..64)];
+        rng.fill_bytes(&mut payload);
+
+        types::ExecuteCall {
+            contract: bs58::encode([rng.gen::<u8>(); 32]).into_string(),
+            method: String::from("transfer"),
+            payload,
+        }
+    });
+
+    let gas_limit = rng.gen_range(1..1000);
+    let fee = Fee::new(rng, gas_limit, 1, &pk);
+
+    let mut rng_seed = [0; 32];
+    rng.fill_bytes(&mut rng_seed);
+
+    UnprovenTransaction::new(
+        &mut utils::rng(rng_seed),
+        inputs,
+        outputs,
+        fee,
+        crossover,
+        call,
+    )
+    .unwrap()
+}
+
+/// Brings the curve points of the transaction to the affine form they are
+/// encoded in, so its rkyv representation matches the one of the decoded
+/// transaction.
+fn normalize(mut utx: UnprovenTransaction) -> UnprovenTransaction {
+    fn reencode<T: Serializable<N>, const N: usize>(value: &T) -> T {
+        T::from_bytes(&value.to_bytes()).ok().unwrap()
+    }
+
+    for input in &mut utx.inputs {
+        input.note = reencode(&input.note);
+        input.note_pk_prime = JubJubAffine::from(input.note_pk_prime).into();
+        input.sig = reencode(&input.sig);
+    }
+    for output in &mut utx.outputs {
+        output.note = reencode(&output.note);
+    }
+    utx.fee = reencode(&utx.fee);
+    if let Some(crossover) = &mut utx.crossover {
+        crossover.crossover = reencode(&crossover.crossover);
+    }
+
+    utx
+}
+
+fn rkyv_bytes<T>(value: &T) -> Vec<u8>
+where
+    T: rkyv::Serialize<rkyv::ser::serializers::AllocSerializer<MAX_LEN>>,
+{
+    rkyv::to_bytes::<_, MAX_LEN>(value).unwrap().to_vec()
+}
@@ -0,0 +1,290 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Deterministic test vectors, checked against `assets/vectors.json`.
+//!
+//! The vectors let alternative wallet implementations prove byte-for-byte
+//! compatibility with this one. After an intended change of the derivations or
+//! of the encodings, run the tests with `UPDATE_VECTORS=1` to regenerate them.
+
+use std::{env, fs, path::PathBuf};
+
+use dusk_bytes::Serializable;
+use dusk_wallet_core::{
+    compat::mnemonic::mnemonic_seed,
+    key,
+    stake::{self, Stct},
+    tx::{self, PreInput, UnprovenTransaction, POSEIDON_TREE_ARITY},
+    types, utils, MAX_LEN, RNG_SEED,
+};
+use phoenix_core::{Fee, Note};
+use poseidon_merkle::{Item, Tree};
+use rand::{rngs::StdRng, SeedableRng};
+use rusk_abi::POSEIDON_TREE_DEPTH;
+use serde::{Deserialize, Serialize};
+
+const PASSPHRASES: [&str; 3] = [
+    "",
+    "Taking a new step, uttering a new word, is what people fear most.",
+    "\u{1f512} passphrase",
+];
+
+const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon \
+                        abandon abandon abandon abandon abandon about";
+
+const SEED: [u8; RNG_SEED] = [0xfa; RNG_SEED];
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Vectors {
+    seed: Vec<SeedVector>,
+    mnemonic_seed: Vec<MnemonicSeedVector>,
+    keys: Vec<KeyVector>,
+    transactions: Vec<TransactionVector>,
+}
+
+/// A seed computed from a passphrase by the `seed` function.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SeedVector {
+    passphrase: String,
+    seed: String,
+}
+
+/// A seed computed from a mnemonic by the `get_mnemonic_seed` function.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct MnemonicSeedVector {
+    mnemonic: String,
+    passphrase: String,
+    seed: String,
+}
+
+/// The keys derived from a seed at an index.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct KeyVector {
+    seed: String,
+    index: u64,
+    sk: String,
+    stake_sk: String,
+    vk: String,
+    pk: String,
+}
+
+/// An unproven transaction built from fixed inputs and `rng_seed`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct TransactionVector {
+    seed: String,
+    sender_index: u64,
+    rng_seed: String,
+    inputs: Vec<InputVector>,
+    outputs: Vec<types::ExecuteOutput>,
+    fee: String,
+    crossover: Option<types::CrossoverType>,
+    call: Option<types::ExecuteCall>,
+    unproven_tx: String,
+    var_bytes: String,
+}
+
+/// An input note, along with its rkyv serialized opening.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct InputVector {
+    note: String,
+    value: u64,
+    opening: String,
+}
+
+#[test]
+fn vectors_match() {
+    let vectors = Vectors {
+        seed: seed_vectors(),
+        mnemonic_seed: mnemonic_seed_vectors(),
+        keys: key_vectors(),
+        transactions: vec![transfer_vector(), contract_call_vector()],
+    };
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("assets")
+        .join("vectors.json");
+
+    if env::var_os("UPDATE_VECTORS").is_some() {
+        let json = serde_json::to_string_pretty(&vectors).unwrap() + "\n";
+        fs::write(&path, json).unwrap();
+    }
+
+    let json = fs::read_to_string(&path).unwrap();
+    let expected: Vectors = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(vectors.seed, expected.seed);
+    assert_eq!(vectors.mnemonic_seed, expected.mnemonic_seed);
+    assert_eq!(vectors.keys, expected.keys);
+    assert_eq!(vectors.transactions, expected.transactions);
+}
+
+fn seed_vectors() -> Vec<SeedVector> {
+    PASSPHRASES
+        .into_iter()
+        .map(|passphrase| SeedVector {
+            passphrase: passphrase.to_string(),
+            seed: hex::encode(key::seed_from_passphrase(passphrase.as_bytes())),
+        })
+        .collect()
+}
+
+fn mnemonic_seed_vectors() -> Vec<MnemonicSeedVector> {
+    PASSPHRASES
+        .into_iter()
+        .map(|passphrase| MnemonicSeedVector {
+            mnemonic: MNEMONIC.to_string(),
+            passphrase: passphrase.to_string(),
+            seed: hex::encode(mnemonic_seed(MNEMONIC, passphrase).unwrap()),
+        })
+        .collect()
+}
+
+fn key_vectors() -> Vec<KeyVector> {
+    [[0; RNG_SEED], SEED]
+        .into_iter()
+        .flat_map(|seed| [0, 1, 42].map(|index| (seed, index)))
+        .map(|(seed, index)| KeyVector {
+            seed: hex::encode(seed),
+            index,
+            sk: hex::encode(key::derive_sk(&seed, index).to_bytes()),
+            stake_sk: hex::encode(
+                key::derive_stake_sk(&seed, index).to_bytes(),
+            ),
+            vk: hex::encode(key::derive_vk(&seed, index).to_bytes()),
+            pk: bs58::encode(key::derive_pk(&seed, index).to_bytes())
+                .into_string(),
+        })
+        .collect()
+}
+
+fn transfer_vector() -> TransactionVector {
+    let receiver =
+        bs58::encode(key::derive_pk(&SEED, 1).to_bytes()).into_string();
+
+    let outputs = vec![
+        types::ExecuteOutput {
+            note_type: types::OutputType::Transparent,
+            receiver: receiver.clone(),
+            ref_id: None,
+            value: 100,
+        },
+        types::ExecuteOutput {
+            note_type: types::OutputType::Obfuscated,
+            receiver,
+            ref_id: Some(15),
+            value: 2000,
+        },
+    ];
+
+    transaction_vector([0xbe; 32], &[500, 1700, 80], outputs, None, None)
+}
+
+fn contract_call_vector() -> TransactionVector {
+    let rng = &mut StdRng::seed_from_u64(0xca11);
+    let sender = key::derive_sk(&SEED, 0);
+    let refund = key::derive_pk(&SEED, 0);
+
+    let stct = Stct::new(rng, &sender, &refund, 1000, 100, 2);
+    let crossover =
+        stake::crossover(&stct.crossover, &stct.blinder, stct.value).unwrap();
+
+    let call = types::ExecuteCall {
+        contract: bs58::encode(rusk_abi::STAKE_CONTRACT).into_string(),
+        method: "stake".to_string(),
+        payload: vec![0xde, 0xad, 0xbe, 0xef],
+    };
+
+    transaction_vector(
+        [0xca; 32],
+        &[900, 600],
+        vec![],
+        Some(crossover),
+        Some(call),
+    )
+}
+
+/// Builds the notes of the given values for the first account into a tree,
+/// and then a transaction spending them all.
+fn transaction_vector(
+    rng_seed: [u8; 32],
+    values: &[u64],
+    outputs: Vec<types::ExecuteOutput>,
+    crossover: Option<types::CrossoverType>,
+    call: Option<types::ExecuteCall>,
+) -> TransactionVector {
+    let rng = &mut StdRng::seed_from_u64(u64::from(rng_seed[0]));
+    let sk = key::derive_sk(&SEED, 0);
+    let pk = key::derive_pk(&SEED, 0);
+
+    let mut tree = Tree::<(), POSEIDON_TREE_DEPTH, POSEIDON_TREE_ARITY>::new();
+    let notes: Vec<Note> = values
+        .iter()
+        .enumerate()
+        .map(|(pos, value)| {
+            let mut note = Note::obfuscated(
+                rng,
+                &pk,
+                *value,
+                dusk_jubjub::JubJubScalar::from(pos as u64 + 1),
+            );
+            note.set_pos(pos as u64);
+            tree.insert(pos as u64, Item::new(note.hash(), ()));
+            note
+        })
+        .collect();
+
+    let openings: Vec<tx::Opening> = notes
+        .iter()
+        .map(|note| tree.opening(*note.pos()).unwrap())
+        .collect();
+
+    let fee = Fee::new(rng, 100, 2, &pk);
+
+    let inputs = notes.iter().zip(&openings).zip(values).map(
+        |((note, opening), value)| PreInput {
+            note: *note,
+            opening: *opening,
+            value: *value,
+            sk: &sk,
+        },
+    );
+
+    let utx = UnprovenTransaction::new(
+        &mut utils::rng(rng_seed),
+        inputs,
+        outputs.clone(),
+        fee,
+        crossover.clone(),
+        call.clone(),
+    )
+    .expect("the transaction should build");
+
+    let inputs = notes
+        .iter()
+        .zip(&openings)
+        .zip(values)
+        .map(|((note, opening), value)| InputVector {
+            note: hex::encode(note.to_bytes()),
+            value: *value,
+            opening: hex::encode(
+                rkyv::to_bytes::<_, MAX_LEN>(opening).unwrap(),
+            ),
+        })
+        .collect();
+
+    TransactionVector {
+        seed: hex::encode(SEED),
+        sender_index: 0,
+        rng_seed: hex::encode(rng_seed),
+        inputs,
+        outputs,
+        fee: hex::encode(fee.to_bytes()),
+        crossover,
+        call,
+        unproven_tx: hex::encode(rkyv::to_bytes::<_, MAX_LEN>(&utx).unwrap()),
+        var_bytes: hex::encode(utx.to_var_bytes()),
+    }
+}
@@ -9,11 +9,13 @@
 use dusk_bytes::Serializable;
 use dusk_jubjub::JubJubScalar;
 use dusk_wallet_core::{
+    offline::Template,
     tx,
     types::{self, CrossoverType as WasmCrossover},
     utils, MAX_KEY, MAX_LEN, RNG_SEED,
 };
-use phoenix_core::{Crossover, PublicKey, ViewKey};
+use phoenix_core::{Crossover, Fee, PublicKey, ViewKey};
+use rand::{rngs::StdRng, SeedableRng};
 use rusk_abi::ContractId;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -30,13 +32,221 @@ fn seed_works() {
     assert_eq!(seed.len(), RNG_SEED);
 }
 
+#[test]
+fn mnemonic_works() {
+    let mut wallet = Wallet::default();
+
+    let types::MnewmonicNewResponse { mnemonic_string } = wallet
+        .call(
+            "new_mnemonic",
+            json!({
+                "rng_seed": vec![0x42; 32],
+                "word_count": 12,
+                "language": "English",
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(mnemonic_string.split_whitespace().count(), 12);
+
+    let types::ValidateMnemonicResponse {
+        valid, language, ..
+    } = wallet
+        .call("validate_mnemonic", json!({ "mnemonic": mnemonic_string }))
+        .take_contents();
+
+    assert!(valid);
+    assert_eq!(language, types::MnemonicLanguage::English);
+
+    let types::GetMnemonicSeedResponse { mnemonic_seed } = wallet
+        .call(
+            "get_mnemonic_seed",
+            json!({
+                "mnemonic": mnemonic_string,
+                "passphrase": "",
+                "language": "English",
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(mnemonic_seed.len(), RNG_SEED);
+
+    // only English is built in by default
+    let result = wallet.call(
+        "new_mnemonic",
+        json!({
+            "rng_seed": vec![0x42; 32],
+            "language": "Spanish",
+        }),
+    );
+    assert!(!result.status);
+
+    let types::MnemonicSuggestionsResponse { words } = wallet
+        .call("mnemonic_suggestions", json!({ "prefix": "zo" }))
+        .take_contents();
+
+    assert_eq!(words, ["zone", "zoo"]);
+}
+
+#[test]
+fn shamir_works() {
+    let seed = [0xfa; RNG_SEED];
+    let mut wallet = Wallet::default();
+
+    let types::ShamirSplitResponse { groups } = wallet
+        .call(
+            "shamir_split",
+            json!({
+                "secret": seed.to_vec(),
+                "group_threshold": 1,
+                "groups": [{ "threshold": 2, "count": 3 }],
+                "rng_seed": vec![0xbe; 32],
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(groups[0].shares.len(), 3);
+
+    let types::ShamirCombineResponse { seed: combined, .. } = wallet
+        .call(
+            "shamir_combine",
+            json!({ "shares": [groups[0].shares[2], groups[0].shares[0]] }),
+        )
+        .take_contents();
+
+    assert_eq!(combined, seed.to_vec());
+
+    let result = wallet
+        .call("shamir_combine", json!({ "shares": [groups[0].shares[1]] }));
+    assert!(!result.status);
+
+    // a seed takes no passphrase
+    let result = wallet.call(
+        "shamir_combine",
+        json!({
+            "shares": [groups[0].shares[2], groups[0].shares[0]],
+            "passphrase": "pass",
+        }),
+    );
+    assert!(!result.status);
+
+    let entropy = [0x42; 16];
+
+    let types::ShamirSplitResponse { groups } = wallet
+        .call(
+            "shamir_split",
+            json!({
+                "secret": entropy.to_vec(),
+                "group_threshold": 1,
+                "groups": [{ "threshold": 1, "count": 1 }],
+                "rng_seed": vec![0xbe; 32],
+            }),
+        )
+        .take_contents();
+
+    let types::ShamirCombineResponse { secret, seed } = wallet
+        .call(
+            "shamir_combine",
+            json!({
+                "shares": groups[0].shares,
+                "passphrase": "pass",
+                "language": "English",
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(secret, entropy.to_vec());
+    assert_eq!(
+        seed,
+        dusk_wallet_core::compat::mnemonic::mnemonic_seed(
+            &bip39::Mnemonic::from_entropy(&entropy).unwrap().to_string(),
+            "pass"
+        )
+        .unwrap()
+        .to_vec()
+    );
+}
+
+#[test]
+fn wallet_file_works() {
+    let seed = [0xfa; RNG_SEED];
+    let mut wallet = Wallet::default();
+
+    let types::PublicKeysResponse { keys } = wallet
+        .call("public_keys", json!({ "seed": vec![0xab; RNG_SEED] }))
+        .take_contents();
+    let alice = &keys[0];
+
+    let types::WalletExportResponse { wallet: file } = wallet
+        .call(
+            "wallet_export",
+            json!({
+                "seed": seed.to_vec(),
+                "password": "correct horse battery staple",
+                "rng_seed": vec![0xbe; 32],
+                "accounts": [{ "index": 0, "label": "main" }],
+                "contacts": [{ "label": "alice", "address": alice }],
+            }),
+        )
+        .take_contents();
+
+    let types::WalletImportResponse {
+        seed: imported,
+        accounts,
+        contacts,
+        ..
+    } = wallet
+        .call(
+            "wallet_import",
+            json!({
+                "wallet": file,
+                "password": "correct horse battery staple",
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(imported, seed.to_vec());
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].label, "main");
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(&contacts[0].address, alice);
+
+    let result = wallet.call(
+        "wallet_import",
+        json!({
+            "wallet": file,
+            "password": "wrong",
+        }),
+    );
+    assert!(!result.status);
+
+    // an account out of range would be synchronized along with all the
+    // accounts before it
+    let result = wallet.call(
+        "wallet_export",
+        json!({
+            "seed": seed.to_vec(),
+            "password": "correct horse battery staple",
+            "rng_seed": vec![0xbe; 32],
+            "accounts": [{ "index": u64::MAX, "label": "main" }],
+        }),
+    );
+    assert!(!result.status);
+}
+
 #[test]
 fn balance_works() {
     let seed = [0xfa; RNG_SEED];
     let values = [10, 250, 15, 39, 55];
     let mut wallet = Wallet::default();
 
-    let types::BalanceResponse { maximum, value } = wallet
+    let types::BalanceResponse {
+        accounts,
+        maximum,
+        obfuscated,
+        transparent,
+        value,
+    } = wallet
         .call(
             "balance",
             json!({
@@ -48,6 +258,280 @@ fn balance_works() {
 
     assert_eq!(value, values.into_iter().sum::<u64>());
     assert_eq!(maximum, 359);
+
+    // the notes are of random types, each counted once
+    assert_eq!(transparent + obfuscated, value);
+
+    assert_eq!(accounts.len(), MAX_KEY);
+    assert_eq!(accounts[0].value, value);
+    assert_eq!(accounts[0].maximum, maximum);
+    assert_eq!(accounts[0].transparent, transparent);
+}
+
+#[test]
+fn watch_balance_works() {
+    let seed = [0xfa; RNG_SEED];
+    let values = [10, 250, 15, 39, 55];
+    let mut wallet = Wallet::default();
+
+    let view_keys = wallet
+        .call(
+            "view_keys",
+            json!({
+                "seed": seed.to_vec()
+            }),
+        )
+        .take_memory();
+
+    let types::BalanceResponse {
+        accounts,
+        maximum,
+        value,
+        ..
+    } = wallet
+        .call(
+            "watch_balance",
+            json!({
+                "notes": node::notes(&seed, values).0,
+                "view_keys": view_keys,
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(value, values.into_iter().sum::<u64>());
+    assert_eq!(maximum, 359);
+    assert_eq!(accounts.len(), MAX_KEY);
+
+    // the notes of another seed are not owned by the keys
+    let result = wallet.call(
+        "watch_balance",
+        json!({
+            "notes": node::notes(&[0xfb; RNG_SEED], values).0,
+            "view_keys": view_keys,
+        }),
+    );
+    assert!(!result.status);
+}
+
+#[test]
+fn check_note_ownership_works() {
+    let seed = [0xfa; RNG_SEED];
+    let leaves = node::leaves(&seed, 0, [(0, 10), (1, 20), (0, 30)]);
+    let mut wallet = Wallet::default();
+
+    // the seed followed by the leaves
+    let args = [&seed[..], &leaves].concat();
+
+    let types::CheckNoteOwnershipResponse {
+        notes,
+        nullifiers,
+        last_pos,
+        ..
+    } = wallet
+        .call_raw("check_note_ownership", &args)
+        .take_contents();
+
+    // only the first account is scanned
+    assert_eq!(notes.len(), 2);
+    assert_eq!(nullifiers.len(), 2);
+    assert_eq!(last_pos, 2);
+
+    let types::CheckNoteOwnershipResponse {
+        notes,
+        public_spend_keys,
+        ..
+    } = wallet
+        .call(
+            "check_accounts_ownership",
+            json!({
+                "seed": seed.to_vec(),
+                "leaves": leaves,
+                "account_count": 2,
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(notes.len(), 3);
+    assert_eq!(public_spend_keys[0], public_spend_keys[2]);
+    assert_ne!(public_spend_keys[0], public_spend_keys[1]);
+
+    let result = wallet.call_raw("check_note_ownership", &args[..RNG_SEED + 1]);
+    assert!(!result.status);
+
+    let result = wallet.call(
+        "check_accounts_ownership",
+        json!({
+            "seed": seed.to_vec(),
+            "leaves": leaves[1..],
+        }),
+    );
+    assert!(!result.status);
+}
+
+#[test]
+fn discover_accounts_works() {
+    let seed = [0xfa; RNG_SEED];
+    let leaves = node::leaves(&seed, 0, [(0, 10), (3, 20), (12, 30)]);
+    let mut wallet = Wallet::default();
+
+    let types::DiscoverAccountsResponse {
+        account_count,
+        indexes,
+        last_pos,
+    } = wallet
+        .call(
+            "discover_accounts",
+            json!({
+                "seed": seed.to_vec(),
+                "leaves": leaves,
+                "gap_limit": 5,
+            }),
+        )
+        .take_contents();
+
+    // the account 12 is past the gap following account 3
+    assert_eq!(indexes, [0, 3]);
+    assert_eq!(account_count, 4);
+    assert_eq!(last_pos, 2);
+
+    // the gap is counted from the accounts already known to be in use
+    let types::DiscoverAccountsResponse {
+        account_count,
+        indexes,
+        ..
+    } = wallet
+        .call(
+            "discover_accounts",
+            json!({
+                "seed": seed.to_vec(),
+                "leaves": leaves,
+                "account_count": 8,
+                "gap_limit": 5,
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(indexes, [0, 3, 12]);
+    assert_eq!(account_count, 13);
+
+    let result = wallet.call(
+        "discover_accounts",
+        json!({
+            "seed": seed.to_vec(),
+            "leaves": leaves,
+            "gap_limit": 0,
+        }),
+    );
+    assert!(!result.status);
+}
+
+#[test]
+fn sync_notes_works() {
+    let seed = [0xfa; RNG_SEED];
+    let first = node::leaves(&seed, 0, [(0, 10), (1, 20), (0, 30)]);
+    let second = node::leaves(&seed, 3, [(0, 40), (1, 50)]);
+    let mut wallet = Wallet::default();
+
+    let types::SyncNotesResponse {
+        checkpoint,
+        next_pos,
+        unspent_notes,
+        ..
+    } = wallet
+        .call(
+            "sync_notes",
+            json!({ "seed": seed.to_vec(), "leaves": first }),
+        )
+        .take_contents();
+
+    assert_eq!(next_pos, 3);
+    assert_eq!(unspent_notes.len(), 2);
+
+    // resume from the checkpoint
+    let types::SyncNotesResponse {
+        checkpoint,
+        next_pos,
+        block_height,
+        unspent_notes,
+        ..
+    } = wallet
+        .call(
+            "sync_notes",
+            json!({
+                "seed": seed.to_vec(),
+                "leaves": second,
+                "checkpoint": checkpoint,
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(next_pos, 5);
+    assert_eq!(block_height, 5);
+    assert_eq!(unspent_notes.len(), 3);
+
+    // the cursor is moved back to the last leaf of the block rolled back to,
+    // even if the note of that leaf is not owned
+    let types::SyncNotesResponse {
+        checkpoint,
+        next_pos,
+        block_height,
+        unspent_notes,
+        ..
+    } = wallet
+        .call(
+            "sync_notes",
+            json!({
+                "seed": seed.to_vec(),
+                "leaves": [],
+                "checkpoint": checkpoint,
+                "rollback_height": 2,
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(next_pos, 2);
+    assert_eq!(block_height, 2);
+    assert_eq!(unspent_notes.len(), 1);
+
+    // adding an account starts over, ignoring the leaves
+    let types::SyncNotesResponse {
+        checkpoint,
+        next_pos,
+        unspent_notes,
+        ..
+    } = wallet
+        .call(
+            "sync_notes",
+            json!({
+                "seed": seed.to_vec(),
+                "leaves": second,
+                "checkpoint": checkpoint,
+                "account_count": 2,
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(next_pos, 0);
+    assert_eq!(unspent_notes.len(), 1);
+
+    let all = [first, second].concat();
+    let types::SyncNotesResponse {
+        next_pos,
+        unspent_notes,
+        ..
+    } = wallet
+        .call(
+            "sync_notes",
+            json!({
+                "seed": seed.to_vec(),
+                "leaves": all,
+                "checkpoint": checkpoint,
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(next_pos, 5);
+    assert_eq!(unspent_notes.len(), 5);
 }
 
 #[test]
@@ -110,9 +594,477 @@ fn execute_works() {
     });
 
     let types::ExecuteResponse { tx } =
-        wallet.call("execute", args).take_contents();
+        wallet.call("execute", args.clone()).take_contents();
 
     rkyv::from_bytes::<tx::UnprovenTransaction>(&tx).unwrap();
+
+    // malformed fee and crossover bytes are errors, rather than left out
+    let mut malformed = args.clone();
+    malformed["fee"] = json!([1, 2, 3]);
+    assert!(!wallet.call("execute", malformed).status);
+
+    let mut malformed = args;
+    malformed["crossover"]["crossover"] = json!([1, 2, 3]);
+    assert!(!wallet.call("execute", malformed).status);
+}
+
+#[test]
+fn execute_sends_all() {
+    let seed = [0xfa; RNG_SEED];
+    let values = [10, 250, 15, 39, 55, 7500];
+
+    let mut wallet = Wallet::default();
+
+    let types::PublicKeysResponse { keys } = wallet
+        .call(
+            "public_keys",
+            json!({
+                "seed": seed.to_vec(),
+            }),
+        )
+        .take_contents();
+
+    let (inputs, openings) = node::notes_and_openings(&seed, values);
+
+    let types::ExecuteResponse { tx } = wallet
+        .call(
+            "execute",
+            json!({
+                "gas_limit": 100,
+                "gas_price": 2,
+                "inputs": inputs,
+                "openings": openings,
+                "output": {
+                    "note_type": "Transparent",
+                    "receiver": &keys[0],
+                    "value": 1,
+                },
+                "refund": &keys[0],
+                "rng_seed": vec![0xfb; 32],
+                "send_all": true,
+                "sender_index": 0,
+                "seed": seed.to_vec(),
+            }),
+        )
+        .take_contents();
+
+    let tx = rkyv::from_bytes::<tx::UnprovenTransaction>(&tx).unwrap();
+
+    // the four largest notes, net of the fee, with no change
+    let mut spent: Vec<u64> = tx.inputs.iter().map(|i| i.value).collect();
+    spent.sort();
+    assert_eq!(spent, [39, 55, 250, 7500]);
+    assert_eq!(tx.outputs.len(), 1);
+    assert_eq!(tx.outputs[0].value, 7644);
+
+    // the largest notes are picked directly among many
+    let (inputs, openings) = node::notes_and_openings(&seed, 1..=300);
+    let mut args = json!({
+        "gas_limit": 100,
+        "gas_price": 2,
+        "inputs": inputs,
+        "openings": openings,
+        "output": {
+            "note_type": "Obfuscated",
+            "receiver": &keys[0],
+            "value": 1,
+        },
+        "refund": &keys[0],
+        "rng_seed": vec![0xfb; 32],
+        "send_all": true,
+        "sender_index": 0,
+        "seed": seed.to_vec(),
+    });
+
+    let types::ExecuteResponse { tx } =
+        wallet.call("execute", &args).take_contents();
+
+    let tx = rkyv::from_bytes::<tx::UnprovenTransaction>(&tx).unwrap();
+    assert_eq!(tx.outputs[0].value, 297 + 298 + 299 + 300 - 200);
+
+    // the fee is computed from the gas, so it can't be given apart
+    let refund = PublicKey::from_bytes(
+        &bs58::decode(&keys[0])
+            .into_vec()
+            .unwrap()
+            .try_into()
+            .unwrap(),
+    )
+    .unwrap();
+    let fee = Fee::new(&mut StdRng::seed_from_u64(0xbeef), 100, 2, &refund);
+    args["fee"] = json!(rkyv::to_bytes::<_, MAX_LEN>(&fee).unwrap().to_vec());
+
+    assert!(!wallet.call("execute", &args).status);
+}
+
+#[test]
+fn execute_template_works() {
+    let seed = [0xfa; RNG_SEED];
+    let values = [10, 250, 15, 39, 55, 7500];
+
+    let mut wallet = Wallet::default();
+
+    let types::PublicKeysResponse { keys } = wallet
+        .call(
+            "public_keys",
+            json!({
+                "seed": seed.to_vec(),
+            }),
+        )
+        .take_contents();
+
+    let (inputs, openings) = node::notes_in_tree(&seed, values);
+    let vk = dusk_wallet_core::key::derive_vk(&seed, 0);
+
+    // the template is built with the view key only, and signed apart
+
+    let types::ExecuteTemplateResponse { template } = wallet
+        .call(
+            "execute_template",
+            json!({
+                "gas_limit": 100,
+                "gas_price": 2,
+                "inputs": inputs,
+                "openings": openings,
+                "output": {
+                    "note_type": "Obfuscated",
+                    "receiver": &keys[0],
+                    "value": 400,
+                },
+                "refund": &keys[0],
+                "rng_seed": vec![0xfb; 32],
+                "view_key": rkyv::to_bytes::<_, MAX_LEN>(&vk).unwrap().to_vec(),
+            }),
+        )
+        .take_contents();
+
+    let types::SignTemplateResponse { tx, preview } = wallet
+        .call(
+            "sign_template",
+            json!({
+                "rng_seed": vec![0xfc; 32],
+                "sender_index": 0,
+                "seed": seed.to_vec(),
+                "template": template,
+            }),
+        )
+        .take_contents();
+
+    let tx = rkyv::from_bytes::<tx::UnprovenTransaction>(&tx).unwrap();
+    let spent: u64 = tx.inputs.iter().map(|i| i.value).sum();
+    let sent: u64 = tx.outputs.iter().map(|o| o.value).sum();
+
+    assert_eq!(spent, sent + 200);
+    assert_eq!(tx.outputs.last().map(|o| o.value), Some(400));
+
+    // the summary is shown to the user for confirmation, the output being
+    // sent to the sender itself
+    assert_eq!(preview.total_input, spent);
+    assert_eq!(preview.fee, 200);
+    assert_eq!(preview.outputs.last().map(|o| o.value), Some(400));
+    assert!(preview.outputs.iter().all(|o| o.change));
+
+    // a template tampered with by the online host is rejected, even if it
+    // is encoded properly
+    let mut tampered = Template::from_transport_bytes(&template).unwrap();
+    tampered.fee.gas_price = 1;
+    let tampered = tampered.to_transport_bytes().unwrap();
+    let result = wallet.call(
+        "sign_template",
+        json!({
+            "rng_seed": vec![0xfc; 32],
+            "sender_index": 0,
+            "seed": seed.to_vec(),
+            "template": tampered,
+        }),
+    );
+    assert!(!result.status);
+}
+
+#[test]
+fn estimate_fee_works() {
+    let seed = [0xfa; RNG_SEED];
+    let values = [10, 250, 15, 39, 55, 7500];
+
+    let mut wallet = Wallet::default();
+
+    let (inputs, _) = node::notes_and_openings(&seed, values);
+    let vk = dusk_wallet_core::key::derive_vk(&seed, 0);
+
+    let args = json!({
+        "coin_selection": "LargestFirst",
+        "gas_schedule": {
+            "call": 0,
+            "input": 5,
+            "output": 2,
+            "payload_byte": 0,
+            "stake": 0,
+            "tx": 20,
+            "withdraw": 0,
+        },
+        "inputs": inputs,
+        "operation": "Transfer",
+        "value": 100,
+        "view_key": rkyv::to_bytes::<_, MAX_LEN>(&vk).unwrap().to_vec(),
+    });
+
+    // the fee is estimated from the view key and the given schedule only
+    let types::EstimateFeeResponse {
+        fee,
+        gas_limit,
+        input_count,
+        output_count,
+        ..
+    } = wallet.call("estimate_fee", args.clone()).take_contents();
+
+    assert_eq!((input_count, output_count), (1, 2));
+    assert_eq!(gas_limit, 20 + 5 + 2 * 2);
+    assert_eq!(fee, gas_limit);
+
+    // the random selection can't do without a seed for its entropy
+    let mut random = args.clone();
+    random["coin_selection"] = json!("Random");
+    assert!(!wallet.call("estimate_fee", random.clone()).status);
+
+    random["rng_seed"] = json!(vec![0xfb; 32]);
+    assert!(wallet.call("estimate_fee", random).status);
+}
+
+#[test]
+fn decode_tx_works() {
+    use dusk_bls12_381::BlsScalar;
+    use phoenix_core::{Fee, Transaction};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let seed = [0xfa; RNG_SEED];
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    let tx = Transaction {
+        anchor: BlsScalar::from(7),
+        nullifiers: vec![BlsScalar::from(1), BlsScalar::from(2)],
+        outputs: node::raw_notes(&seed, [10, 20]),
+        fee: Fee::new(rng, 100, 2, &dusk_wallet_core::key::derive_pk(&seed, 0)),
+        crossover: None,
+        proof: vec![0xab; 100],
+        call: Some(([0xfa; 32], String::from("commit"), vec![1, 2, 3])),
+    };
+
+    let mut wallet = Wallet::default();
+
+    let response: types::DecodeTxResponse = wallet
+        .call("decode_tx", json!({ "bytes": tx.to_var_bytes() }))
+        .take_contents();
+
+    let hash = rusk_abi::hash::Hasher::digest(tx.to_hash_input_bytes());
+    assert_eq!(response.hash, hex::encode(hash.to_bytes()));
+    assert_eq!(
+        response.nullifiers[1],
+        hex::encode(BlsScalar::from(2).to_bytes())
+    );
+    assert_eq!((response.gas_limit, response.gas_price), (100, 2));
+    assert_eq!(response.method.as_deref(), Some("commit"));
+    assert_eq!(response.payload_len, Some(3));
+    assert_eq!(response.proof_len, 100);
+    assert_eq!(response.crossover, None);
+
+    for (output, note) in response.outputs.iter().zip(tx.outputs()) {
+        assert_eq!(output.hash, hex::encode(note.hash().to_bytes()));
+        assert_eq!(output.value, note.value(None).ok());
+    }
+
+    let result = wallet.call("decode_tx", json!({ "bytes": [1, 2, 3] }));
+    assert!(!result.status);
+}
+
+#[test]
+fn get_history_works() {
+    use dusk_bls12_381::BlsScalar;
+    use phoenix_core::{Note, Transaction};
+    use types::{OutputType, TransactionDirectionType};
+
+    let seed = [0xfa; RNG_SEED];
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    let sk = dusk_wallet_core::key::derive_sk(&seed, 0);
+    let pk = PublicKey::from(&sk);
+
+    let received = Note::transparent(rng, &pk, 100);
+    let change = Note::transparent(rng, &pk, 60);
+    let transparent = Note::transparent(rng, &pk, 10);
+    let obfuscated = Note::obfuscated(rng, &pk, 20, JubJubScalar::from(42u64));
+
+    // the first two flows are transparent only, while the last one spends a
+    // transparent note into both a transparent and an obfuscated one
+    let flows = [
+        (1, vec![], vec![received]),
+        (2, vec![received], vec![change]),
+        (3, vec![change], vec![transparent, obfuscated]),
+    ];
+
+    let mut notes = Vec::new();
+    let mut tx_data = Vec::new();
+
+    for (block_height, inputs, outputs) in flows {
+        let tx = Transaction {
+            anchor: BlsScalar::from(7),
+            nullifiers: inputs
+                .iter()
+                .map(|note: &Note| note.gen_nullifier(&sk))
+                .collect(),
+            outputs: outputs.clone(),
+            fee: Fee::new(rng, 100, 1, &pk),
+            crossover: None,
+            proof: vec![],
+            call: None,
+        };
+
+        for note in outputs {
+            notes.push(json!({
+                "pos": 0,
+                "pk": "",
+                "note": rkyv::to_bytes::<_, MAX_LEN>(&note).unwrap().to_vec(),
+                "nullifier": rkyv::to_bytes::<_, MAX_LEN>(
+                    &note.gen_nullifier(&sk)
+                )
+                .unwrap()
+                .to_vec(),
+                "block_height": block_height,
+            }));
+        }
+
+        tx_data.push(json!({
+            "block_height": block_height,
+            "txs": [{
+                "raw_tx": hex::encode(tx.to_var_bytes()),
+                "gas_spent": 100,
+            }],
+        }));
+    }
+
+    let mut wallet = Wallet::default();
+
+    let types::GetHistoryResponse { history } = wallet
+        .call(
+            "get_history",
+            json!({
+                "seed": seed.to_vec(),
+                "index": 0,
+                "notes": notes,
+                "tx_data": tx_data,
+            }),
+        )
+        .take_contents();
+
+    let history: Vec<_> = history
+        .into_iter()
+        .map(|th| (th.block_height, th.direction, th.note_type))
+        .collect();
+
+    assert_eq!(
+        history,
+        [
+            (1, TransactionDirectionType::In, OutputType::Transparent),
+            (2, TransactionDirectionType::Out, OutputType::Transparent),
+            (3, TransactionDirectionType::Out, OutputType::Obfuscated),
+        ]
+    );
+}
+
+#[test]
+fn ref_id_works() {
+    use dusk_bls12_381::BlsScalar;
+    use phoenix_core::{transaction::TreeLeaf, Note, Transaction};
+
+    let seed = [0xfa; RNG_SEED];
+    let rng = &mut StdRng::seed_from_u64(0xbeef);
+
+    let pk = dusk_wallet_core::key::derive_pk(&seed, 0);
+    let receiver = bs58::encode(pk.to_bytes()).into_string();
+
+    let output = |rng: &mut StdRng, ref_id| {
+        let output = types::ExecuteOutput {
+            note_type: types::OutputType::Obfuscated,
+            receiver: receiver.clone(),
+            ref_id,
+            value: 100,
+        };
+        tx::Output::new(rng, output).unwrap().note
+    };
+
+    let notes: Vec<Note> = vec![output(rng, Some(42)), output(rng, None)];
+
+    let leaves: Vec<u8> = notes
+        .iter()
+        .zip(0..)
+        .flat_map(|(note, pos)| {
+            let mut note = *note;
+            note.set_pos(pos);
+
+            let leaf = TreeLeaf {
+                block_height: 1,
+                note,
+            };
+            rkyv::to_bytes::<_, MAX_LEN>(&leaf).unwrap().into_vec()
+        })
+        .collect();
+
+    let mut wallet = Wallet::default();
+
+    let types::CheckNoteOwnershipResponse {
+        notes: raw_notes,
+        nullifiers,
+        ref_ids,
+        ..
+    } = wallet
+        .call_raw("check_note_ownership", &[&seed[..], &leaves].concat())
+        .take_contents();
+
+    assert_eq!(ref_ids, [Some(42), None]);
+
+    let tx = Transaction {
+        anchor: BlsScalar::from(7),
+        nullifiers: vec![],
+        outputs: notes,
+        fee: Fee::new(rng, 100, 1, &pk),
+        crossover: None,
+        proof: vec![],
+        call: None,
+    };
+
+    let notes: Vec<_> = raw_notes
+        .into_iter()
+        .zip(nullifiers)
+        .map(|(note, nullifier)| {
+            json!({
+                "pos": 0,
+                "pk": "",
+                "note": note,
+                "nullifier": nullifier,
+                "block_height": 1,
+            })
+        })
+        .collect();
+
+    let types::GetHistoryResponse { history } = wallet
+        .call(
+            "get_history",
+            json!({
+                "seed": seed.to_vec(),
+                "index": 0,
+                "notes": notes,
+                "tx_data": [{
+                    "block_height": 1,
+                    "txs": [{
+                        "raw_tx": hex::encode(tx.to_var_bytes()),
+                        "gas_spent": 100,
+                    }],
+                }],
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].ref_id, Some(42));
 }
 
 #[test]
@@ -201,6 +1153,112 @@ fn public_keys_works() {
     }
 
     assert_eq!(keys.len(), MAX_KEY);
+
+    let types::PublicKeysResponse { keys } = wallet
+        .call(
+            "public_keys",
+            json!({
+                "account_count": 3,
+                "seed": seed.to_vec(),
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(keys.len(), 3);
+}
+
+#[test]
+fn validate_address_works() {
+    let seed = [0xfa; RNG_SEED];
+
+    let mut wallet = Wallet::default();
+
+    let types::PublicKeysResponse { keys } = wallet
+        .call(
+            "public_keys",
+            json!({
+                "checksum": true,
+                "seed": seed.to_vec(),
+            }),
+        )
+        .take_contents();
+
+    let types::ValidateAddressResponse {
+        valid, checksummed, ..
+    } = wallet
+        .call("validate_address", json!({ "address": keys[0] }))
+        .take_contents();
+
+    assert!(valid);
+    assert!(checksummed);
+
+    // `l` is not in the alphabet
+    let mut typo = keys[0].clone();
+    typo.replace_range(3..4, "l");
+    let types::ValidateAddressResponse {
+        valid,
+        reason,
+        position,
+        ..
+    } = wallet
+        .call("validate_address", json!({ "address": typo }))
+        .take_contents();
+
+    assert!(!valid);
+    assert_eq!(reason.as_deref(), Some("alphabet"));
+    assert_eq!(position, Some(3));
+}
+
+#[test]
+fn payment_uri_works() {
+    let seed = [0xfa; RNG_SEED];
+
+    let mut wallet = Wallet::default();
+
+    let types::PublicKeysResponse { keys } = wallet
+        .call("public_keys", json!({ "seed": seed.to_vec() }))
+        .take_contents();
+
+    let types::PaymentUriResponse { uri } = wallet
+        .call(
+            "payment_uri",
+            json!({
+                "receiver": keys[0],
+                "value": 1_500_000_000u64,
+                "ref_id": 42,
+                "expiry": 1_700_000_000u64,
+            }),
+        )
+        .take_contents();
+
+    assert_eq!(
+        uri,
+        format!("dusk:{}?amount=1.5&ref=42&expiry=1700000000", keys[0])
+    );
+
+    let types::PaymentOutputResponse { expiry, output } = wallet
+        .call(
+            "payment_output",
+            json!({ "uri": uri, "now": 1_600_000_000u64 }),
+        )
+        .take_contents();
+
+    assert_eq!(expiry, Some(1_700_000_000));
+    assert_eq!(
+        output,
+        types::ExecuteOutput {
+            note_type: types::OutputType::Obfuscated,
+            receiver: keys[0].clone(),
+            ref_id: Some(42),
+            value: 1_500_000_000,
+        }
+    );
+
+    let result = wallet.call(
+        "payment_output",
+        json!({ "uri": uri, "now": 1_700_000_000u64 }),
+    );
+    assert!(!result.status);
 }
 
 #[test]
@@ -255,11 +1313,17 @@ mod node {
     use core::mem;
 
     use dusk_jubjub::{BlsScalar, JubJubScalar};
-    use dusk_wallet_core::{key, tx, MAX_KEY, MAX_LEN, RNG_SEED};
+    use dusk_wallet_core::{
+        key,
+        tx::{self, POSEIDON_TREE_ARITY},
+        MAX_KEY, MAX_LEN, RNG_SEED,
+    };
     use ff::Field;
-    use phoenix_core::{Note, PublicKey};
+    use phoenix_core::{transaction::TreeLeaf, Note, PublicKey};
+    use poseidon_merkle::{Item, Tree};
     use rand::{rngs::StdRng, RngCore};
     use rand_core::SeedableRng;
+    use rusk_abi::POSEIDON_TREE_DEPTH;
 
     pub fn raw_notes<Values>(seed: &[u8; RNG_SEED], values: Values) -> Vec<Note>
     where
@@ -324,6 +1388,78 @@ mod node {
         (notes, openings)
     }
 
+    /// Serializes obfuscated notes of the given values, inserted at
+    /// consecutive positions of a tree, along with their openings to it.
+    pub fn notes_in_tree<Values>(
+        seed: &[u8; RNG_SEED],
+        values: Values,
+    ) -> (Vec<u8>, Vec<u8>)
+    where
+        Values: IntoIterator<Item = u64>,
+    {
+        let rng = &mut StdRng::from_entropy();
+        let pk = key::derive_pk(seed, 0);
+
+        let mut tree =
+            Tree::<(), POSEIDON_TREE_DEPTH, POSEIDON_TREE_ARITY>::new();
+        let notes: Vec<Note> = values
+            .into_iter()
+            .zip(0..)
+            .map(|(value, pos)| {
+                let blinder = JubJubScalar::random(&mut *rng);
+                let mut note = Note::obfuscated(rng, &pk, value, blinder);
+                note.set_pos(pos);
+                tree.insert(pos, Item::new(note.hash(), ()));
+                note
+            })
+            .collect();
+
+        let openings: Vec<(tx::Opening, u64)> = notes
+            .iter()
+            .map(|note| (tree.opening(*note.pos()).unwrap(), *note.pos()))
+            .collect();
+
+        (
+            rkyv::to_bytes::<_, MAX_LEN>(&notes)
+                .expect("failed to serialize notes")
+                .into_vec(),
+            rkyv::to_bytes::<_, MAX_LEN>(&openings)
+                .expect("failed to serialize openings")
+                .into_vec(),
+        )
+    }
+
+    /// Serializes the tree leaves of transparent notes of the given values,
+    /// each owned by the account of the given index, at consecutive
+    /// positions and block heights starting from `pos`.
+    pub fn leaves<Values>(
+        seed: &[u8; RNG_SEED],
+        pos: u64,
+        values: Values,
+    ) -> Vec<u8>
+    where
+        Values: IntoIterator<Item = (u64, u64)>,
+    {
+        let rng = &mut StdRng::from_entropy();
+        values
+            .into_iter()
+            .zip(pos..)
+            .flat_map(|((index, value), pos)| {
+                let pk = key::derive_pk(seed, index);
+                let mut note = Note::transparent(rng, &pk, value);
+                note.set_pos(pos);
+
+                let leaf = TreeLeaf {
+                    block_height: pos + 1,
+                    note,
+                };
+                rkyv::to_bytes::<_, MAX_LEN>(&leaf)
+                    .expect("failed to serialize leaf")
+                    .into_vec()
+            })
+            .collect()
+    }
+
     pub fn raw_notes_and_nulifiers<Values>(
         seed: &[u8; RNG_SEED],
         values: Values,
@@ -421,12 +1557,16 @@ impl<'a> CallResult<'a> {
 }
 
 impl Wallet {
-    pub fn call<T>(&mut self, f: &str, args: T) -> CallResult
+    pub fn call<T>(&mut self, f: &str, args: T) -> CallResult<'_>
     where
         T: Serialize,
     {
         let bytes = serde_json::to_string(&args).unwrap();
 
+        self.call_raw(f, bytes.as_bytes())
+    }
+
+    pub fn call_raw(&mut self, f: &str, bytes: &[u8]) -> CallResult<'_> {
         let len_params = [Val::I32(bytes.len() as i32)];
         let mut ptr_results = [Val::I32(0)];
 
@@ -442,11 +1582,7 @@ impl Wallet {
         self.instance
             .get_memory(&mut self.store, "memory")
             .expect("There should be one memory")
-            .write(
-                &mut self.store,
-                ptr_results[0].unwrap_i32() as usize,
-                bytes.as_bytes(),
-            )
+            .write(&mut self.store, ptr_results[0].unwrap_i32() as usize, bytes)
             .expect("Writing to memory should succeed");
 
         let params = [ptr_results[0].clone(), len_params[0].clone()];